@@ -1,4 +1,14 @@
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PortScanMode {
+    #[default]
+    Connect,
+    /// Half-open scan over raw sockets, falls back to `Connect` when the
+    /// process is not allowed to open them.
+    Syn,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScannerConfig {
@@ -11,6 +21,40 @@ pub struct ScannerConfig {
     pub port_timeout_ms: u64,
     pub min_port_timeout_ms: u64,
     pub adapt_port_timeout: bool,
+    pub max_port_concurrency: usize,
+    pub max_sockets: usize,
+    pub port_scan_mode: PortScanMode,
+    pub banner_timeout_ms: u64,
+    pub banner_max_bytes: usize,
+    /// Also grab banners from the raw printing ports 9100-9107, where
+    /// printers print whatever they are sent.
+    pub banner_print_ports: bool,
+    pub http_timeout_ms: u64,
+    /// Hosts without NetBIOS never answer, so this stays short.
+    pub netbios_timeout_ms: u64,
+    /// Connect and handshake timeout of the fetchers that probe services.
+    pub service_timeout_ms: u64,
+    /// Servers for reverse lookups; empty means those of the system.
+    pub dns_servers: Vec<SocketAddr>,
+    pub dns_timeout_ms: u64,
+    pub dns_retries: u32,
+    /// Only keep a PTR name that resolves back to the scanned address.
+    pub dns_confirm_forward: bool,
+    /// Community strings the SNMP fetcher tries, in order.
+    pub snmp_communities: Vec<String>,
+    /// Also check for SMBv1, which takes one more connection per host.
+    pub smb1_probe: bool,
+    /// Try an anonymous FTP login, which shows up in the server's logs.
+    pub ftp_anonymous_check: bool,
+    /// Limit the industrial fetcher to read-only identification requests;
+    /// MQTT logins and the longer Modbus reads need this turned off.
+    pub industrial_passive_only: bool,
+    /// Zone the DNS server fetcher asks each server to transfer.
+    pub dns_axfr_zone: Option<String>,
+    /// Probe database that extends the service fingerprint fetcher's own.
+    pub service_probes_file: Option<String>,
+    /// Rules file that extends the web technology fetcher's own.
+    pub web_tech_rules_file: Option<String>,
 }
 
 impl Default for ScannerConfig {
@@ -25,6 +69,26 @@ impl Default for ScannerConfig {
             port_timeout_ms: 500,
             min_port_timeout_ms: 100,
             adapt_port_timeout: true,
+            max_port_concurrency: 50,
+            max_sockets: 500,
+            port_scan_mode: PortScanMode::Connect,
+            banner_timeout_ms: 2000,
+            banner_max_bytes: 256,
+            banner_print_ports: false,
+            http_timeout_ms: 3000,
+            netbios_timeout_ms: 500,
+            service_timeout_ms: 3000,
+            dns_servers: Vec::new(),
+            dns_timeout_ms: 1000,
+            dns_retries: 1,
+            dns_confirm_forward: false,
+            snmp_communities: vec!["public".to_string()],
+            smb1_probe: false,
+            ftp_anonymous_check: false,
+            industrial_passive_only: true,
+            dns_axfr_zone: None,
+            service_probes_file: None,
+            web_tech_rules_file: None,
         }
     }
 }
@@ -1,5 +1,11 @@
 use std::str::FromStr;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
 pub struct PortIterator {
     ports: Vec<u16>,
     current: usize,
@@ -7,12 +13,28 @@ pub struct PortIterator {
 
 impl PortIterator {
     pub fn new(port_string: &str) -> Result<Self, String> {
+        Self::for_protocol(port_string, Protocol::Tcp)
+    }
+
+    /// Parses the ports of one protocol. A `T:` or `U:` prefix switches the
+    /// protocol for that entry and the ones after it, so `T:22,80,U:53,161`
+    /// is TCP 22 and 80 plus UDP 53 and 161. Unprefixed entries are TCP.
+    pub fn for_protocol(port_string: &str, protocol: Protocol) -> Result<Self, String> {
         const MAX_PORTS: usize = 65535;
 
         let mut ports = Vec::new();
+        let mut current_protocol = Protocol::Tcp;
 
         for part in port_string.split(',') {
-            let part = part.trim();
+            let mut part = part.trim();
+            if let Some(rest) = part.strip_prefix("T:").or_else(|| part.strip_prefix("t:")) {
+                current_protocol = Protocol::Tcp;
+                part = rest.trim();
+            } else if let Some(rest) = part.strip_prefix("U:").or_else(|| part.strip_prefix("u:")) {
+                current_protocol = Protocol::Udp;
+                part = rest.trim();
+            }
+
             if part.contains('-') {
                 let range: Vec<&str> = part.split('-').collect();
                 if range.len() != 2 {
@@ -28,6 +50,10 @@ impl PortIterator {
                     return Err(format!("Invalid range: {} > {}", start, end));
                 }
 
+                if current_protocol != protocol {
+                    continue;
+                }
+
                 let range_size = (end - start + 1) as usize;
                 if ports.len() + range_size > MAX_PORTS {
                     return Err(format!("Too many ports specified (max: {})", MAX_PORTS));
@@ -39,7 +65,9 @@ impl PortIterator {
             } else {
                 let port =
                     u16::from_str(part).map_err(|_| format!("Invalid port number: {}", part))?;
-                ports.push(port);
+                if current_protocol == protocol {
+                    ports.push(port);
+                }
             }
         }
 
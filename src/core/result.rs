@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::net::IpAddr;
 
+use super::subject::Parameters;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ResultType {
     Unknown,
@@ -12,6 +14,7 @@ pub enum ResultType {
 pub struct ScanningResult {
     address: IpAddr,
     values: HashMap<String, String>,
+    parameters: Parameters,
     result_type: ResultType,
     mac: Option<String>,
 }
@@ -21,6 +24,7 @@ impl ScanningResult {
         Self {
             address,
             values: HashMap::new(),
+            parameters: HashMap::new(),
             result_type: ResultType::Unknown,
             mac: None,
         }
@@ -38,6 +42,14 @@ impl ScanningResult {
         self.values.get(key)
     }
 
+    pub fn set_parameters(&mut self, parameters: Parameters) {
+        self.parameters = parameters;
+    }
+
+    pub fn get_parameter<T: 'static>(&self, key: &str) -> Option<&T> {
+        self.parameters.get(key)?.downcast_ref()
+    }
+
     pub fn set_type(&mut self, result_type: ResultType) {
         self.result_type = result_type;
     }
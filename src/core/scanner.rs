@@ -60,6 +60,7 @@ impl Scanner {
                 }
 
                 result.set_type(subject.result_type());
+                result.set_parameters(subject.into_parameters());
                 result
             });
         }
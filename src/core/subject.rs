@@ -5,10 +5,12 @@ use std::sync::Arc;
 use super::result::ResultType;
 use crate::config::ScannerConfig;
 
+pub type Parameters = HashMap<String, Box<dyn std::any::Any + Send + Sync>>;
+
 pub struct ScanningSubject {
     address: IpAddr,
     config: Arc<ScannerConfig>,
-    parameters: HashMap<String, Box<dyn std::any::Any + Send + Sync>>,
+    parameters: Parameters,
     result_type: ResultType,
     aborted: bool,
     adapted_port_timeout: Option<u64>,
@@ -42,6 +44,10 @@ impl ScanningSubject {
         self.parameters.get(key)?.downcast_ref()
     }
 
+    pub fn into_parameters(self) -> Parameters {
+        self.parameters
+    }
+
     pub fn set_result_type(&mut self, result_type: ResultType) {
         self.result_type = result_type;
     }
@@ -0,0 +1,338 @@
+// Just enough of the DNS wire format for the name lookups and DNS probes.
+
+use serde::Serialize;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::timeout;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_NS: u16 = 2;
+pub const TYPE_CNAME: u16 = 5;
+pub const TYPE_SOA: u16 = 6;
+pub const TYPE_PTR: u16 = 12;
+pub const TYPE_TXT: u16 = 16;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_SRV: u16 = 33;
+pub const TYPE_AXFR: u16 = 252;
+
+pub const CLASS_IN: u16 = 1;
+pub const CLASS_CH: u16 = 3;
+
+pub const RCODE_NOERROR: u8 = 0;
+pub const RCODE_NXDOMAIN: u8 = 3;
+pub const RCODE_REFUSED: u8 = 5;
+
+const FLAG_RESPONSE: u16 = 0x8000;
+const FLAG_AUTHORITATIVE: u16 = 0x0400;
+const FLAG_TRUNCATED: u16 = 0x0200;
+const FLAG_RECURSION_DESIRED: u16 = 0x0100;
+const FLAG_RECURSION_AVAILABLE: u16 = 0x0080;
+// mDNS reuses the top bit of the class for cache flushing.
+const CLASS_MASK: u16 = 0x7fff;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum RecordData {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    /// PTR, CNAME and NS all hold a single name.
+    Name(String),
+    Txt(Vec<String>),
+    Srv {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: String,
+    },
+    Other(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Record {
+    pub name: String,
+    pub record_type: u16,
+    pub class: u16,
+    pub ttl: u32,
+    pub data: RecordData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Question {
+    pub name: String,
+    pub record_type: u16,
+    pub class: u16,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Message {
+    pub id: u16,
+    pub flags: u16,
+    pub questions: Vec<Question>,
+    pub answers: Vec<Record>,
+    pub authorities: Vec<Record>,
+    pub additionals: Vec<Record>,
+}
+
+impl Message {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let header = |i: usize| u16::from_be_bytes([bytes[i], bytes[i + 1]]);
+        if bytes.len() < 12 {
+            return None;
+        }
+        let (questions, answers, authorities, additionals) =
+            (header(4), header(6), header(8), header(10));
+
+        let mut pos = 12;
+        let mut question_list = Vec::new();
+        for _ in 0..questions {
+            let name = read_name(bytes, &mut pos)?;
+            let fixed = bytes.get(pos..pos + 4)?;
+            question_list.push(Question {
+                name,
+                record_type: u16::from_be_bytes([fixed[0], fixed[1]]),
+                class: u16::from_be_bytes([fixed[2], fixed[3]]) & CLASS_MASK,
+            });
+            pos += 4;
+        }
+
+        let mut records = |count: u16| -> Option<Vec<Record>> {
+            (0..count).map(|_| read_record(bytes, &mut pos)).collect()
+        };
+        Some(Self {
+            id: header(0),
+            flags: header(2),
+            questions: question_list,
+            answers: records(answers)?,
+            authorities: records(authorities)?,
+            additionals: records(additionals)?,
+        })
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.flags & FLAG_RESPONSE != 0
+    }
+
+    pub fn is_authoritative(&self) -> bool {
+        self.flags & FLAG_AUTHORITATIVE != 0
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.flags & FLAG_TRUNCATED != 0
+    }
+
+    pub fn recursion_available(&self) -> bool {
+        self.flags & FLAG_RECURSION_AVAILABLE != 0
+    }
+
+    pub fn rcode(&self) -> u8 {
+        (self.flags & 0x000f) as u8
+    }
+
+    /// Names held by the answers of the given type, in answer order.
+    pub fn answer_names(&self, record_type: u16) -> Vec<String> {
+        self.answers
+            .iter()
+            .filter(|r| r.record_type == record_type)
+            .filter_map(|r| match &r.data {
+                RecordData::Name(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Builds a query with a single question.
+pub fn query(id: u16, name: &str, record_type: u16, class: u16, recursion: bool) -> Vec<u8> {
+    let flags = if recursion { FLAG_RECURSION_DESIRED } else { 0 };
+    let mut message = Vec::with_capacity(18 + name.len());
+    message.extend_from_slice(&id.to_be_bytes());
+    message.extend_from_slice(&flags.to_be_bytes());
+    message.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    write_name(&mut message, name);
+    message.extend_from_slice(&record_type.to_be_bytes());
+    message.extend_from_slice(&class.to_be_bytes());
+    message
+}
+
+/// `4.3.2.1.in-addr.arpa` style name for PTR lookups.
+pub fn reverse_name(address: IpAddr) -> String {
+    match address {
+        IpAddr::V4(v4) => {
+            let [a, b, c, d] = v4.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+        }
+        IpAddr::V6(v6) => {
+            let mut name = String::with_capacity(72);
+            for byte in v6.octets().iter().rev() {
+                name.push_str(&format!("{:x}.{:x}.", byte & 0x0f, byte >> 4));
+            }
+            name.push_str("ip6.arpa");
+            name
+        }
+    }
+}
+
+/// Query id that differs between calls, so stale answers are told apart.
+pub fn next_id() -> u16 {
+    use std::sync::atomic::{AtomicU16, Ordering};
+    static NEXT_ID: AtomicU16 = AtomicU16::new(0);
+    let seed = std::process::id() as u16;
+    NEXT_ID.fetch_add(1, Ordering::Relaxed).wrapping_mul(40503) ^ seed
+}
+
+/// Sends `query` over UDP and returns the first datagram answering it.
+pub async fn exchange(server: SocketAddr, query: &[u8], wait: Duration) -> io::Result<Vec<u8>> {
+    let bind: IpAddr = match server {
+        SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((bind, 0)).await?;
+    socket.connect(server).await?;
+    socket.send(query).await?;
+
+    let receive = async {
+        let mut buffer = vec![0u8; 65535];
+        loop {
+            let len = socket.recv(&mut buffer).await?;
+            // Anything else is late or spoofed.
+            if len >= 2 && buffer[..2] == query[..2] {
+                buffer.truncate(len);
+                return Ok(buffer);
+            }
+        }
+    };
+    timeout(wait, receive)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "DNS query timed out"))?
+}
+
+/// Writes `message` with the two byte length prefix DNS uses over TCP.
+pub async fn write_tcp_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &[u8],
+) -> io::Result<()> {
+    let mut framed = (message.len() as u16).to_be_bytes().to_vec();
+    framed.extend_from_slice(message);
+    writer.write_all(&framed).await
+}
+
+/// Reads one length-prefixed message from a TCP stream.
+pub async fn read_tcp_message<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 2];
+    reader.read_exact(&mut len).await?;
+    let mut message = vec![0u8; u16::from_be_bytes(len) as usize];
+    reader.read_exact(&mut message).await?;
+    Ok(message)
+}
+
+/// Sends `query` over TCP and returns the first message answering it.
+pub async fn exchange_tcp(server: SocketAddr, query: &[u8], wait: Duration) -> io::Result<Vec<u8>> {
+    let exchange = async {
+        let mut stream = TcpStream::connect(server).await?;
+        write_tcp_message(&mut stream, query).await?;
+        loop {
+            let message = read_tcp_message(&mut stream).await?;
+            if message.len() >= 2 && message[..2] == query[..2] {
+                return Ok(message);
+            }
+        }
+    };
+    timeout(wait, exchange)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "DNS query timed out"))?
+}
+
+fn write_name(buffer: &mut Vec<u8>, name: &str) {
+    for label in name
+        .trim_end_matches('.')
+        .split('.')
+        .filter(|l| !l.is_empty())
+    {
+        let label = &label.as_bytes()[..label.len().min(63)];
+        buffer.push(label.len() as u8);
+        buffer.extend_from_slice(label);
+    }
+    buffer.push(0);
+}
+
+fn read_name(bytes: &[u8], pos: &mut usize) -> Option<String> {
+    let mut labels = Vec::new();
+    let mut cursor = *pos;
+    let mut jumped = false;
+
+    // Bounded so that pointer loops cannot hang us.
+    for _ in 0..128 {
+        let len = *bytes.get(cursor)? as usize;
+        if len & 0xc0 == 0xc0 {
+            let target = ((len & 0x3f) << 8) | *bytes.get(cursor + 1)? as usize;
+            if !jumped {
+                *pos = cursor + 2;
+                jumped = true;
+            }
+            cursor = target;
+        } else if len == 0 {
+            if !jumped {
+                *pos = cursor + 1;
+            }
+            return Some(labels.join("."));
+        } else {
+            let label = bytes.get(cursor + 1..cursor + 1 + len)?;
+            labels.push(String::from_utf8_lossy(label).into_owned());
+            cursor += 1 + len;
+        }
+    }
+    None
+}
+
+fn read_record(bytes: &[u8], pos: &mut usize) -> Option<Record> {
+    let name = read_name(bytes, pos)?;
+    let fixed = bytes.get(*pos..*pos + 10)?;
+    let record_type = u16::from_be_bytes([fixed[0], fixed[1]]);
+    let class = u16::from_be_bytes([fixed[2], fixed[3]]) & CLASS_MASK;
+    let ttl = u32::from_be_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]);
+    let len = u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+    let start = *pos + 10;
+    let rdata = bytes.get(start..start + len)?;
+    *pos = start + len;
+
+    let data = match record_type {
+        TYPE_A if len == 4 => RecordData::A(Ipv4Addr::from(<[u8; 4]>::try_from(rdata).ok()?)),
+        TYPE_AAAA if len == 16 => {
+            RecordData::Aaaa(Ipv6Addr::from(<[u8; 16]>::try_from(rdata).ok()?))
+        }
+        TYPE_PTR | TYPE_CNAME | TYPE_NS => {
+            let mut name_pos = start;
+            RecordData::Name(read_name(bytes, &mut name_pos)?)
+        }
+        TYPE_TXT => {
+            let mut strings = Vec::new();
+            let mut rest = rdata;
+            while let Some((&len, tail)) = rest.split_first() {
+                let text = tail.get(..len as usize)?;
+                strings.push(String::from_utf8_lossy(text).into_owned());
+                rest = &tail[len as usize..];
+            }
+            RecordData::Txt(strings)
+        }
+        TYPE_SRV if len >= 7 => {
+            let mut target_pos = start + 6;
+            RecordData::Srv {
+                priority: u16::from_be_bytes([rdata[0], rdata[1]]),
+                weight: u16::from_be_bytes([rdata[2], rdata[3]]),
+                port: u16::from_be_bytes([rdata[4], rdata[5]]),
+                target: read_name(bytes, &mut target_pos)?,
+            }
+        }
+        _ => RecordData::Other(rdata.to_vec()),
+    };
+
+    Some(Record {
+        name,
+        record_type,
+        class,
+        ttl,
+        data,
+    })
+}
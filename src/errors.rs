@@ -17,6 +17,12 @@ pub enum ScanError {
     #[error("DNS resolution failed: {0}")]
     DnsResolutionFailed(String),
 
+    #[error("Invalid probe database: {0}")]
+    ProbeDatabase(String),
+
+    #[error("Invalid web technology rules: {0}")]
+    WebTechRules(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
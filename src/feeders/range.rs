@@ -67,7 +67,7 @@ impl Feeder for RangeFeeder {
             (IpAddr::V6(start), IpAddr::V6(end)) => {
                 let start_num = u128::from_be_bytes(start.octets());
                 let end_num = u128::from_be_bytes(end.octets());
-                ((end_num - start_num + 1) as usize).min(usize::MAX)
+                usize::try_from(end_num - start_num + 1).unwrap_or(usize::MAX)
             }
             _ => 0,
         }
@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::ports;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+use crate::network_utils;
+
+pub const BANNERS: &str = "banners";
+
+/// JetDirect and other raw printing ports, which print anything sent to
+/// them, the CRLF of a banner grab included.
+pub const RAW_PRINT_PORTS: RangeInclusive<u16> = 9100..=9107;
+
+pub type Banners = BTreeMap<u16, String>;
+
+pub struct BannerFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+}
+
+impl BannerFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        Self { config, sockets }
+    }
+}
+
+/// Reads what the service on `port` says first. Services that wait for the
+/// client, like HTTP, get a bare CRLF, which most of them answer with an
+/// error page or a prompt.
+pub async fn grab_banner(
+    address: IpAddr,
+    port: u16,
+    max_bytes: usize,
+    wait: Duration,
+) -> Option<Vec<u8>> {
+    let mut stream = timeout(wait, TcpStream::connect((address, port)))
+        .await
+        .ok()?
+        .ok()?;
+    let mut buffer = vec![0u8; max_bytes.max(1)];
+
+    let mut len = network_utils::read_available(&mut stream, &mut buffer, wait).await;
+    if len == 0 {
+        stream.write_all(b"\r\n").await.ok()?;
+        len = network_utils::read_available(&mut stream, &mut buffer, wait).await;
+    }
+
+    buffer.truncate(len);
+    (len > 0).then_some(buffer)
+}
+
+/// Turns raw bytes into a single printable line. Line breaks and tabs become
+/// spaces, other non-printable bytes are escaped as `\xNN`.
+pub fn sanitize_banner(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        match byte {
+            b'\r' => {}
+            b'\n' | b'\t' => text.push(' '),
+            0x20..=0x7e => text.push(byte as char),
+            _ => text.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    text.trim().to_string()
+}
+
+#[async_trait]
+impl Fetcher for BannerFetcher {
+    fn id(&self) -> String {
+        "banner".to_string()
+    }
+
+    fn name(&self) -> String {
+        "Banners".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let open_ports: Vec<u16> = ports::open_ports(subject)
+            .into_iter()
+            .filter(|port| self.config.banner_print_ports || !RAW_PRINT_PORTS.contains(port))
+            .collect();
+        if open_ports.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let max_bytes = self.config.banner_max_bytes;
+        let wait = Duration::from_millis(self.config.banner_timeout_ms);
+        let mut grabs = JoinSet::new();
+        for port in open_ports {
+            let sockets = self.sockets.clone();
+            grabs.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                (port, grab_banner(address, port, max_bytes, wait).await)
+            });
+        }
+
+        let mut banners = Banners::new();
+        while let Some(grab) = grabs.join_next().await {
+            if let Ok((port, Some(bytes))) = grab {
+                let banner = sanitize_banner(&bytes);
+                if !banner.is_empty() {
+                    banners.insert(port, banner);
+                }
+            }
+        }
+
+        if banners.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = banners
+            .iter()
+            .map(|(port, banner)| format!("{}: {}", port, banner))
+            .collect::<Vec<_>>()
+            .join("; ");
+        subject.set_parameter(BANNERS.to_string(), banners);
+        Ok(summary)
+    }
+}
@@ -0,0 +1,374 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::ports;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const SERVICE_CAPABILITIES: &str = "service.capabilities";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TextProtocol {
+    Ftp,
+    Smtp,
+    Pop3,
+    Imap,
+}
+
+impl fmt::Display for TextProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TextProtocol::Ftp => "FTP",
+            TextProtocol::Smtp => "SMTP",
+            TextProtocol::Pop3 => "POP3",
+            TextProtocol::Imap => "IMAP",
+        })
+    }
+}
+
+/// Plain text ports of the services that can upgrade to TLS.
+pub const CAPABILITY_PORTS: [(u16, TextProtocol); 6] = [
+    (21, TextProtocol::Ftp),
+    (25, TextProtocol::Smtp),
+    (110, TextProtocol::Pop3),
+    (143, TextProtocol::Imap),
+    (587, TextProtocol::Smtp),
+    (2525, TextProtocol::Smtp),
+];
+
+// Bounds a reply, as a misbehaving server could stream lines forever.
+const MAX_REPLY_LINES: usize = 200;
+// Longer lines are cut, the rest read as the next line.
+const MAX_LINE_BYTES: u64 = 1024;
+
+pub type Capabilities = BTreeMap<u16, ServiceCapabilities>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ServiceCapabilities {
+    pub protocol: TextProtocol,
+    /// First line of the greeting, without the status code.
+    pub greeting: String,
+    pub capabilities: Vec<String>,
+    pub starttls: bool,
+    /// Credentials may be sent before the connection is encrypted.
+    pub plaintext_auth: bool,
+    /// Whether FTP let `anonymous` in; `None` when not checked.
+    pub anonymous_login: Option<bool>,
+}
+
+impl ServiceCapabilities {
+    fn new(protocol: TextProtocol, greeting: String) -> Self {
+        Self {
+            protocol,
+            greeting,
+            capabilities: Vec::new(),
+            starttls: false,
+            plaintext_auth: false,
+            anonymous_login: None,
+        }
+    }
+}
+
+impl fmt::Display for ServiceCapabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = vec![if self.starttls {
+            "STARTTLS"
+        } else {
+            "no STARTTLS"
+        }];
+        if self.plaintext_auth {
+            flags.push("plaintext auth");
+        }
+        if self.anonymous_login == Some(true) {
+            flags.push("anonymous login");
+        }
+        write!(f, "{} ({})", self.protocol, flags.join(", "))
+    }
+}
+
+type Reader = BufReader<TcpStream>;
+
+async fn read_line(reader: &mut Reader) -> Option<String> {
+    let mut line = Vec::new();
+    match reader
+        .take(MAX_LINE_BYTES)
+        .read_until(b'\n', &mut line)
+        .await
+    {
+        Ok(n) if n > 0 => Some(
+            String::from_utf8_lossy(&line)
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
+
+async fn send(reader: &mut Reader, command: &str) -> Option<()> {
+    reader
+        .get_mut()
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await
+        .ok()
+}
+
+/// Reads an FTP or SMTP reply: `123-` lines up to the final `123 ` one.
+async fn read_reply(reader: &mut Reader) -> Option<(u16, Vec<String>)> {
+    let mut lines = Vec::new();
+    for _ in 0..MAX_REPLY_LINES {
+        let line = read_line(reader).await?;
+        let code = line.get(..3).and_then(|code| code.parse().ok());
+        let last = line.as_bytes().get(3).is_none_or(|&b| b == b' ');
+        lines.push(line);
+        if let Some(code) = code
+            && last
+        {
+            return Some((code, lines));
+        }
+    }
+    None
+}
+
+/// Text of a reply line after the status code or tag.
+fn reply_text(line: &str, prefix_len: usize) -> String {
+    line.get(prefix_len..)
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+fn has_plaintext_mechanism(mechanisms: &str) -> bool {
+    mechanisms
+        .split_whitespace()
+        .any(|m| m.eq_ignore_ascii_case("PLAIN") || m.eq_ignore_ascii_case("LOGIN"))
+}
+
+async fn ftp(reader: &mut Reader, anonymous: bool) -> Option<ServiceCapabilities> {
+    let (220, greeting) = read_reply(reader).await? else {
+        return None;
+    };
+    let mut service = ServiceCapabilities::new(TextProtocol::Ftp, reply_text(&greeting[0], 4));
+
+    send(reader, "FEAT").await?;
+    if let Some((211, lines)) = read_reply(reader).await {
+        // Features are the indented lines between the first and last.
+        service.capabilities = lines
+            .iter()
+            .filter(|line| line.starts_with(' '))
+            .map(|line| line.trim().to_string())
+            .collect();
+    }
+    service.starttls = service
+        .capabilities
+        .iter()
+        .any(|feature| feature.starts_with("AUTH TLS") || feature.starts_with("AUTH SSL"));
+
+    // Without AUTH, credentials can only go in the clear. Whether a server
+    // offering it still takes them without TLS takes a USER to find out,
+    // which is a login attempt, so only the anonymous check tells.
+    service.plaintext_auth = !service.starttls;
+    if anonymous {
+        send(reader, "USER anonymous").await?;
+        let (code, _) = read_reply(reader).await?;
+        service.plaintext_auth |= code == 331 || code == 230;
+        let logged_in = match code {
+            230 => true,
+            331 => {
+                send(reader, "PASS ipscan@").await?;
+                matches!(read_reply(reader).await, Some((230, _)))
+            }
+            _ => false,
+        };
+        service.anonymous_login = Some(logged_in);
+    }
+    let _ = send(reader, "QUIT").await;
+    Some(service)
+}
+
+async fn smtp(reader: &mut Reader) -> Option<ServiceCapabilities> {
+    let (220, greeting) = read_reply(reader).await? else {
+        return None;
+    };
+    let mut service = ServiceCapabilities::new(TextProtocol::Smtp, reply_text(&greeting[0], 4));
+
+    send(reader, "EHLO ipscan-rs").await?;
+    if let Some((250, lines)) = read_reply(reader).await {
+        // The first line greets us back by name.
+        service.capabilities = lines
+            .iter()
+            .skip(1)
+            .map(|line| reply_text(line, 4))
+            .collect();
+    }
+    service.starttls = service
+        .capabilities
+        .iter()
+        .any(|extension| extension.eq_ignore_ascii_case("STARTTLS"));
+    service.plaintext_auth = service.capabilities.iter().any(|extension| {
+        extension
+            .get(..5)
+            .is_some_and(|name| name.eq_ignore_ascii_case("AUTH "))
+            && has_plaintext_mechanism(&extension[5..])
+    });
+    let _ = send(reader, "QUIT").await;
+    Some(service)
+}
+
+async fn pop3(reader: &mut Reader) -> Option<ServiceCapabilities> {
+    let greeting = read_line(reader).await?;
+    if !greeting.starts_with("+OK") {
+        return None;
+    }
+    let mut service = ServiceCapabilities::new(TextProtocol::Pop3, reply_text(&greeting, 3));
+
+    send(reader, "CAPA").await?;
+    if read_line(reader).await?.starts_with("+OK") {
+        for _ in 0..MAX_REPLY_LINES {
+            let line = read_line(reader).await?;
+            if line == "." {
+                break;
+            }
+            service.capabilities.push(line);
+        }
+    }
+    service.starttls = service.capabilities.iter().any(|c| c == "STLS");
+    service.plaintext_auth = service.capabilities.iter().any(|capability| {
+        capability == "USER"
+            || capability
+                .strip_prefix("SASL ")
+                .is_some_and(has_plaintext_mechanism)
+    });
+    let _ = send(reader, "QUIT").await;
+    Some(service)
+}
+
+async fn imap(reader: &mut Reader) -> Option<ServiceCapabilities> {
+    let greeting = read_line(reader).await?;
+    if !greeting.starts_with("* OK") {
+        return None;
+    }
+    let mut service = ServiceCapabilities::new(TextProtocol::Imap, reply_text(&greeting, 4));
+
+    send(reader, "a1 CAPABILITY").await?;
+    for _ in 0..MAX_REPLY_LINES {
+        let line = read_line(reader).await?;
+        if let Some(capabilities) = line.strip_prefix("* CAPABILITY ") {
+            service.capabilities = capabilities
+                .split_whitespace()
+                .map(str::to_string)
+                .collect();
+        }
+        if line.starts_with("a1 ") {
+            break;
+        }
+    }
+    service.starttls = service.capabilities.iter().any(|c| c == "STARTTLS");
+    // LOGIN works unless the server disables it until TLS is up.
+    service.plaintext_auth = !service.capabilities.iter().any(|c| c == "LOGINDISABLED");
+    let _ = send(reader, "a2 LOGOUT").await;
+    Some(service)
+}
+
+/// Reads the greeting and capabilities of the `protocol` server on `port`,
+/// all within `wait`. `anonymous` also tries an anonymous FTP login.
+pub async fn inspect(
+    protocol: TextProtocol,
+    address: IpAddr,
+    port: u16,
+    anonymous: bool,
+    wait: Duration,
+) -> Option<ServiceCapabilities> {
+    let exchange = async {
+        let stream = TcpStream::connect((address, port)).await.ok()?;
+        let mut reader = BufReader::new(stream);
+        match protocol {
+            TextProtocol::Ftp => ftp(&mut reader, anonymous).await,
+            TextProtocol::Smtp => smtp(&mut reader).await,
+            TextProtocol::Pop3 => pop3(&mut reader).await,
+            TextProtocol::Imap => imap(&mut reader).await,
+        }
+    };
+    timeout(wait, exchange).await.ok()?
+}
+
+pub struct CapabilityFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+}
+
+impl CapabilityFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        Self { config, sockets }
+    }
+}
+
+#[async_trait]
+impl Fetcher for CapabilityFetcher {
+    fn id(&self) -> String {
+        "capabilities".to_string()
+    }
+
+    fn name(&self) -> String {
+        "Mail/FTP Capabilities".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let open_ports = ports::open_ports(subject);
+        let targets: Vec<(u16, TextProtocol)> = CAPABILITY_PORTS
+            .into_iter()
+            .filter(|(port, _)| open_ports.contains(port))
+            .collect();
+        if targets.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        let anonymous = self.config.ftp_anonymous_check;
+        let mut sessions = JoinSet::new();
+        for (port, protocol) in targets {
+            let sockets = self.sockets.clone();
+            sessions.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                let service = inspect(protocol, address, port, anonymous, wait).await;
+                (port, service)
+            });
+        }
+
+        let mut capabilities = Capabilities::new();
+        while let Some(session) = sessions.join_next().await {
+            if let Ok((port, Some(service))) = session {
+                capabilities.insert(port, service);
+            }
+        }
+
+        if capabilities.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = capabilities
+            .iter()
+            .map(|(port, service)| format!("{}: {}", port, service))
+            .collect::<Vec<_>>()
+            .join("; ");
+        subject.set_parameter(SERVICE_CAPABILITIES.to_string(), capabilities);
+        Ok(summary)
+    }
+}
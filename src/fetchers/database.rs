@@ -0,0 +1,578 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::ports;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+use crate::network_utils;
+
+pub const DATABASE_SERVERS: &str = "database.servers";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DatabaseKind {
+    MySql,
+    PostgreSql,
+    Redis,
+    MongoDb,
+    MsSql,
+}
+
+impl fmt::Display for DatabaseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DatabaseKind::MySql => "MySQL",
+            DatabaseKind::PostgreSql => "PostgreSQL",
+            DatabaseKind::Redis => "Redis",
+            DatabaseKind::MongoDb => "MongoDB",
+            DatabaseKind::MsSql => "MSSQL",
+        })
+    }
+}
+
+/// Default ports of the databases we know how to greet.
+pub const DATABASE_PORTS: [(u16, DatabaseKind); 7] = [
+    (1433, DatabaseKind::MsSql),
+    (3306, DatabaseKind::MySql),
+    (5432, DatabaseKind::PostgreSql),
+    (6379, DatabaseKind::Redis),
+    (27017, DatabaseKind::MongoDb),
+    (27018, DatabaseKind::MongoDb),
+    (27019, DatabaseKind::MongoDb),
+];
+
+const MAX_MESSAGE: usize = 65536;
+
+const OP_REPLY: i32 = 1;
+const OP_QUERY: i32 = 2004;
+const OP_MSG: i32 = 2013;
+
+pub type DatabaseServers = BTreeMap<u16, DatabaseServer>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DatabaseServer {
+    pub kind: DatabaseKind,
+    pub version: Option<String>,
+    /// The probe got in without any credentials.
+    pub unauthenticated: bool,
+    /// Authentication methods, TLS support and errors the server reported.
+    pub details: Vec<String>,
+}
+
+impl DatabaseServer {
+    fn new(kind: DatabaseKind) -> Self {
+        Self {
+            kind,
+            version: None,
+            unauthenticated: false,
+            details: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for DatabaseServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(version) = &self.version {
+            write!(f, " {}", version)?;
+        }
+        let mut details = Vec::new();
+        if self.unauthenticated {
+            details.push("NO AUTHENTICATION");
+        }
+        details.extend(self.details.iter().map(String::as_str));
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+async fn read_exact(stream: &mut TcpStream, len: usize) -> Option<Vec<u8>> {
+    if len > MAX_MESSAGE {
+        return None;
+    }
+    let mut buffer = vec![0u8; len];
+    stream.read_exact(&mut buffer).await.ok()?;
+    Some(buffer)
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// MySQL and MariaDB greet first, with their version or with an error.
+async fn mysql(stream: &mut TcpStream) -> Option<DatabaseServer> {
+    let header = read_exact(stream, 4).await?;
+    let len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+    let payload = read_exact(stream, len).await?;
+
+    let mut server = DatabaseServer::new(DatabaseKind::MySql);
+    match payload.first()? {
+        10 => {
+            let version = c_string(&payload[1..]);
+            // MariaDB prefixes its version for clients that expect 5.x.
+            let version = version.strip_prefix("5.5.5-").unwrap_or(&version);
+            server.version = Some(version.to_string());
+        }
+        0xff if payload.len() > 3 => {
+            let message = String::from_utf8_lossy(&payload[3..]);
+            server.details.push(message.trim().to_string());
+        }
+        _ => return None,
+    }
+    Some(server)
+}
+
+fn postgres_startup() -> Vec<u8> {
+    let mut body = 196608u32.to_be_bytes().to_vec(); // protocol 3.0
+    for text in ["user", "postgres", "database", "postgres"] {
+        body.extend_from_slice(text.as_bytes());
+        body.push(0);
+    }
+    body.extend_from_slice(b"application_name\0ipscan-rs\0\0");
+    let mut message = ((body.len() + 4) as u32).to_be_bytes().to_vec();
+    message.extend_from_slice(&body);
+    message
+}
+
+async fn postgres_session(stream: &mut TcpStream, server: &mut DatabaseServer) -> Option<()> {
+    stream.write_all(&postgres_startup()).await.ok()?;
+    // A trusting server sends its parameters and is ready after a few dozen
+    // messages at most.
+    for _ in 0..64 {
+        let header = read_exact(stream, 5).await?;
+        let len = u32::from_be_bytes(header[1..5].try_into().ok()?) as usize;
+        let body = read_exact(stream, len.checked_sub(4)?).await?;
+        match header[0] {
+            b'R' => {
+                let method = u32::from_be_bytes(body.get(..4)?.try_into().ok()?);
+                let auth = match method {
+                    0 => {
+                        server.unauthenticated = true;
+                        continue;
+                    }
+                    3 => "password".to_string(),
+                    5 => "md5".to_string(),
+                    10 => body[4..]
+                        .split(|&b| b == 0)
+                        .filter(|m| !m.is_empty())
+                        .map(|m| String::from_utf8_lossy(m).into_owned())
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    other => format!("method {}", other),
+                };
+                server.details.push(format!("{} auth", auth));
+                return Some(());
+            }
+            b'S' => {
+                let mut fields = body.split(|&b| b == 0);
+                if fields.next() == Some(b"server_version") {
+                    server.version = fields
+                        .next()
+                        .map(|v| String::from_utf8_lossy(v).into_owned());
+                }
+            }
+            b'E' => {
+                let message = body
+                    .split(|&b| b == 0)
+                    .find_map(|field| field.strip_prefix(b"M"))
+                    .map(|m| String::from_utf8_lossy(m).into_owned());
+                server.details.extend(message);
+                return Some(());
+            }
+            b'Z' => {
+                let _ = stream.write_all(b"X\0\0\0\x04").await;
+                return Some(());
+            }
+            _ => {}
+        }
+    }
+    Some(())
+}
+
+/// Asks for TLS, then logs in as `postgres` without a password, which only
+/// a server with `trust` authentication lets through.
+async fn postgres(address: IpAddr, port: u16, mut stream: TcpStream) -> Option<DatabaseServer> {
+    stream
+        .write_all(&[0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f])
+        .await
+        .ok()?;
+    let mut answer = [0u8; 1];
+    stream.read_exact(&mut answer).await.ok()?;
+
+    let mut server = DatabaseServer::new(DatabaseKind::PostgreSql);
+    match answer[0] {
+        b'N' => {}
+        b'S' => {
+            server.details.push("TLS".to_string());
+            // The TLS handshake is not ours to finish; start over in plain text.
+            stream = TcpStream::connect((address, port)).await.ok()?;
+        }
+        _ => return None,
+    }
+    postgres_session(&mut stream, &mut server).await;
+    Some(server)
+}
+
+async fn redis(stream: &mut TcpStream, wait: Duration) -> Option<DatabaseServer> {
+    stream.write_all(b"PING\r\nINFO server\r\n").await.ok()?;
+    let mut buffer = vec![0u8; 16 * 1024];
+    let len = network_utils::read_available(stream, &mut buffer, wait).await;
+    let reply = String::from_utf8_lossy(&buffer[..len]);
+
+    let mut server = DatabaseServer::new(DatabaseKind::Redis);
+    if reply.starts_with("+PONG") {
+        server.unauthenticated = true;
+        server.version = reply
+            .lines()
+            .find_map(|line| line.strip_prefix("redis_version:"))
+            .map(str::to_string);
+    } else if reply.starts_with("-NOAUTH") || reply.starts_with("-WRONGPASS") {
+        server.details.push("auth required".to_string());
+    } else if reply.starts_with("-DENIED") {
+        server.details.push("protected mode".to_string());
+    } else {
+        return None;
+    }
+    Some(server)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum BsonValue {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Other,
+}
+
+/// `{command: 1}`, with `$db: "admin"` as `OP_MSG` wants it.
+fn bson_command(command: &str, with_db: bool) -> Vec<u8> {
+    let mut elements = vec![0x10];
+    elements.extend_from_slice(command.as_bytes());
+    elements.push(0);
+    elements.extend_from_slice(&1i32.to_le_bytes());
+    if with_db {
+        elements.push(0x02);
+        elements.extend_from_slice(b"$db\0");
+        elements.extend_from_slice(&6i32.to_le_bytes());
+        elements.extend_from_slice(b"admin\0");
+    }
+    elements.push(0);
+
+    let mut document = ((elements.len() + 4) as i32).to_le_bytes().to_vec();
+    document.extend_from_slice(&elements);
+    document
+}
+
+/// Top level fields of a BSON document, nested ones left as `Other`.
+fn bson_fields(document: &[u8]) -> Option<Vec<(String, BsonValue)>> {
+    let i32_at = |at: usize| -> Option<i32> {
+        Some(i32::from_le_bytes(
+            document.get(at..at + 4)?.try_into().ok()?,
+        ))
+    };
+    // A negative length would wrap around.
+    let len_at = |at: usize| -> Option<usize> { Some(i32_at(at)?.max(0) as usize) };
+    let end = len_at(0)?.min(document.len());
+    let mut pos = 4;
+    let mut fields = Vec::new();
+    while pos < end {
+        let kind = document[pos];
+        if kind == 0 {
+            break;
+        }
+        let name_len = document[pos + 1..].iter().position(|&b| b == 0)?;
+        let name = String::from_utf8_lossy(&document[pos + 1..pos + 1 + name_len]).into_owned();
+        pos += name_len + 2;
+
+        let (value, size) = match kind {
+            0x01 => {
+                let bytes = document.get(pos..pos + 8)?.try_into().ok()?;
+                (BsonValue::Number(f64::from_le_bytes(bytes)), 8)
+            }
+            0x02 => {
+                let len = len_at(pos)?;
+                let text = document.get(pos + 4..pos + 4 + len.saturating_sub(1))?;
+                (
+                    BsonValue::String(String::from_utf8_lossy(text).into_owned()),
+                    4 + len,
+                )
+            }
+            0x03 | 0x04 => (BsonValue::Other, len_at(pos)?),
+            0x05 => (BsonValue::Other, 5 + len_at(pos)?),
+            0x07 => (BsonValue::Other, 12),
+            0x08 => (BsonValue::Bool(*document.get(pos)? != 0), 1),
+            0x09 | 0x11 => (BsonValue::Other, 8),
+            0x0a => (BsonValue::Other, 0),
+            0x10 => (BsonValue::Number(i32_at(pos)? as f64), 4),
+            0x12 => {
+                let bytes = document.get(pos..pos + 8)?.try_into().ok()?;
+                (BsonValue::Number(i64::from_le_bytes(bytes) as f64), 8)
+            }
+            _ => return Some(fields),
+        };
+        fields.push((name, value));
+        pos += size;
+    }
+    Some(fields)
+}
+
+/// Runs a command against the `admin` database, as an `OP_MSG` or, when
+/// `legacy` is set, as an `OP_QUERY` on `admin.$cmd` for servers before 3.6.
+async fn mongodb_command(
+    stream: &mut TcpStream,
+    request_id: i32,
+    command: &str,
+    legacy: bool,
+) -> Option<Vec<(String, BsonValue)>> {
+    let mut body = 0u32.to_le_bytes().to_vec(); // flags
+    let op_code = if legacy {
+        body.extend_from_slice(b"admin.$cmd\0");
+        body.extend_from_slice(&0i32.to_le_bytes()); // documents to skip
+        body.extend_from_slice(&(-1i32).to_le_bytes()); // return one, no cursor
+        body.extend_from_slice(&bson_command(command, false));
+        OP_QUERY
+    } else {
+        body.push(0); // body section
+        body.extend_from_slice(&bson_command(command, true));
+        OP_MSG
+    };
+    let mut message = ((16 + body.len()) as i32).to_le_bytes().to_vec();
+    message.extend_from_slice(&request_id.to_le_bytes());
+    message.extend_from_slice(&0i32.to_le_bytes());
+    message.extend_from_slice(&op_code.to_le_bytes());
+    message.extend_from_slice(&body);
+    stream.write_all(&message).await.ok()?;
+
+    let header = read_exact(stream, 16).await?;
+    let len = i32::from_le_bytes(header[..4].try_into().ok()?) as usize;
+    let reply_op_code = i32::from_le_bytes(header[12..16].try_into().ok()?);
+    let body = read_exact(stream, len.checked_sub(16)?).await?;
+    match reply_op_code {
+        OP_MSG if !legacy && body.get(4) == Some(&0) => bson_fields(&body[5..]),
+        // Flags, cursor id, starting position and count precede the document.
+        OP_REPLY if legacy => bson_fields(body.get(20..)?),
+        _ => None,
+    }
+}
+
+fn bson_ok(fields: &[(String, BsonValue)]) -> bool {
+    fields
+        .iter()
+        .any(|(name, value)| name == "ok" && *value == BsonValue::Number(1.0))
+}
+
+/// `hello`, or `isMaster` on servers that predate it, answers without a
+/// login. `buildInfo` needs none either; `listDatabases` succeeding means
+/// none is required at all.
+async fn mongodb(address: IpAddr, port: u16, mut stream: TcpStream) -> Option<DatabaseServer> {
+    let ok = |reply: Option<Vec<(String, BsonValue)>>| reply.is_some_and(|r| bson_ok(&r));
+    let legacy = if ok(mongodb_command(&mut stream, 1, "hello", false).await)
+        || ok(mongodb_command(&mut stream, 2, "isMaster", false).await)
+    {
+        false
+    } else {
+        // Servers before 3.6 drop the connection on an OP_MSG.
+        stream = TcpStream::connect((address, port)).await.ok()?;
+        if !ok(mongodb_command(&mut stream, 1, "isMaster", true).await) {
+            return None;
+        }
+        true
+    };
+
+    let mut server = DatabaseServer::new(DatabaseKind::MongoDb);
+    if let Some(build_info) = mongodb_command(&mut stream, 3, "buildInfo", legacy).await {
+        server.version = build_info.iter().find_map(|(name, value)| match value {
+            BsonValue::String(version) if name == "version" => Some(version.clone()),
+            _ => None,
+        });
+    }
+    if let Some(databases) = mongodb_command(&mut stream, 4, "listDatabases", legacy).await {
+        if bson_ok(&databases) {
+            server.unauthenticated = true;
+        } else {
+            server.details.push("auth required".to_string());
+        }
+    }
+    Some(server)
+}
+
+fn mssql_prelogin() -> Vec<u8> {
+    // Version, encryption, instance, thread id and MARS, then their data.
+    let options: [(u8, u16, u16); 5] = [(0, 26, 6), (1, 32, 1), (2, 33, 1), (3, 34, 4), (4, 38, 1)];
+    let mut payload = Vec::new();
+    for (token, offset, len) in options {
+        payload.push(token);
+        payload.extend_from_slice(&offset.to_be_bytes());
+        payload.extend_from_slice(&len.to_be_bytes());
+    }
+    payload.push(0xff);
+    payload.extend_from_slice(&[0; 6]);
+    payload.push(0x02); // encryption not supported
+    payload.extend_from_slice(&[0; 6]);
+
+    let mut packet = vec![0x12, 0x01];
+    packet.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 1, 0]);
+    packet.extend_from_slice(&payload);
+    packet
+}
+
+fn mssql_release(major: u8) -> Option<&'static str> {
+    Some(match major {
+        8 => "2000",
+        9 => "2005",
+        10 => "2008",
+        11 => "2012",
+        12 => "2014",
+        13 => "2016",
+        14 => "2017",
+        15 => "2019",
+        16 => "2022",
+        _ => return None,
+    })
+}
+
+async fn mssql(stream: &mut TcpStream) -> Option<DatabaseServer> {
+    stream.write_all(&mssql_prelogin()).await.ok()?;
+    let header = read_exact(stream, 8).await?;
+    if header[0] != 0x04 {
+        return None;
+    }
+    let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+    let payload = read_exact(stream, len.checked_sub(8)?).await?;
+
+    let mut server = DatabaseServer::new(DatabaseKind::MsSql);
+    let mut pos = 0;
+    while let Some(&token) = payload.get(pos) {
+        if token == 0xff {
+            break;
+        }
+        let option = payload.get(pos + 1..pos + 5)?;
+        let offset = u16::from_be_bytes([option[0], option[1]]) as usize;
+        let len = u16::from_be_bytes([option[2], option[3]]) as usize;
+        let data = payload.get(offset..offset + len)?;
+        match token {
+            0 if len >= 4 => {
+                let build = u16::from_be_bytes([data[2], data[3]]);
+                server.version = Some(format!("{}.{}.{}", data[0], data[1], build));
+                if let Some(release) = mssql_release(data[0]) {
+                    server.details.push(format!("SQL Server {}", release));
+                }
+            }
+            1 if len >= 1 => server.details.push(
+                match data[0] {
+                    0 => "encryption off",
+                    1 => "encryption on",
+                    2 => "encryption not supported",
+                    _ => "encryption required",
+                }
+                .to_string(),
+            ),
+            _ => {}
+        }
+        pos += 5;
+    }
+    Some(server)
+}
+
+/// Greets the server on `port` the way a `kind` client would.
+pub async fn probe(
+    kind: DatabaseKind,
+    address: IpAddr,
+    port: u16,
+    wait: Duration,
+) -> Option<DatabaseServer> {
+    let exchange = async {
+        let mut stream = TcpStream::connect((address, port)).await.ok()?;
+        match kind {
+            DatabaseKind::MySql => mysql(&mut stream).await,
+            DatabaseKind::PostgreSql => postgres(address, port, stream).await,
+            DatabaseKind::Redis => redis(&mut stream, wait).await,
+            DatabaseKind::MongoDb => mongodb(address, port, stream).await,
+            DatabaseKind::MsSql => mssql(&mut stream).await,
+        }
+    };
+    timeout(wait, exchange).await.ok()?
+}
+
+pub struct DatabaseFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+}
+
+impl DatabaseFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        Self { config, sockets }
+    }
+}
+
+#[async_trait]
+impl Fetcher for DatabaseFetcher {
+    fn id(&self) -> String {
+        "database".to_string()
+    }
+
+    fn name(&self) -> String {
+        "Databases".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let open_ports = ports::open_ports(subject);
+        let targets: Vec<(u16, DatabaseKind)> = DATABASE_PORTS
+            .into_iter()
+            .filter(|(port, _)| open_ports.contains(port))
+            .collect();
+        if targets.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        let mut probes = JoinSet::new();
+        for (port, kind) in targets {
+            let sockets = self.sockets.clone();
+            probes.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                (port, probe(kind, address, port, wait).await)
+            });
+        }
+
+        let mut servers = DatabaseServers::new();
+        while let Some(result) = probes.join_next().await {
+            if let Ok((port, Some(server))) = result {
+                servers.insert(port, server);
+            }
+        }
+
+        if servers.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = servers
+            .iter()
+            .map(|(port, server)| format!("{}: {}", port, server))
+            .collect::<Vec<_>>()
+            .join("; ");
+        subject.set_parameter(DATABASE_SERVERS.to_string(), servers);
+        Ok(summary)
+    }
+}
@@ -0,0 +1,246 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::{Instant, timeout_at};
+
+use super::ports;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::dns::{self, Message, RecordData};
+use crate::errors::ScanError;
+
+pub const DNS_SERVER: &str = "dns.server";
+
+pub const DNS_PORT: u16 = 53;
+
+// A name no server on the scanned network is authoritative for, so an
+// answer means the server went and asked the internet.
+const RECURSION_PROBE_NAME: &str = "example.com";
+// Bounds a transfer of a huge zone, or a server that never ends it.
+const MAX_AXFR_MESSAGES: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ZoneTransfer {
+    pub zone: String,
+    pub allowed: bool,
+    pub records: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DnsServerInfo {
+    pub udp: bool,
+    pub tcp: bool,
+    /// Answer to `version.bind`, which many servers hide.
+    pub version: Option<String>,
+    pub open_recursion: bool,
+    pub zone_transfer: Option<ZoneTransfer>,
+}
+
+impl DnsServerInfo {
+    /// Open resolvers and open zone transfers, both worth a look.
+    pub fn is_exposed(&self) -> bool {
+        self.open_recursion
+            || self
+                .zone_transfer
+                .as_ref()
+                .is_some_and(|transfer| transfer.allowed)
+    }
+}
+
+impl fmt::Display for DnsServerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let transports: Vec<&str> = [(self.udp, "UDP"), (self.tcp, "TCP")]
+            .into_iter()
+            .filter_map(|(on, name)| on.then_some(name))
+            .collect();
+        match &self.version {
+            Some(version) => write!(f, "DNS {} [{}]", version, transports.join(", "))?,
+            None => write!(f, "DNS [{}]", transports.join(", "))?,
+        }
+        if self.open_recursion {
+            f.write_str("; OPEN RESOLVER")?;
+        }
+        if let Some(transfer) = &self.zone_transfer {
+            if transfer.allowed {
+                write!(
+                    f,
+                    "; AXFR {} ALLOWED ({} records)",
+                    transfer.zone, transfer.records
+                )?;
+            } else {
+                write!(f, "; AXFR {} refused", transfer.zone)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn ask(server: SocketAddr, tcp: bool, query: &[u8], wait: Duration) -> Option<Message> {
+    let response = if tcp {
+        dns::exchange_tcp(server, query, wait).await.ok()?
+    } else {
+        dns::exchange(server, query, wait).await.ok()?
+    };
+    Message::parse(&response).filter(Message::is_response)
+}
+
+fn version_bind(response: &Message) -> Option<String> {
+    if response.rcode() != dns::RCODE_NOERROR {
+        return None;
+    }
+    response
+        .answers
+        .iter()
+        .find_map(|record| match &record.data {
+            RecordData::Txt(strings) if !strings.concat().trim().is_empty() => {
+                Some(strings.concat().trim().to_string())
+            }
+            _ => None,
+        })
+}
+
+/// Asks for a transfer of `zone` and counts the records until the SOA
+/// that closes it, all within `wait`. A transfer cut short by the deadline
+/// still counts as allowed once a message has answered it.
+pub async fn zone_transfer(
+    address: IpAddr,
+    port: u16,
+    zone: &str,
+    wait: Duration,
+) -> Option<ZoneTransfer> {
+    let deadline = Instant::now() + wait;
+    let mut stream = timeout_at(deadline, TcpStream::connect((address, port)))
+        .await
+        .ok()?
+        .ok()?;
+    let query = dns::query(dns::next_id(), zone, dns::TYPE_AXFR, dns::CLASS_IN, false);
+    timeout_at(deadline, dns::write_tcp_message(&mut stream, &query))
+        .await
+        .ok()?
+        .ok()?;
+
+    let mut result = ZoneTransfer {
+        zone: zone.to_string(),
+        allowed: false,
+        records: 0,
+    };
+    let mut soas = 0;
+    for _ in 0..MAX_AXFR_MESSAGES {
+        // Servers that refuse often just close the connection.
+        let Ok(Ok(bytes)) = timeout_at(deadline, dns::read_tcp_message(&mut stream)).await else {
+            break;
+        };
+        let Some(message) = Message::parse(&bytes) else {
+            break;
+        };
+        if message.id != u16::from_be_bytes([query[0], query[1]]) {
+            continue;
+        }
+        if message.rcode() != dns::RCODE_NOERROR || message.answers.is_empty() {
+            break;
+        }
+        result.allowed = true;
+        result.records += message.answers.len();
+        soas += message
+            .answers
+            .iter()
+            .filter(|record| record.record_type == dns::TYPE_SOA)
+            .count();
+        if soas >= 2 {
+            break;
+        }
+    }
+    Some(result)
+}
+
+/// Probes the DNS server on `port`, over TCP too when `tcp_open`, and
+/// tries a transfer of `zone` when one is given.
+pub async fn probe(
+    address: IpAddr,
+    port: u16,
+    tcp_open: bool,
+    zone: Option<&str>,
+    wait: Duration,
+) -> Option<DnsServerInfo> {
+    let server = SocketAddr::new(address, port);
+    let version_query = |id| dns::query(id, "version.bind", dns::TYPE_TXT, dns::CLASS_CH, false);
+
+    let udp = ask(server, false, &version_query(dns::next_id()), wait).await;
+    let tcp = if tcp_open {
+        ask(server, true, &version_query(dns::next_id()), wait).await
+    } else {
+        None
+    };
+    let mut info = DnsServerInfo {
+        udp: udp.is_some(),
+        tcp: tcp.is_some(),
+        version: udp.as_ref().or(tcp.as_ref()).and_then(version_bind),
+        ..Default::default()
+    };
+    if !info.udp && !info.tcp {
+        return None;
+    }
+
+    let recursion = dns::query(
+        dns::next_id(),
+        RECURSION_PROBE_NAME,
+        dns::TYPE_A,
+        dns::CLASS_IN,
+        true,
+    );
+    if let Some(response) = ask(server, !info.udp, &recursion, wait).await {
+        info.open_recursion = response.recursion_available()
+            && response.rcode() == dns::RCODE_NOERROR
+            && !response.answers.is_empty();
+    }
+
+    // Transfers only run over TCP.
+    if let Some(zone) = zone
+        && info.tcp
+    {
+        info.zone_transfer = zone_transfer(address, port, zone, wait).await;
+    }
+    Some(info)
+}
+
+pub struct DnsServerFetcher {
+    config: Arc<ScannerConfig>,
+}
+
+impl DnsServerFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Fetcher for DnsServerFetcher {
+    fn id(&self) -> String {
+        "dns_server".to_string()
+    }
+
+    fn name(&self) -> String {
+        "DNS Server".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let address = subject.address();
+        let tcp_open = ports::open_ports(subject).contains(&DNS_PORT);
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        let zone = self.config.dns_axfr_zone.as_deref();
+
+        match probe(address, DNS_PORT, tcp_open, zone, wait).await {
+            Some(info) => {
+                let summary = info.to_string();
+                subject.set_parameter(DNS_SERVER.to_string(), info);
+                Ok(summary)
+            }
+            None => Ok("[n/a]".to_string()),
+        }
+    }
+}
@@ -1,15 +1,27 @@
 use async_trait::async_trait;
-use dns_lookup::lookup_addr;
 
+use super::llmnr::LLMNR_NAME;
+use super::mdns::{MDNS, MdnsInfo};
+use super::netbios::{NETBIOS, NetBiosInfo};
 use super::traits::Fetcher;
+use crate::config::ScannerConfig;
 use crate::core::subject::ScanningSubject;
 use crate::errors::ScanError;
+use crate::resolver::Resolver;
 
-pub struct HostnameFetcher;
+pub struct HostnameFetcher {
+    resolver: Resolver,
+}
 
 impl HostnameFetcher {
     pub fn new() -> Self {
-        Self
+        Self::with_config(&ScannerConfig::default())
+    }
+
+    pub fn with_config(config: &ScannerConfig) -> Self {
+        Self {
+            resolver: Resolver::from_config(config),
+        }
     }
 }
 
@@ -30,14 +42,23 @@ impl Fetcher for HostnameFetcher {
     }
 
     async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
-        tokio::task::spawn_blocking({
-            let addr = subject.address();
-            move || match lookup_addr(&addr) {
-                Ok(hostname) => Ok(hostname),
-                Err(_) => Ok("[n/a]".to_string()),
-            }
-        })
-        .await
-        .map_err(|e| ScanError::DnsResolutionFailed(e.to_string()))?
+        let hostname = self.resolver.reverse(subject.address()).await;
+
+        // Windows machines and home devices often lack a PTR record but
+        // answer NetBIOS, mDNS or LLMNR.
+        let discovered_name = || {
+            subject
+                .get_parameter::<NetBiosInfo>(NETBIOS)
+                .and_then(|info| info.computer_name.clone())
+                .or_else(|| {
+                    subject
+                        .get_parameter::<MdnsInfo>(MDNS)
+                        .and_then(|info| info.name.clone())
+                })
+                .or_else(|| subject.get_parameter::<String>(LLMNR_NAME).cloned())
+        };
+        Ok(hostname
+            .or_else(discovered_name)
+            .unwrap_or_else(|| "[n/a]".to_string()))
     }
 }
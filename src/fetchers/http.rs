@@ -0,0 +1,239 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::ports;
+use super::tls;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+use crate::network_utils;
+
+pub const HTTP_RESPONSES: &str = "http.responses";
+
+/// Ports where web servers and web UIs usually listen.
+pub const WEB_PORTS: [u16; 16] = [
+    80, 81, 443, 591, 3000, 4443, 5000, 8000, 8008, 8080, 8081, 8088, 8443, 8888, 9000, 9443,
+];
+/// Web ports that usually speak TLS; those are tried with TLS first.
+pub const TLS_WEB_PORTS: [u16; 3] = [443, 4443, 8443];
+
+// Enough for the headers and the <title> of nearly any landing page.
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+pub type HttpResponses = BTreeMap<u16, HttpResponse>;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HttpResponse {
+    pub tls: bool,
+    pub status: u16,
+    pub server: Option<String>,
+    pub title: Option<String>,
+    pub location: Option<String>,
+    /// From `Content-Length`, or the size of the body actually received.
+    pub content_length: Option<usize>,
+}
+
+impl HttpResponse {
+    /// Parses the status line, the interesting headers and the page title.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let text = String::from_utf8_lossy(bytes);
+        let (head, body) = match text.find("\r\n\r\n") {
+            Some(end) => (&text[..end], Some(&text[end + 4..])),
+            None => (&text[..], None),
+        };
+
+        let mut lines = head.lines();
+        let status_line = lines.next()?;
+        if !status_line.starts_with("HTTP/") {
+            return None;
+        }
+        let status = status_line.split_whitespace().nth(1)?.parse().ok()?;
+
+        let mut response = HttpResponse {
+            status,
+            ..Default::default()
+        };
+        for line in lines {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().to_string();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "server" => response.server = Some(value),
+                "location" => response.location = Some(value),
+                "content-length" => response.content_length = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        if let Some(body) = body {
+            response.content_length = response.content_length.or(Some(body.len()));
+            response.title = parse_title(body);
+        }
+        Some(response)
+    }
+}
+
+impl fmt::Display for HttpResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.status)?;
+        if let Some(title) = &self.title {
+            write!(f, " \"{}\"", title)?;
+        }
+        if let Some(location) = &self.location {
+            write!(f, " -> {}", location)?;
+        }
+        if let Some(server) = &self.server {
+            write!(f, " ({})", server)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_title(body: &str) -> Option<String> {
+    let lower = body.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = body[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+async fn get<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    host: &str,
+    path: &str,
+    wait: Duration,
+) -> Option<Vec<u8>> {
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: ipscan-rs\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    timeout(wait, stream.write_all(request.as_bytes()))
+        .await
+        .ok()?
+        .ok()?;
+
+    let mut buffer = vec![0u8; MAX_RESPONSE_BYTES];
+    let len = network_utils::read_available(stream, &mut buffer, wait).await;
+    buffer.truncate(len);
+    Some(buffer)
+}
+
+/// Raw response to `GET path`, cut off at 64 KiB.
+pub async fn get_raw(
+    address: IpAddr,
+    port: u16,
+    tls: bool,
+    path: &str,
+    wait: Duration,
+) -> Option<Vec<u8>> {
+    let host = match address {
+        IpAddr::V4(v4) => format!("{}:{}", v4, port),
+        IpAddr::V6(v6) => format!("[{}]:{}", v6, port),
+    };
+
+    if tls {
+        let mut stream = tls::connect(address, port, wait).await.ok()?;
+        get(&mut stream, &host, path, wait).await
+    } else {
+        let mut stream = timeout(wait, TcpStream::connect((address, port)))
+            .await
+            .ok()?
+            .ok()?;
+        get(&mut stream, &host, path, wait).await
+    }
+}
+
+pub async fn fetch(address: IpAddr, port: u16, tls: bool, wait: Duration) -> Option<HttpResponse> {
+    let response = get_raw(address, port, tls, "/", wait).await?;
+    HttpResponse::parse(&response).map(|response| HttpResponse { tls, ..response })
+}
+
+pub struct HttpFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+}
+
+impl HttpFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        Self { config, sockets }
+    }
+}
+
+#[async_trait]
+impl Fetcher for HttpFetcher {
+    fn id(&self) -> String {
+        "http".to_string()
+    }
+
+    fn name(&self) -> String {
+        "HTTP".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let web_ports: Vec<u16> = ports::open_ports(subject)
+            .into_iter()
+            .filter(|port| WEB_PORTS.contains(port))
+            .collect();
+        if web_ports.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let wait = Duration::from_millis(self.config.http_timeout_ms);
+        let mut requests = JoinSet::new();
+        for port in web_ports {
+            let sockets = self.sockets.clone();
+            requests.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                // Fall back to the other scheme: plenty of appliances serve
+                // TLS on 8080 or plain HTTP on 8443.
+                let tls_first = TLS_WEB_PORTS.contains(&port);
+                let response = match fetch(address, port, tls_first, wait).await {
+                    Some(response) => Some(response),
+                    None => fetch(address, port, !tls_first, wait).await,
+                };
+                (port, response)
+            });
+        }
+
+        let mut responses = HttpResponses::new();
+        while let Some(request) = requests.join_next().await {
+            if let Ok((port, Some(response))) = request {
+                responses.insert(port, response);
+            }
+        }
+
+        if responses.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = responses
+            .iter()
+            .map(|(port, response)| format!("{}: {}", port, response))
+            .collect::<Vec<_>>()
+            .join("; ");
+        subject.set_parameter(HTTP_RESPONSES.to_string(), responses);
+        Ok(summary)
+    }
+}
@@ -0,0 +1,384 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::{Instant, timeout, timeout_at};
+
+use super::ports;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const INDUSTRIAL: &str = "industrial.info";
+
+pub const MODBUS_PORT: u16 = 502;
+pub const BACNET_PORT: u16 = 47808;
+pub const MQTT_PORT: u16 = 1883;
+
+// Controllers can stall on a half-finished exchange, so no probe holds a
+// connection longer than this, whatever the service timeout.
+const MAX_WAIT: Duration = Duration::from_millis(1500);
+// Devices answering on one connection with "more follows" forever.
+const MAX_MODBUS_ROUNDS: usize = 4;
+// 255 is "not used" on Modbus/TCP; 0 would be a broadcast behind gateways.
+const MODBUS_UNIT_ID: u8 = 0xff;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ModbusInfo {
+    /// False when the device refused Read Device Identification.
+    pub identification: bool,
+    pub vendor: Option<String>,
+    pub product_code: Option<String>,
+    pub revision: Option<String>,
+    pub product_name: Option<String>,
+    pub model_name: Option<String>,
+}
+
+impl fmt::Display for ModbusInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<&str> = [
+            &self.vendor,
+            &self.product_name,
+            &self.model_name,
+            &self.product_code,
+            &self.revision,
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+        if parts.is_empty() {
+            f.write_str("Modbus (no device identification)")
+        } else {
+            write!(f, "Modbus {}", parts.join(" "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BacnetInfo {
+    pub device_instance: u32,
+    pub vendor_id: u16,
+    pub max_apdu: u32,
+}
+
+impl fmt::Display for BacnetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "BACnet device {} (vendor {})",
+            self.device_instance, self.vendor_id
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MqttInfo {
+    /// CONNACK return code; 0 lets a client in without credentials.
+    pub return_code: u8,
+    pub anonymous: bool,
+}
+
+impl fmt::Display for MqttInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.return_code {
+            0 => f.write_str("MQTT ANONYMOUS ACCESS"),
+            4 | 5 => f.write_str("MQTT requires authentication"),
+            code => write!(f, "MQTT refused (code {})", code),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IndustrialInfo {
+    pub modbus: Option<ModbusInfo>,
+    pub bacnet: Option<BacnetInfo>,
+    pub mqtt: Option<MqttInfo>,
+}
+
+impl IndustrialInfo {
+    pub fn is_empty(&self) -> bool {
+        self.modbus.is_none() && self.bacnet.is_none() && self.mqtt.is_none()
+    }
+
+    pub fn has_anonymous_mqtt(&self) -> bool {
+        self.mqtt.is_some_and(|mqtt| mqtt.anonymous)
+    }
+}
+
+impl fmt::Display for IndustrialInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(modbus) = &self.modbus {
+            parts.push(modbus.to_string());
+        }
+        if let Some(bacnet) = &self.bacnet {
+            parts.push(bacnet.to_string());
+        }
+        if let Some(mqtt) = &self.mqtt {
+            parts.push(mqtt.to_string());
+        }
+        f.write_str(&parts.join("; "))
+    }
+}
+
+/// Modbus/TCP request for the device identification objects from `object`.
+fn read_device_identification(transaction: u16, category: u8, object: u8) -> Vec<u8> {
+    let mut request = transaction.to_be_bytes().to_vec();
+    request.extend_from_slice(&[0, 0, 0, 5, MODBUS_UNIT_ID]);
+    request.extend_from_slice(&[0x2b, 0x0e, category, object]);
+    request
+}
+
+/// Asks for the basic identification objects, and with `extended` for the
+/// regular ones too. Only reads; nothing on the device changes.
+pub async fn modbus(
+    address: IpAddr,
+    port: u16,
+    extended: bool,
+    wait: Duration,
+) -> Option<ModbusInfo> {
+    let exchange = async {
+        let mut stream = TcpStream::connect((address, port)).await.ok()?;
+        let mut info = ModbusInfo::default();
+        let category = if extended { 2 } else { 1 };
+        let mut object = 0;
+
+        for round in 0..MAX_MODBUS_ROUNDS {
+            let transaction = round as u16 + 1;
+            stream
+                .write_all(&read_device_identification(transaction, category, object))
+                .await
+                .ok()?;
+            let mut header = [0u8; 7];
+            stream.read_exact(&mut header).await.ok()?;
+            let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+            if header[2..4] != [0, 0] || !(3..=254).contains(&len) {
+                return None;
+            }
+            let mut pdu = vec![0u8; len - 1];
+            stream.read_exact(&mut pdu).await.ok()?;
+
+            // An exception reply still tells us Modbus is there.
+            if pdu[0] == 0xab {
+                return Some(info);
+            }
+            if pdu[..2] != [0x2b, 0x0e] || pdu.len() < 7 {
+                return None;
+            }
+            info.identification = true;
+            let (more_follows, next_object, count) = (pdu[4], pdu[5], pdu[6]);
+            let mut pos = 7;
+            for _ in 0..count {
+                let (Some(&id), Some(&object_len)) = (pdu.get(pos), pdu.get(pos + 1)) else {
+                    break;
+                };
+                let Some(value) = pdu.get(pos + 2..pos + 2 + object_len as usize) else {
+                    break;
+                };
+                pos += 2 + object_len as usize;
+                let value = String::from_utf8_lossy(value).trim().to_string();
+                if value.is_empty() {
+                    continue;
+                }
+                match id {
+                    0 => info.vendor = Some(value),
+                    1 => info.product_code = Some(value),
+                    2 => info.revision = Some(value),
+                    4 => info.product_name = Some(value),
+                    5 => info.model_name = Some(value),
+                    _ => {}
+                }
+            }
+            if more_follows != 0xff || next_object <= object {
+                break;
+            }
+            object = next_object;
+        }
+        Some(info)
+    };
+    timeout(wait, exchange).await.ok()?
+}
+
+/// BACnet/IP unicast Who-Is, an unconfirmed request every device answers
+/// with I-Am.
+fn who_is() -> Vec<u8> {
+    vec![0x81, 0x0a, 0x00, 0x08, 0x01, 0x00, 0x10, 0x08]
+}
+
+/// Unsigned value of a BACnet application tag at `pos`, and where the
+/// next tag starts.
+fn application_tag(packet: &[u8], pos: usize) -> Option<(u8, u32, usize)> {
+    let tag = *packet.get(pos)?;
+    let len = (tag & 0x07) as usize;
+    if tag & 0x08 != 0 || len > 4 {
+        return None;
+    }
+    let value = packet
+        .get(pos + 1..pos + 1 + len)?
+        .iter()
+        .fold(0u32, |value, &b| (value << 8) | b as u32);
+    Some((tag >> 4, value, pos + 1 + len))
+}
+
+pub fn parse_i_am(packet: &[u8]) -> Option<BacnetInfo> {
+    if packet.first() != Some(&0x81) || packet.get(4) != Some(&0x01) {
+        return None;
+    }
+    let control = *packet.get(5)?;
+    let mut pos = 6;
+    if control & 0x20 != 0 {
+        pos += 3 + *packet.get(pos + 2)? as usize;
+    }
+    if control & 0x08 != 0 {
+        pos += 3 + *packet.get(pos + 2)? as usize;
+    }
+    if control & 0x20 != 0 {
+        pos += 1; // hop count
+    }
+    if control & 0x80 != 0 || packet.get(pos..pos + 2)? != [0x10, 0x00] {
+        return None;
+    }
+
+    let (12, object, pos) = application_tag(packet, pos + 2)? else {
+        return None;
+    };
+    let (2, max_apdu, pos) = application_tag(packet, pos)? else {
+        return None;
+    };
+    let (9, _, pos) = application_tag(packet, pos)? else {
+        return None;
+    };
+    let (2, vendor_id, _) = application_tag(packet, pos)? else {
+        return None;
+    };
+    // Object type 8 is a device; the instance is the low 22 bits.
+    if object >> 22 != 8 {
+        return None;
+    }
+    Some(BacnetInfo {
+        device_instance: object & 0x3f_ffff,
+        vendor_id: vendor_id as u16,
+        max_apdu,
+    })
+}
+
+pub async fn bacnet(address: IpAddr, port: u16, wait: Duration) -> Option<BacnetInfo> {
+    let bind: IpAddr = match address {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((bind, 0)).await.ok()?;
+    socket.connect((address, port)).await.ok()?;
+    socket.send(&who_is()).await.ok()?;
+
+    let deadline = Instant::now() + wait;
+    let mut buffer = [0u8; 1500];
+    loop {
+        let len = timeout_at(deadline, socket.recv(&mut buffer))
+            .await
+            .ok()?
+            .ok()?;
+        if let Some(info) = parse_i_am(&buffer[..len]) {
+            return Some(info);
+        }
+    }
+}
+
+/// MQTT 3.1.1 CONNECT without credentials.
+fn mqtt_connect() -> Vec<u8> {
+    let client_id = b"ipscan-rs";
+    let mut variable = vec![0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x02, 0x00, 0x3c];
+    variable.extend_from_slice(&(client_id.len() as u16).to_be_bytes());
+    variable.extend_from_slice(client_id);
+    let mut packet = vec![0x10, variable.len() as u8];
+    packet.extend_from_slice(&variable);
+    packet
+}
+
+/// Connects without credentials and disconnects right after the CONNACK,
+/// without subscribing or publishing anything.
+pub async fn mqtt(address: IpAddr, port: u16, wait: Duration) -> Option<MqttInfo> {
+    let exchange = async {
+        let mut stream = TcpStream::connect((address, port)).await.ok()?;
+        stream.write_all(&mqtt_connect()).await.ok()?;
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack).await.ok()?;
+        if connack[..2] != [0x20, 0x02] {
+            return None;
+        }
+        if connack[3] == 0 {
+            let _ = stream.write_all(&[0xe0, 0x00]).await;
+        }
+        Some(MqttInfo {
+            return_code: connack[3],
+            anonymous: connack[3] == 0,
+        })
+    };
+    timeout(wait, exchange).await.ok()?
+}
+
+pub struct IndustrialFetcher {
+    config: Arc<ScannerConfig>,
+}
+
+impl IndustrialFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Fetcher for IndustrialFetcher {
+    fn id(&self) -> String {
+        "industrial".to_string()
+    }
+
+    fn name(&self) -> String {
+        "Industrial".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let address = subject.address();
+        let open_ports = ports::open_ports(subject);
+        let wait = Duration::from_millis(self.config.service_timeout_ms).min(MAX_WAIT);
+        let passive = self.config.industrial_passive_only;
+
+        // BACnet is UDP and answers Who-Is wherever it runs, so it does not
+        // wait for the port scan.
+        let (modbus, bacnet, mqtt) = tokio::join!(
+            async {
+                if open_ports.contains(&MODBUS_PORT) {
+                    modbus(address, MODBUS_PORT, !passive, wait).await
+                } else {
+                    None
+                }
+            },
+            bacnet(address, BACNET_PORT, wait),
+            async {
+                if !passive && open_ports.contains(&MQTT_PORT) {
+                    mqtt(address, MQTT_PORT, wait).await
+                } else {
+                    None
+                }
+            },
+        );
+
+        let info = IndustrialInfo {
+            modbus,
+            bacnet,
+            mqtt,
+        };
+        if info.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+        let summary = info.to_string();
+        subject.set_parameter(INDUSTRIAL.to_string(), info);
+        Ok(summary)
+    }
+}
@@ -0,0 +1,296 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::ports;
+use super::snmp::{read_tlv, tlv};
+use super::tls;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const LDAP_SERVERS: &str = "ldap.servers";
+
+/// LDAP ports, and whether they expect TLS right away.
+pub const LDAP_PORTS: [(u16, bool); 2] = [(389, false), (636, true)];
+
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+const ROOT_DSE_ATTRIBUTES: [&str; 6] = [
+    "defaultNamingContext",
+    "namingContexts",
+    "dnsHostName",
+    "supportedLDAPVersion",
+    "supportedSASLMechanisms",
+    "domainFunctionality",
+];
+
+const SEARCH_RESULT_ENTRY: u8 = 0x64;
+const SEARCH_RESULT_DONE: u8 = 0x65;
+
+pub type LdapServers = BTreeMap<u16, LdapInfo>;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LdapInfo {
+    pub tls: bool,
+    /// Whether the server let an anonymous client read the rootDSE.
+    pub anonymous_read: bool,
+    pub default_naming_context: Option<String>,
+    pub naming_contexts: Vec<String>,
+    pub dns_host_name: Option<String>,
+    pub ldap_versions: Vec<String>,
+    pub sasl_mechanisms: Vec<String>,
+    /// Active Directory domain functional level; only domain controllers
+    /// publish it.
+    pub domain_functionality: Option<u32>,
+}
+
+impl LdapInfo {
+    /// DNS domain of the default naming context, `DC=corp,DC=example`
+    /// giving `corp.example`.
+    pub fn domain(&self) -> Option<String> {
+        let context = self.default_naming_context.as_ref()?;
+        let labels: Vec<&str> = context
+            .split(',')
+            .filter_map(|rdn| {
+                let (key, value) = rdn.trim().split_once('=')?;
+                key.eq_ignore_ascii_case("DC").then_some(value)
+            })
+            .collect();
+        (!labels.is_empty()).then(|| labels.join("."))
+    }
+
+    pub fn is_domain_controller(&self) -> bool {
+        self.domain_functionality.is_some()
+    }
+}
+
+/// Windows Server release that introduced a functional level.
+fn functional_level_name(level: u32) -> String {
+    match level {
+        0 => "2000".to_string(),
+        1 => "2003 interim".to_string(),
+        2 => "2003".to_string(),
+        3 => "2008".to_string(),
+        4 => "2008 R2".to_string(),
+        5 => "2012".to_string(),
+        6 => "2012 R2".to_string(),
+        7 => "2016".to_string(),
+        10 => "2025".to_string(),
+        other => other.to_string(),
+    }
+}
+
+impl fmt::Display for LdapInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.tls { "LDAPS" } else { "LDAP" })?;
+        if !self.anonymous_read {
+            return f.write_str(" (anonymous rootDSE refused)");
+        }
+        let mut parts = Vec::new();
+        if self.is_domain_controller() {
+            parts.push("domain controller".to_string());
+        }
+        if let Some(host) = &self.dns_host_name {
+            parts.push(host.clone());
+        }
+        match self.domain() {
+            Some(domain) => parts.push(format!("domain {}", domain)),
+            None => parts.extend(self.naming_contexts.first().cloned()),
+        }
+        if let Some(level) = self.domain_functionality {
+            parts.push(format!("level {}", functional_level_name(level)));
+        }
+        if !parts.is_empty() {
+            write!(f, " {}", parts.join(", "))?;
+        }
+        if !self.ldap_versions.is_empty() {
+            write!(f, " (v{})", self.ldap_versions.join(", v"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Anonymous base search of the empty DN for the rootDSE attributes.
+fn root_dse_request() -> Vec<u8> {
+    let attributes: Vec<u8> = ROOT_DSE_ATTRIBUTES
+        .iter()
+        .flat_map(|name| tlv(0x04, name.as_bytes()))
+        .collect();
+    let search = [
+        tlv(0x04, b""),            // base object
+        tlv(0x0a, &[0]),           // scope: base
+        tlv(0x0a, &[0]),           // never dereference aliases
+        tlv(0x02, &[0]),           // no size limit
+        tlv(0x02, &[0]),           // no time limit
+        tlv(0x01, &[0]),           // types and values
+        tlv(0x87, b"objectClass"), // (objectClass=*)
+        tlv(0x30, &attributes),
+    ]
+    .concat();
+    tlv(0x30, &[tlv(0x02, &[1]), tlv(0x63, &search)].concat())
+}
+
+fn read_entry(info: &mut LdapInfo, entry: &[u8]) -> Option<()> {
+    let (_, _, rest) = read_tlv(entry)?; // object name
+    let (0x30, mut attributes, _) = read_tlv(rest)? else {
+        return None;
+    };
+    while let Some((_, attribute, rest)) = read_tlv(attributes) {
+        attributes = rest;
+        let (_, name, rest) = read_tlv(attribute)?;
+        let (_, mut set, _) = read_tlv(rest)?;
+        let mut values = Vec::new();
+        while let Some((_, value, rest)) = read_tlv(set) {
+            values.push(String::from_utf8_lossy(value).into_owned());
+            set = rest;
+        }
+
+        match String::from_utf8_lossy(name).to_ascii_lowercase().as_str() {
+            "defaultnamingcontext" => info.default_naming_context = values.into_iter().next(),
+            "namingcontexts" => info.naming_contexts = values,
+            "dnshostname" => info.dns_host_name = values.into_iter().next(),
+            "supportedldapversion" => info.ldap_versions = values,
+            "supportedsaslmechanisms" => info.sasl_mechanisms = values,
+            "domainfunctionality" => {
+                info.domain_functionality = values.first().and_then(|v| v.parse().ok())
+            }
+            _ => {}
+        }
+    }
+    info.anonymous_read = true;
+    Some(())
+}
+
+async fn search<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    tls: bool,
+    wait: Duration,
+) -> Option<LdapInfo> {
+    let exchange = async {
+        stream.write_all(&root_dse_request()).await.ok()?;
+        let mut info = LdapInfo {
+            tls,
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            // Entries and the final result can arrive in any split.
+            while let Some((tag, message, rest)) = read_tlv(&data) {
+                if tag != 0x30 {
+                    return None;
+                }
+                let (_, _, operation) = read_tlv(message)?; // message ID
+                let (operation, content, _) = read_tlv(operation)?;
+                match operation {
+                    SEARCH_RESULT_ENTRY => read_entry(&mut info, content)?,
+                    SEARCH_RESULT_DONE => return Some(info),
+                    _ => {}
+                }
+                let consumed = data.len() - rest.len();
+                data.drain(..consumed);
+            }
+            if data.len() > MAX_RESPONSE_BYTES {
+                return None;
+            }
+            let len = stream.read(&mut chunk).await.ok()?;
+            if len == 0 {
+                return None;
+            }
+            data.extend_from_slice(&chunk[..len]);
+        }
+    };
+    timeout(wait, exchange).await.ok()?
+}
+
+/// Reads the rootDSE without binding, over TLS when `tls` is set.
+pub async fn root_dse(address: IpAddr, port: u16, tls: bool, wait: Duration) -> Option<LdapInfo> {
+    if tls {
+        let mut stream = tls::connect(address, port, wait).await.ok()?;
+        search(&mut stream, true, wait).await
+    } else {
+        let mut stream = timeout(wait, TcpStream::connect((address, port)))
+            .await
+            .ok()?
+            .ok()?;
+        search(&mut stream, false, wait).await
+    }
+}
+
+pub struct LdapFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+}
+
+impl LdapFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        Self { config, sockets }
+    }
+}
+
+#[async_trait]
+impl Fetcher for LdapFetcher {
+    fn id(&self) -> String {
+        "ldap".to_string()
+    }
+
+    fn name(&self) -> String {
+        "LDAP".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let open_ports = ports::open_ports(subject);
+        let targets: Vec<(u16, bool)> = LDAP_PORTS
+            .into_iter()
+            .filter(|(port, _)| open_ports.contains(port))
+            .collect();
+        if targets.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        let mut searches = JoinSet::new();
+        for (port, tls) in targets {
+            let sockets = self.sockets.clone();
+            searches.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                (port, root_dse(address, port, tls, wait).await)
+            });
+        }
+
+        let mut servers = LdapServers::new();
+        while let Some(search) = searches.join_next().await {
+            if let Ok((port, Some(info))) = search {
+                servers.insert(port, info);
+            }
+        }
+
+        if servers.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = servers
+            .iter()
+            .map(|(port, info)| format!("{}: {}", port, info))
+            .collect::<Vec<_>>()
+            .join("; ");
+        subject.set_parameter(LDAP_SERVERS.to_string(), servers);
+        Ok(summary)
+    }
+}
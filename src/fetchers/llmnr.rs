@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::dns;
+use crate::errors::ScanError;
+
+pub const LLMNR_NAME: &str = "llmnr.name";
+
+pub const LLMNR_PORT: u16 = 5355;
+
+/// Reverse lookup of `address` over LLMNR, which Windows answers for its
+/// own addresses.
+pub async fn query(address: IpAddr, port: u16, wait: Duration) -> Option<String> {
+    let query = dns::query(
+        dns::next_id(),
+        &dns::reverse_name(address),
+        dns::TYPE_PTR,
+        dns::CLASS_IN,
+        false,
+    );
+    let response = dns::exchange(SocketAddr::new(address, port), &query, wait)
+        .await
+        .ok()?;
+    dns::Message::parse(&response)
+        .filter(|message| message.is_response())?
+        .answer_names(dns::TYPE_PTR)
+        .into_iter()
+        .next()
+}
+
+pub struct LlmnrFetcher {
+    config: Arc<ScannerConfig>,
+}
+
+impl LlmnrFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Fetcher for LlmnrFetcher {
+    fn id(&self) -> String {
+        "llmnr".to_string()
+    }
+
+    fn name(&self) -> String {
+        "LLMNR".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        match query(subject.address(), LLMNR_PORT, wait).await {
+            Some(name) => {
+                subject.set_parameter(LLMNR_NAME.to_string(), name.clone());
+                Ok(name)
+            }
+            None => Ok("[n/a]".to_string()),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::dns;
+use crate::errors::ScanError;
+
+pub const MDNS: &str = "mdns.info";
+
+pub const MDNS_PORT: u16 = 5353;
+
+const SERVICE_TYPES: &str = "_services._dns-sd._udp.local";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MdnsInfo {
+    /// The `.local` name the host answers to.
+    pub name: Option<String>,
+    /// Advertised DNS-SD service types such as `_ipp._tcp`.
+    pub services: Vec<String>,
+}
+
+impl fmt::Display for MdnsInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name.as_deref().unwrap_or("?"))?;
+        if !self.services.is_empty() {
+            write!(f, " [{}]", self.services.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Asks the host itself over unicast. A query from a port other than 5353
+/// is a "legacy unicast" query, which responders answer directly.
+async fn ask(server: SocketAddr, name: &str, wait: Duration) -> Option<dns::Message> {
+    let query = dns::query(dns::next_id(), name, dns::TYPE_PTR, dns::CLASS_IN, false);
+    let response = dns::exchange(server, &query, wait).await.ok()?;
+    dns::Message::parse(&response).filter(|message| message.is_response())
+}
+
+pub async fn query(address: IpAddr, port: u16, wait: Duration) -> Option<MdnsInfo> {
+    let server = SocketAddr::new(address, port);
+    let reverse_name = dns::reverse_name(address);
+    let (names, services) = tokio::join!(
+        ask(server, &reverse_name, wait),
+        ask(server, SERVICE_TYPES, wait)
+    );
+
+    let info = MdnsInfo {
+        name: names.and_then(|m| m.answer_names(dns::TYPE_PTR).into_iter().next()),
+        services: services
+            .map(|m| m.answer_names(dns::TYPE_PTR))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|service| service.trim_end_matches(".local").to_string())
+            .collect(),
+    };
+    (info.name.is_some() || !info.services.is_empty()).then_some(info)
+}
+
+pub struct MdnsFetcher {
+    config: Arc<ScannerConfig>,
+}
+
+impl MdnsFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Fetcher for MdnsFetcher {
+    fn id(&self) -> String {
+        "mdns".to_string()
+    }
+
+    fn name(&self) -> String {
+        "mDNS".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        match query(subject.address(), MDNS_PORT, wait).await {
+            Some(info) => {
+                let summary = info.to_string();
+                subject.set_parameter(MDNS.to_string(), info);
+                Ok(summary)
+            }
+            None => Ok("[n/a]".to_string()),
+        }
+    }
+}
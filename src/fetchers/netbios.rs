@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+use super::traits::Fetcher;
+use super::udp_payloads::NETBIOS_NBSTAT;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const NETBIOS: &str = "netbios.info";
+
+pub const NETBIOS_PORT: u16 = 137;
+
+const NBSTAT: u16 = 0x0021;
+const GROUP_FLAG: u16 = 0x8000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NetBiosName {
+    pub name: String,
+    pub suffix: u8,
+    pub group: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NetBiosInfo {
+    pub computer_name: Option<String>,
+    pub workgroup: Option<String>,
+    pub user: Option<String>,
+    pub mac: Option<String>,
+    pub names: Vec<NetBiosName>,
+}
+
+impl NetBiosInfo {
+    /// Parses a node status (NBSTAT) response.
+    pub fn parse(response: &[u8]) -> Option<Self> {
+        if response.get(6..8)? == [0, 0] {
+            return None;
+        }
+
+        // The answer repeats the queried name, possibly as a pointer.
+        let mut pos = 12;
+        if response.get(pos)? & 0xc0 == 0xc0 {
+            pos += 2;
+        } else {
+            while *response.get(pos)? != 0 {
+                pos += 1 + *response.get(pos)? as usize;
+            }
+            pos += 1;
+        }
+
+        let record_type = u16::from_be_bytes(response.get(pos..pos + 2)?.try_into().ok()?);
+        if record_type != NBSTAT {
+            return None;
+        }
+        // Type, class, TTL and data length come before the name count.
+        pos += 10;
+        let count = *response.get(pos)? as usize;
+        pos += 1;
+
+        let mut info = NetBiosInfo::default();
+        for _ in 0..count {
+            let entry = response.get(pos..pos + 18)?;
+            let flags = u16::from_be_bytes([entry[16], entry[17]]);
+            info.names.push(NetBiosName {
+                name: String::from_utf8_lossy(&entry[..15]).trim_end().to_string(),
+                suffix: entry[15],
+                group: flags & GROUP_FLAG != 0,
+            });
+            pos += 18;
+        }
+
+        // Samba reports an all-zero MAC.
+        if let Some(mac) = response.get(pos..pos + 6)
+            && mac.iter().any(|&b| b != 0)
+        {
+            info.mac = Some(
+                mac.iter()
+                    .map(|b| format!("{:02X}", b))
+                    .collect::<Vec<_>>()
+                    .join(":"),
+            );
+        }
+
+        let find = |suffix: u8, group: bool| {
+            info.names
+                .iter()
+                .find(|n| n.suffix == suffix && n.group == group)
+                .map(|n| n.name.clone())
+        };
+        info.computer_name = find(0x00, false);
+        info.workgroup = find(0x00, true);
+        // The messenger service registers the logged-in user under <03>,
+        // next to one for the computer itself.
+        info.user = info
+            .names
+            .iter()
+            .find(|n| n.suffix == 0x03 && !n.group && Some(&n.name) != info.computer_name.as_ref())
+            .map(|n| n.name.clone());
+        Some(info)
+    }
+}
+
+impl fmt::Display for NetBiosInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(workgroup) = &self.workgroup {
+            write!(f, "{}\\", workgroup)?;
+        }
+        f.write_str(self.computer_name.as_deref().unwrap_or("?"))?;
+        if let Some(user) = &self.user {
+            write!(f, " ({})", user)?;
+        }
+        Ok(())
+    }
+}
+
+pub async fn query(address: IpAddr, port: u16, wait: Duration) -> Option<NetBiosInfo> {
+    let bind: IpAddr = match address {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((bind, 0)).await.ok()?;
+    socket.connect((address, port)).await.ok()?;
+    socket.send(NETBIOS_NBSTAT).await.ok()?;
+
+    let mut buffer = [0u8; 1500];
+    let len = timeout(wait, socket.recv(&mut buffer)).await.ok()?.ok()?;
+    NetBiosInfo::parse(&buffer[..len])
+}
+
+pub struct NetBiosFetcher {
+    config: Arc<ScannerConfig>,
+}
+
+impl NetBiosFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Fetcher for NetBiosFetcher {
+    fn id(&self) -> String {
+        "netbios".to_string()
+    }
+
+    fn name(&self) -> String {
+        "NetBIOS".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        // NetBIOS name service only exists over IPv4.
+        if subject.address().is_ipv6() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let wait = Duration::from_millis(self.config.netbios_timeout_ms);
+        match query(subject.address(), NETBIOS_PORT, wait).await {
+            Some(info) => {
+                let summary = info.to_string();
+                subject.set_parameter(NETBIOS.to_string(), info);
+                Ok(summary)
+            }
+            None => Ok("[n/a]".to_string()),
+        }
+    }
+}
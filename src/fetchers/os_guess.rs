@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::ping::{PING_STATS, PingStats};
+use super::traits::Fetcher;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const OS_GUESS: &str = "os.guess";
+/// TTL of a TCP SYN-ACK, set by port scanners that see the raw reply.
+pub const TCP_TTL: &str = "tcp.ttl";
+
+const INITIAL_TTLS: [u8; 4] = [32, 64, 128, 255];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OsFamily {
+    Legacy,
+    UnixLike,
+    Windows,
+    NetworkDevice,
+}
+
+impl fmt::Display for OsFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OsFamily::Legacy => "Legacy (Win9x)",
+            OsFamily::UnixLike => "Linux/Unix/macOS",
+            OsFamily::Windows => "Windows",
+            OsFamily::NetworkDevice => "Network device",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OsGuess {
+    pub family: OsFamily,
+    pub initial_ttl: u8,
+    pub hops: u8,
+    pub confidence: Confidence,
+}
+
+impl OsGuess {
+    /// Guesses from a single observed TTL, assuming the sender used the
+    /// smallest common initial TTL that is not below it. A TTL of 0 never
+    /// reaches us, so it stands for an unknown one.
+    pub fn from_ttl(observed: u8) -> Option<Self> {
+        if observed == 0 {
+            return None;
+        }
+        let initial_ttl = INITIAL_TTLS
+            .into_iter()
+            .find(|&ttl| observed <= ttl)
+            .unwrap_or(255);
+        let hops = initial_ttl - observed;
+        let family = match initial_ttl {
+            32 => OsFamily::Legacy,
+            64 => OsFamily::UnixLike,
+            128 => OsFamily::Windows,
+            _ => OsFamily::NetworkDevice,
+        };
+
+        Some(Self {
+            family,
+            initial_ttl,
+            hops,
+            confidence: distance_confidence(hops).min(Confidence::Medium),
+        })
+    }
+
+    pub fn from_ttls(icmp_ttl: Option<u8>, tcp_ttl: Option<u8>) -> Option<Self> {
+        match (
+            icmp_ttl.and_then(Self::from_ttl),
+            tcp_ttl.and_then(Self::from_ttl),
+        ) {
+            (Some(icmp), Some(tcp)) if icmp.family == tcp.family => Some(Self {
+                confidence: distance_confidence(icmp.hops.max(tcp.hops)),
+                ..tcp
+            }),
+            // Firewalls and load balancers often answer ICMP themselves, so
+            // the TCP reply is the better witness of the host's own stack.
+            (Some(_), Some(tcp)) => Some(Self {
+                confidence: Confidence::Low,
+                ..tcp
+            }),
+            (icmp, tcp) => tcp.or(icmp),
+        }
+    }
+}
+
+impl fmt::Display for OsGuess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (TTL {}, {} hops, {})",
+            self.family, self.initial_ttl, self.hops, self.confidence
+        )
+    }
+}
+
+fn distance_confidence(hops: u8) -> Confidence {
+    match hops {
+        0..=10 => Confidence::High,
+        11..=20 => Confidence::Medium,
+        _ => Confidence::Low,
+    }
+}
+
+pub struct OsGuessFetcher;
+
+impl OsGuessFetcher {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for OsGuessFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Fetcher for OsGuessFetcher {
+    fn id(&self) -> String {
+        "os_guess".to_string()
+    }
+
+    fn name(&self) -> String {
+        "OS Guess".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let icmp_ttl = subject
+            .get_parameter::<PingStats>(PING_STATS)
+            .and_then(|stats| stats.ttl);
+        let tcp_ttl = subject.get_parameter::<u8>(TCP_TTL).copied();
+
+        match OsGuess::from_ttls(icmp_ttl, tcp_ttl) {
+            Some(guess) => {
+                subject.set_parameter(OS_GUESS.to_string(), guess);
+                Ok(guess.to_string())
+            }
+            None => Ok("[n/a]".to_string()),
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Duration;
-use surge_ping::{Client, Config, PingIdentifier, PingSequence};
+use surge_ping::{Client, Config, IcmpPacket, PingIdentifier, PingSequence};
 use tokio::time::timeout;
 
 use super::traits::Fetcher;
@@ -10,6 +11,74 @@ use crate::core::result::ResultType;
 use crate::core::subject::ScanningSubject;
 use crate::errors::ScanError;
 
+/// Subject/result parameter under which the [`PingStats`] of a host are stored.
+pub const PING_STATS: &str = "ping.stats";
+
+/// Round-trip statistics collected over all echo requests sent to a host.
+///
+/// Times are in milliseconds. They are only meaningful when `received > 0`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PingStats {
+    pub sent: u8,
+    pub received: u8,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub avg_ms: f64,
+    pub stddev_ms: f64,
+    /// Mean difference between consecutive round-trip times.
+    pub jitter_ms: f64,
+    pub loss_percent: f64,
+    /// TTL (hop limit for IPv6) of the last echo reply.
+    pub ttl: Option<u8>,
+}
+
+impl PingStats {
+    pub fn from_samples(sent: u8, rtts: &[Duration], ttl: Option<u8>) -> Self {
+        let received = rtts.len().min(sent as usize) as u8;
+        let loss_percent = if sent == 0 {
+            0.0
+        } else {
+            (sent - received) as f64 * 100.0 / sent as f64
+        };
+
+        if rtts.is_empty() {
+            return Self {
+                sent,
+                received,
+                loss_percent,
+                ttl,
+                ..Default::default()
+            };
+        }
+
+        let ms: Vec<f64> = rtts.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        let count = ms.len() as f64;
+        let avg_ms = ms.iter().sum::<f64>() / count;
+        let variance = ms.iter().map(|v| (v - avg_ms).powi(2)).sum::<f64>() / count;
+        let jitter_ms = if ms.len() > 1 {
+            ms.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>() / (count - 1.0)
+        } else {
+            0.0
+        };
+
+        Self {
+            sent,
+            received,
+            min_ms: ms.iter().copied().fold(f64::INFINITY, f64::min),
+            max_ms: ms.iter().copied().fold(0.0, f64::max),
+            avg_ms,
+            stddev_ms: variance.sqrt(),
+            jitter_ms,
+            loss_percent,
+            ttl,
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.received > 0
+    }
+}
+
 pub struct PingFetcher {
     config: Arc<ScannerConfig>,
 }
@@ -20,6 +89,15 @@ impl PingFetcher {
     }
 }
 
+fn reply_ttl(packet: &IcmpPacket) -> Option<u8> {
+    match packet {
+        IcmpPacket::V4(packet) => packet.get_ttl(),
+        // surge-ping does not expose the hop limit of replies and always
+        // reports 0 here.
+        IcmpPacket::V6(_) => None,
+    }
+}
+
 #[async_trait]
 impl Fetcher for PingFetcher {
     fn id(&self) -> String {
@@ -34,27 +112,30 @@ impl Fetcher for PingFetcher {
         let client =
             Client::new(&Config::default()).map_err(|e| ScanError::PingFailed(e.to_string()))?;
 
-        let mut total_time = Duration::ZERO;
-        let mut successful_pings = 0;
+        let mut rtts = Vec::new();
+        let mut ttl = None;
 
         for seq in 0..self.config.ping_count {
             let mut pinger = client.pinger(subject.address(), PingIdentifier(0)).await;
             pinger.timeout(Duration::from_millis(self.config.ping_timeout_ms));
 
-            if let Ok(Ok((_, duration))) = timeout(
+            if let Ok(Ok((packet, duration))) = timeout(
                 Duration::from_millis(self.config.ping_timeout_ms),
                 pinger.ping(PingSequence(seq as u16), &[]),
             )
             .await
             {
-                total_time += duration;
-                successful_pings += 1;
+                rtts.push(duration);
+                ttl = reply_ttl(&packet).or(ttl);
             }
         }
 
-        if successful_pings > 0 {
+        let stats = PingStats::from_samples(self.config.ping_count, &rtts, ttl);
+        subject.set_parameter(PING_STATS.to_string(), stats);
+
+        if stats.is_alive() {
             subject.set_result_type(ResultType::Alive);
-            let avg_time = total_time / successful_pings;
+            let avg_time = rtts.iter().sum::<Duration>() / rtts.len() as u32;
 
             if self.config.adapt_port_timeout {
                 let adapted =
@@ -1,23 +1,165 @@
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::net::TcpStream;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use tokio::time::timeout;
+use tracing::warn;
 
+use super::os_guess::TCP_TTL;
+use super::syn_scan::SynScanner;
 use super::traits::Fetcher;
-use crate::config::ScannerConfig;
-use crate::core::port_iterator::PortIterator;
+use super::udp_payloads;
+use crate::config::{PortScanMode, ScannerConfig};
+use crate::core::port_iterator::{PortIterator, Protocol};
 use crate::core::result::ResultType;
 use crate::core::subject::ScanningSubject;
 use crate::errors::ScanError;
 
+pub const PORT_STATES: &str = "ports.states";
+pub const UDP_PORT_STATES: &str = "ports.udp_states";
+
+pub type PortStates = BTreeMap<u16, PortState>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortState {
+    Open,
+    /// The host answered with a RST.
+    Closed,
+    /// No answer, or an ICMP unreachable from the path.
+    Filtered,
+    /// No answer to a UDP probe. Services often ignore unexpected
+    /// datagrams, so this cannot be told apart from a filter.
+    #[serde(rename = "open|filtered")]
+    OpenFiltered,
+    Error,
+}
+
+impl PortState {
+    pub fn from_connect_error(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::ConnectionRefused => PortState::Closed,
+            io::ErrorKind::TimedOut
+            | io::ErrorKind::HostUnreachable
+            | io::ErrorKind::NetworkUnreachable => PortState::Filtered,
+            _ => PortState::Error,
+        }
+    }
+}
+
+impl fmt::Display for PortState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PortState::Open => "open",
+            PortState::Closed => "closed",
+            PortState::Filtered => "filtered",
+            PortState::OpenFiltered => "open|filtered",
+            PortState::Error => "error",
+        };
+        f.write_str(name)
+    }
+}
+
+pub fn ports_in_state(states: &PortStates, state: PortState) -> Vec<u16> {
+    states
+        .iter()
+        .filter(|(_, s)| **s == state)
+        .map(|(port, _)| *port)
+        .collect()
+}
+
+/// Open TCP ports found by `PortsFetcher` earlier in the scan of `subject`.
+pub fn open_ports(subject: &ScanningSubject) -> Vec<u16> {
+    subject
+        .get_parameter::<PortStates>(PORT_STATES)
+        .map(|states| ports_in_state(states, PortState::Open))
+        .unwrap_or_default()
+}
+
+/// Permits for the sockets in flight, shared by the port scan and the
+/// service fetchers of a scan.
+pub fn socket_limit(config: &ScannerConfig) -> Arc<Semaphore> {
+    Arc::new(Semaphore::new(config.max_sockets.max(1)))
+}
+
 pub struct PortsFetcher {
     config: Arc<ScannerConfig>,
+    // Shared by every host of a scan, and by the service fetchers, so the
+    // total number of sockets in flight stays bounded regardless of
+    // `max_threads`.
+    sockets: Arc<Semaphore>,
+    syn_scanner: Option<Arc<SynScanner>>,
 }
 
 impl PortsFetcher {
     pub fn new(config: Arc<ScannerConfig>) -> Self {
-        Self { config }
+        let sockets = socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    /// Shares `sockets` with the other fetchers of a scan.
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        let syn_scanner = match config.port_scan_mode {
+            PortScanMode::Connect => None,
+            PortScanMode::Syn => match SynScanner::new() {
+                Ok(scanner) => Some(Arc::new(scanner)),
+                Err(e) => {
+                    warn!("SYN scan unavailable, using connect scan: {}", e);
+                    None
+                }
+            },
+        };
+
+        Self {
+            config,
+            sockets,
+            syn_scanner,
+        }
+    }
+
+    pub fn is_syn_scan(&self) -> bool {
+        self.syn_scanner.is_some()
+    }
+}
+
+async fn connect_probe(address: IpAddr, port: u16, wait: Duration) -> PortState {
+    match timeout(wait, TcpStream::connect((address, port))).await {
+        Ok(Ok(_)) => PortState::Open,
+        Ok(Err(e)) => PortState::from_connect_error(&e),
+        Err(_) => PortState::Filtered,
+    }
+}
+
+async fn udp_probe(address: IpAddr, port: u16, wait: Duration) -> PortState {
+    let bind: IpAddr = match address {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = match UdpSocket::bind((bind, 0)).await {
+        Ok(socket) => socket,
+        Err(_) => return PortState::Error,
+    };
+    if let Err(e) = socket.connect((address, port)).await {
+        return PortState::from_connect_error(&e);
+    }
+    if let Err(e) = socket.send(udp_payloads::payload_for(port)).await {
+        return PortState::from_connect_error(&e);
+    }
+
+    // On a connected socket an ICMP port unreachable surfaces as a refused
+    // receive, which makes the port closed rather than silent.
+    let mut buffer = [0u8; 1500];
+    match timeout(wait, socket.recv(&mut buffer)).await {
+        Ok(Ok(_)) => PortState::Open,
+        Ok(Err(e)) => PortState::from_connect_error(&e),
+        Err(_) => PortState::OpenFiltered,
     }
 }
 
@@ -32,36 +174,99 @@ impl Fetcher for PortsFetcher {
     }
 
     async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
-        let port_iterator =
-            PortIterator::new(&self.config.port_string).map_err(ScanError::PortScanFailed)?;
+        let tcp_ports = PortIterator::for_protocol(&self.config.port_string, Protocol::Tcp)
+            .map_err(ScanError::PortScanFailed)?;
+        let udp_ports = PortIterator::for_protocol(&self.config.port_string, Protocol::Udp)
+            .map_err(ScanError::PortScanFailed)?;
 
-        if port_iterator.is_empty() {
+        if tcp_ports.is_empty() && udp_ports.is_empty() {
             return Ok("[n/s]".to_string());
         }
 
-        let mut open_ports = Vec::new();
         let timeout_ms = subject.adapted_port_timeout();
+        let address = subject.address();
+        let host_limit = Arc::new(Semaphore::new(self.config.max_port_concurrency.max(1)));
+        let mut probes = JoinSet::new();
 
-        for port in port_iterator {
-            let addr = format!("{}:{}", subject.address(), port);
+        let ports = tcp_ports
+            .map(|port| (Protocol::Tcp, port))
+            .chain(udp_ports.map(|port| (Protocol::Udp, port)));
+        for (protocol, port) in ports {
+            let host_permit = host_limit.clone().acquire_owned().await.unwrap();
+            let sockets = self.sockets.clone();
+            let syn_scanner = self.syn_scanner.clone();
 
-            if let Ok(Ok(_)) =
-                timeout(Duration::from_millis(timeout_ms), TcpStream::connect(&addr)).await
-            {
-                open_ports.push(port);
-            }
+            probes.spawn(async move {
+                let _host_permit = host_permit;
+                let _socket = sockets.acquire_owned().await.unwrap();
+                let wait = Duration::from_millis(timeout_ms);
+
+                let (state, ttl) = match (protocol, syn_scanner, address) {
+                    (Protocol::Udp, _, _) => (udp_probe(address, port, wait).await, None),
+                    (Protocol::Tcp, Some(scanner), IpAddr::V4(target)) => {
+                        match scanner.probe(target, port, wait).await {
+                            Ok(Some(reply)) => (reply.state, Some(reply.ttl)),
+                            Ok(None) => (PortState::Filtered, None),
+                            Err(_) => (PortState::Error, None),
+                        }
+                    }
+                    (Protocol::Tcp, _, _) => (connect_probe(address, port, wait).await, None),
+                };
+                (protocol, port, state, ttl)
+            });
+        }
+
+        let mut tcp_states = PortStates::new();
+        let mut udp_states = PortStates::new();
+        let mut reply_ttl = None;
+        while let Some(probe) = probes.join_next().await {
+            let (protocol, port, state, ttl) =
+                probe.map_err(|e| ScanError::PortScanFailed(e.to_string()))?;
+            match protocol {
+                Protocol::Tcp => tcp_states.insert(port, state),
+                Protocol::Udp => udp_states.insert(port, state),
+            };
+            reply_ttl = reply_ttl.or(ttl);
+        }
+
+        if let Some(ttl) = reply_ttl {
+            subject.set_parameter(TCP_TTL.to_string(), ttl);
+        }
+
+        let open_tcp = ports_in_state(&tcp_states, PortState::Open);
+        let open_udp = ports_in_state(&udp_states, PortState::Open);
+        let has_closed = tcp_states
+            .values()
+            .chain(udp_states.values())
+            .any(|s| *s == PortState::Closed);
+        subject.set_parameter(PORT_STATES.to_string(), tcp_states);
+        if !udp_states.is_empty() {
+            subject.set_parameter(UDP_PORT_STATES.to_string(), udp_states);
         }
 
-        if !open_ports.is_empty() {
+        if !open_tcp.is_empty() || !open_udp.is_empty() {
             subject.set_result_type(ResultType::WithPorts);
-            Ok(format_ports(&open_ports))
+            // Same syntax as the port string: unprefixed ports are TCP.
+            let mut formatted = format_ports(&open_tcp);
+            if !open_udp.is_empty() {
+                if !formatted.is_empty() {
+                    formatted.push(',');
+                }
+                formatted.push_str(&format!("U:{}", format_ports(&open_udp)));
+            }
+            Ok(formatted)
         } else {
+            // A RST or an ICMP port unreachable still proves the host is up,
+            // even if it ignored pings.
+            if has_closed {
+                subject.set_result_type(ResultType::Alive);
+            }
             Ok("[n/a]".to_string())
         }
     }
 }
 
-fn format_ports(ports: &[u16]) -> String {
+pub fn format_ports(ports: &[u16]) -> String {
     if ports.is_empty() {
         return String::new();
     }
@@ -0,0 +1,353 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::ports;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+use crate::network_utils;
+
+pub const PRINTERS: &str = "printers";
+
+pub const IPP_PORT: u16 = 631;
+pub const PJL_PORT: u16 = 9100;
+pub const LPD_PORT: u16 = 515;
+
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+// Tried in order: IPP Everywhere printers use the first, CUPS the second.
+const IPP_PATHS: [&str; 2] = ["/ipp/print", "/"];
+const REQUESTED_ATTRIBUTES: [&str; 5] = [
+    "printer-make-and-model",
+    "printer-state",
+    "printer-state-message",
+    "printer-device-id",
+    "printer-info",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PrinterProtocol {
+    Ipp,
+    Pjl,
+    Lpd,
+}
+
+impl fmt::Display for PrinterProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PrinterProtocol::Ipp => "IPP",
+            PrinterProtocol::Pjl => "PJL",
+            PrinterProtocol::Lpd => "LPD",
+        })
+    }
+}
+
+pub type Printers = BTreeMap<u16, PrinterInfo>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrinterInfo {
+    pub protocol: PrinterProtocol,
+    pub make_and_model: Option<String>,
+    pub status: Option<String>,
+    pub serial: Option<String>,
+}
+
+impl PrinterInfo {
+    fn new(protocol: PrinterProtocol) -> Self {
+        Self {
+            protocol,
+            make_and_model: None,
+            status: None,
+            serial: None,
+        }
+    }
+}
+
+impl fmt::Display for PrinterInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.protocol)?;
+        if let Some(model) = &self.make_and_model {
+            write!(f, " {}", model)?;
+        }
+        if let Some(status) = &self.status {
+            write!(f, " ({})", status)?;
+        }
+        if let Some(serial) = &self.serial {
+            write!(f, " SN {}", serial)?;
+        }
+        Ok(())
+    }
+}
+
+/// Value of the first of `keys` in an IEEE 1284 device ID such as
+/// `MFG:HP;MDL:LaserJet M404;SN:PHB1234;`.
+pub fn device_id_field(device_id: &str, keys: &[&str]) -> Option<String> {
+    device_id.split(';').find_map(|field| {
+        let (key, value) = field.split_once(':')?;
+        let value = value.trim();
+        (keys.iter().any(|k| key.trim().eq_ignore_ascii_case(k)) && !value.is_empty())
+            .then(|| value.to_string())
+    })
+}
+
+async fn request(address: IpAddr, port: u16, request: &[u8], wait: Duration) -> Option<Vec<u8>> {
+    let mut stream = timeout(wait, TcpStream::connect((address, port)))
+        .await
+        .ok()?
+        .ok()?;
+    timeout(wait, stream.write_all(request)).await.ok()?.ok()?;
+    let mut buffer = vec![0u8; MAX_RESPONSE_BYTES];
+    let len = network_utils::read_available(&mut stream, &mut buffer, wait).await;
+    buffer.truncate(len);
+    Some(buffer)
+}
+
+fn ipp_attribute(body: &mut Vec<u8>, tag: u8, name: &str, value: &str) {
+    body.push(tag);
+    body.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    body.extend_from_slice(name.as_bytes());
+    body.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    body.extend_from_slice(value.as_bytes());
+}
+
+/// HTTP request carrying an IPP Get-Printer-Attributes operation.
+fn get_printer_attributes(host: &str, path: &str) -> Vec<u8> {
+    let mut body = vec![0x02, 0x00, 0x00, 0x0b, 0, 0, 0, 1, 0x01];
+    ipp_attribute(&mut body, 0x47, "attributes-charset", "utf-8");
+    ipp_attribute(&mut body, 0x48, "attributes-natural-language", "en");
+    ipp_attribute(
+        &mut body,
+        0x45,
+        "printer-uri",
+        &format!("ipp://{}{}", host, path),
+    );
+    for (i, attribute) in REQUESTED_ATTRIBUTES.iter().enumerate() {
+        // Further values of the same attribute go without a name.
+        let name = if i == 0 { "requested-attributes" } else { "" };
+        ipp_attribute(&mut body, 0x44, name, attribute);
+    }
+    body.push(0x03);
+
+    // HTTP/1.0 keeps servers from answering with a chunked body.
+    let mut request = format!(
+        "POST {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/ipp\r\nContent-Length: {}\r\n\r\n",
+        path,
+        host,
+        body.len()
+    )
+    .into_bytes();
+    request.extend_from_slice(&body);
+    request
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IppValue {
+    Text(String),
+    Integer(i32),
+}
+
+/// Status code and first value of each attribute of an IPP response.
+pub fn parse_ipp(body: &[u8]) -> Option<(u16, BTreeMap<String, IppValue>)> {
+    let status = u16::from_be_bytes(body.get(2..4)?.try_into().ok()?);
+    let mut attributes = BTreeMap::new();
+    let mut pos = 8;
+    while let Some(&tag) = body.get(pos) {
+        pos += 1;
+        if tag == 0x03 {
+            break;
+        }
+        if tag < 0x10 {
+            continue; // group delimiter
+        }
+        let name_len = u16::from_be_bytes(body.get(pos..pos + 2)?.try_into().ok()?) as usize;
+        let name = body.get(pos + 2..pos + 2 + name_len)?;
+        pos += 2 + name_len;
+        let value_len = u16::from_be_bytes(body.get(pos..pos + 2)?.try_into().ok()?) as usize;
+        let value = body.get(pos + 2..pos + 2 + value_len)?;
+        pos += 2 + value_len;
+
+        if name.is_empty() {
+            continue; // additional value
+        }
+        let value = match tag {
+            0x21 | 0x23 if value_len == 4 => {
+                IppValue::Integer(i32::from_be_bytes(value.try_into().ok()?))
+            }
+            0x41..=0x49 => IppValue::Text(String::from_utf8_lossy(value).into_owned()),
+            _ => continue,
+        };
+        attributes
+            .entry(String::from_utf8_lossy(name).into_owned())
+            .or_insert(value);
+    }
+    Some((status, attributes))
+}
+
+pub async fn ipp(address: IpAddr, port: u16, wait: Duration) -> Option<PrinterInfo> {
+    let host = match address {
+        IpAddr::V4(v4) => format!("{}:{}", v4, port),
+        IpAddr::V6(v6) => format!("[{}]:{}", v6, port),
+    };
+    for path in IPP_PATHS {
+        let response = request(address, port, &get_printer_attributes(&host, path), wait).await?;
+        let Some(start) = response.windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+        let Some((status, attributes)) = parse_ipp(&response[start + 4..]) else {
+            continue;
+        };
+        // 0x0000 to 0x00ff are successful statuses.
+        if status > 0x00ff {
+            continue;
+        }
+
+        let text = |name: &str| match attributes.get(name) {
+            Some(IppValue::Text(text)) if !text.trim().is_empty() => Some(text.trim().to_string()),
+            _ => None,
+        };
+        let device_id = text("printer-device-id").unwrap_or_default();
+        let mut printer = PrinterInfo::new(PrinterProtocol::Ipp);
+        printer.make_and_model = text("printer-make-and-model")
+            .or_else(|| device_id_field(&device_id, &["MDL", "MODEL"]))
+            .or_else(|| text("printer-info"));
+        printer.serial = device_id_field(&device_id, &["SN", "SERN", "SERIALNUMBER"]);
+        let state = match attributes.get("printer-state") {
+            Some(IppValue::Integer(3)) => Some("idle"),
+            Some(IppValue::Integer(4)) => Some("processing"),
+            Some(IppValue::Integer(5)) => Some("stopped"),
+            _ => None,
+        };
+        printer.status = match (state, text("printer-state-message")) {
+            (Some(state), Some(message)) => Some(format!("{}: {}", state, message)),
+            (Some(state), None) => Some(state.to_string()),
+            (None, message) => message,
+        };
+        return Some(printer);
+    }
+    None
+}
+
+/// Value of a PJL `KEY=value` line, unquoted.
+fn pjl_value(reply: &str, key: &str) -> Option<String> {
+    reply.lines().find_map(|line| {
+        let (name, value) = line.trim().split_once('=')?;
+        (name.eq_ignore_ascii_case(key)).then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Asks a JetDirect port for its PJL identity and status. PJL printers do
+/// not print these commands, but a raw port without PJL would.
+pub async fn pjl(address: IpAddr, port: u16, wait: Duration) -> Option<PrinterInfo> {
+    let query = b"\x1b%-12345X@PJL\r\n@PJL INFO ID\r\n@PJL INFO STATUS\r\n\x1b%-12345X";
+    let response = request(address, port, query, wait).await?;
+    let reply = String::from_utf8_lossy(&response);
+    if !reply.contains("@PJL") {
+        return None;
+    }
+
+    let mut printer = PrinterInfo::new(PrinterProtocol::Pjl);
+    let mut lines = reply.lines().map(str::trim);
+    if lines.any(|line| line.eq_ignore_ascii_case("@PJL INFO ID")) {
+        printer.make_and_model = lines
+            .next()
+            .map(|id| id.trim_matches(['"', '\x0c']).trim().to_string())
+            .filter(|id| !id.is_empty());
+    }
+    printer.status = pjl_value(&reply, "DISPLAY").filter(|status| !status.is_empty());
+    Some(printer)
+}
+
+/// Asks for the short state of the default `lp` queue. Many daemons only
+/// talk to privileged source ports, so silence still means LPD.
+pub async fn lpd(address: IpAddr, port: u16, wait: Duration) -> Option<PrinterInfo> {
+    let response = request(address, port, b"\x03lp\n", wait).await?;
+    let mut printer = PrinterInfo::new(PrinterProtocol::Lpd);
+    printer.status = String::from_utf8_lossy(&response)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string);
+    Some(printer)
+}
+
+pub struct PrinterFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+}
+
+impl PrinterFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        Self { config, sockets }
+    }
+}
+
+#[async_trait]
+impl Fetcher for PrinterFetcher {
+    fn id(&self) -> String {
+        "printer".to_string()
+    }
+
+    fn name(&self) -> String {
+        "Printer".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let printer_ports: Vec<u16> = ports::open_ports(subject)
+            .into_iter()
+            .filter(|port| [IPP_PORT, PJL_PORT, LPD_PORT].contains(port))
+            .collect();
+        if printer_ports.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        let mut probes = JoinSet::new();
+        for port in printer_ports {
+            let sockets = self.sockets.clone();
+            probes.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                let printer = match port {
+                    IPP_PORT => ipp(address, port, wait).await,
+                    PJL_PORT => pjl(address, port, wait).await,
+                    _ => lpd(address, port, wait).await,
+                };
+                (port, printer)
+            });
+        }
+
+        let mut printers = Printers::new();
+        while let Some(probe) = probes.join_next().await {
+            if let Ok((port, Some(printer))) = probe {
+                printers.insert(port, printer);
+            }
+        }
+
+        if printers.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = printers
+            .iter()
+            .map(|(port, printer)| format!("{}: {}", port, printer))
+            .collect::<Vec<_>>()
+            .join("; ");
+        subject.set_parameter(PRINTERS.to_string(), printers);
+        Ok(summary)
+    }
+}
@@ -1,11 +1,36 @@
+use super::banner::BannerFetcher;
+use super::capabilities::CapabilityFetcher;
+use super::database::DatabaseFetcher;
+use super::dns_server::DnsServerFetcher;
 use super::hostname::HostnameFetcher;
+use super::http::HttpFetcher;
+use super::industrial::IndustrialFetcher;
+use super::ldap::LdapFetcher;
+use super::llmnr::LlmnrFetcher;
 use super::mac::MacFetcher;
+use super::mdns::MdnsFetcher;
+use super::netbios::NetBiosFetcher;
+use super::os_guess::OsGuessFetcher;
 use super::ping::PingFetcher;
-use super::ports::PortsFetcher;
+use super::ports::{self, PortsFetcher};
+use super::printer::PrinterFetcher;
+use super::remote_desktop::RemoteDesktopFetcher;
+use super::service_fingerprint::ServiceFingerprintFetcher;
+use super::smb::SmbFetcher;
+use super::snmp::SnmpFetcher;
+use super::ssdp::SsdpFetcher;
+use super::ssh::SshFetcher;
+use super::tls_cert::TlsCertFetcher;
 use super::traits::Fetcher;
+use super::web_tech::WebTechFetcher;
 use crate::config::ScannerConfig;
 use std::sync::Arc;
 
+/// Selected unless the user picks otherwise. NetBIOS costs one datagram and
+/// names Windows machines that have no PTR record. The others actively
+/// probe the services of a host and run only on request.
+const DEFAULT_FETCHERS: [&str; 6] = ["ping", "netbios", "hostname", "ports", "mac", "os_guess"];
+
 pub struct FetcherRegistry {
     fetchers: Vec<Arc<dyn Fetcher>>,
     selected: Vec<usize>,
@@ -24,11 +49,95 @@ impl FetcherRegistry {
     }
 
     pub fn register_default_fetchers(&mut self, config: Arc<ScannerConfig>) {
+        let sockets = ports::socket_limit(&config);
         self.register(Arc::new(PingFetcher::new(config.clone())));
-        self.register(Arc::new(HostnameFetcher::new()));
-        self.register(Arc::new(PortsFetcher::new(config.clone())));
+        // Before the hostname fetcher, which falls back to the names these
+        // discover when reverse DNS fails.
+        self.register(Arc::new(NetBiosFetcher::new(config.clone())));
+        self.register(Arc::new(MdnsFetcher::new(config.clone())));
+        self.register(Arc::new(LlmnrFetcher::new(config.clone())));
+        self.register(Arc::new(HostnameFetcher::with_config(&config)));
+        self.register(Arc::new(PortsFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
         self.register(Arc::new(MacFetcher::new()));
-        self.selected = (0..self.fetchers.len()).collect();
+        self.register(Arc::new(OsGuessFetcher::new()));
+        self.register(Arc::new(BannerFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(HttpFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(TlsCertFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(SshFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(SnmpFetcher::new(config.clone())));
+        self.register(Arc::new(SsdpFetcher::new(config.clone())));
+        self.register(Arc::new(SmbFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(DatabaseFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(CapabilityFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(RemoteDesktopFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(PrinterFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(IndustrialFetcher::new(config.clone())));
+        self.register(Arc::new(LdapFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(DnsServerFetcher::new(config.clone())));
+        self.register(Arc::new(ServiceFingerprintFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.register(Arc::new(WebTechFetcher::with_sockets(
+            config.clone(),
+            sockets.clone(),
+        )));
+        self.select(&DEFAULT_FETCHERS.map(String::from));
+    }
+
+    pub fn fetchers(&self) -> &[Arc<dyn Fetcher>] {
+        &self.fetchers
+    }
+
+    /// Selects the fetchers with the given ids. They always run in
+    /// registration order, since later fetchers build on earlier ones.
+    pub fn select(&mut self, ids: &[String]) {
+        self.selected = self
+            .fetchers
+            .iter()
+            .enumerate()
+            .filter(|(_, fetcher)| ids.contains(&fetcher.id()))
+            .map(|(i, _)| i)
+            .collect();
+    }
+
+    pub fn is_selected(&self, id: &str) -> bool {
+        self.selected
+            .iter()
+            .any(|&i| self.fetchers.get(i).is_some_and(|f| f.id() == id))
     }
 
     pub fn get_selected_fetchers(&self) -> Vec<Arc<dyn Fetcher>> {
@@ -0,0 +1,352 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::banner::{BANNERS, Banners};
+use super::ports;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const REMOTE_DESKTOPS: &str = "remote.desktops";
+
+pub const RDP_PORTS: [u16; 1] = [3389];
+pub const VNC_PORTS: [u16; 4] = [5900, 5901, 5902, 5903];
+
+const PROTOCOL_RDP: u32 = 0x0;
+const PROTOCOL_SSL: u32 = 0x1;
+const PROTOCOL_HYBRID: u32 = 0x2;
+const PROTOCOL_HYBRID_EX: u32 = 0x8;
+const TYPE_RDP_NEG_RSP: u8 = 0x02;
+const TYPE_RDP_NEG_FAILURE: u8 = 0x03;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RdpSecurity {
+    /// Standard RDP security, without TLS.
+    Rdp,
+    Tls,
+    Nla,
+    NlaEx,
+}
+
+impl RdpSecurity {
+    fn from_protocol(protocol: u32) -> Self {
+        match protocol {
+            PROTOCOL_RDP => RdpSecurity::Rdp,
+            PROTOCOL_SSL => RdpSecurity::Tls,
+            PROTOCOL_HYBRID_EX => RdpSecurity::NlaEx,
+            _ => RdpSecurity::Nla,
+        }
+    }
+}
+
+impl fmt::Display for RdpSecurity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RdpSecurity::Rdp => "RDP",
+            RdpSecurity::Tls => "TLS",
+            RdpSecurity::Nla => "NLA",
+            RdpSecurity::NlaEx => "NLA-EX",
+        })
+    }
+}
+
+pub type RemoteDesktops = BTreeMap<u16, RemoteDesktop>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "protocol")]
+pub enum RemoteDesktop {
+    Rdp {
+        /// What the server picks when offered everything.
+        selected: RdpSecurity,
+        /// Protocols the server accepts when offered only that one.
+        accepted: Vec<RdpSecurity>,
+        nla_required: bool,
+    },
+    Vnc {
+        version: String,
+        security_types: Vec<String>,
+        no_auth: bool,
+    },
+}
+
+impl RemoteDesktop {
+    /// RDP that lets anyone reach the login screen, or VNC without a password.
+    pub fn is_weak(&self) -> bool {
+        match self {
+            RemoteDesktop::Rdp { nla_required, .. } => !nla_required,
+            RemoteDesktop::Vnc { no_auth, .. } => *no_auth,
+        }
+    }
+}
+
+impl fmt::Display for RemoteDesktop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemoteDesktop::Rdp {
+                selected,
+                accepted,
+                nla_required,
+            } => {
+                write!(f, "RDP {}", selected)?;
+                if !nla_required {
+                    let accepted: Vec<String> = accepted.iter().map(|a| a.to_string()).collect();
+                    write!(f, " (NLA not required: {})", accepted.join(", "))?;
+                }
+                Ok(())
+            }
+            RemoteDesktop::Vnc {
+                version,
+                security_types,
+                no_auth,
+            } => {
+                write!(f, "VNC {} [{}]", version, security_types.join(", "))?;
+                if *no_auth {
+                    f.write_str(" NO AUTHENTICATION")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn connect(address: IpAddr, port: u16, wait: Duration) -> Option<TcpStream> {
+    timeout(wait, TcpStream::connect((address, port)))
+        .await
+        .ok()?
+        .ok()
+}
+
+/// X.224 Connection Request carrying an RDP negotiation request.
+fn connection_request(protocols: u32) -> Vec<u8> {
+    let mut request = vec![0x03, 0x00, 0x00, 19];
+    request.extend_from_slice(&[14, 0xe0, 0, 0, 0, 0, 0]);
+    request.extend_from_slice(&[0x01, 0x00, 0x08, 0x00]);
+    request.extend_from_slice(&protocols.to_le_bytes());
+    request
+}
+
+/// Offers `protocols`; `Some(Ok(selected))` when the server accepts one,
+/// `Some(Err(code))` when it refuses them, `None` when it is no RDP server.
+async fn negotiate(
+    address: IpAddr,
+    port: u16,
+    protocols: u32,
+    wait: Duration,
+) -> Option<Result<u32, u32>> {
+    let exchange = async {
+        let mut stream = connect(address, port, wait).await?;
+        stream
+            .write_all(&connection_request(protocols))
+            .await
+            .ok()?;
+        let mut tpkt = [0u8; 4];
+        stream.read_exact(&mut tpkt).await.ok()?;
+        let len = u16::from_be_bytes([tpkt[2], tpkt[3]]) as usize;
+        if tpkt[0] != 0x03 || !(11..=512).contains(&len) {
+            return None;
+        }
+        let mut reply = vec![0u8; len - 4];
+        stream.read_exact(&mut reply).await.ok()?;
+        // Connection Confirm, with the negotiation result after 7 bytes.
+        if reply[1] & 0xf0 != 0xd0 {
+            return None;
+        }
+        match reply.get(7..15) {
+            // Servers that predate negotiation only speak standard RDP.
+            None => Some(Ok(PROTOCOL_RDP)),
+            Some(negotiation) => {
+                let value = u32::from_le_bytes(negotiation[4..8].try_into().ok()?);
+                match negotiation[0] {
+                    TYPE_RDP_NEG_RSP => Some(Ok(value)),
+                    TYPE_RDP_NEG_FAILURE => Some(Err(value)),
+                    _ => None,
+                }
+            }
+        }
+    };
+    timeout(wait, exchange).await.ok()?
+}
+
+pub async fn rdp(address: IpAddr, port: u16, wait: Duration) -> Option<RemoteDesktop> {
+    let all = PROTOCOL_SSL | PROTOCOL_HYBRID | PROTOCOL_HYBRID_EX;
+    let Ok(selected) = negotiate(address, port, all, wait).await? else {
+        return None;
+    };
+
+    let offers = [
+        (RdpSecurity::Rdp, PROTOCOL_RDP),
+        (RdpSecurity::Tls, PROTOCOL_SSL),
+        (RdpSecurity::Nla, PROTOCOL_SSL | PROTOCOL_HYBRID),
+    ];
+    let mut probes = JoinSet::new();
+    for (security, protocols) in offers {
+        probes.spawn(async move {
+            let accepted = matches!(negotiate(address, port, protocols, wait).await, Some(Ok(_)));
+            (security, accepted)
+        });
+    }
+    let mut accepted = Vec::new();
+    while let Some(probe) = probes.join_next().await {
+        if let Ok((security, true)) = probe {
+            accepted.push(security);
+        }
+    }
+    accepted.sort_by_key(|security| offers.iter().position(|(s, _)| s == security));
+
+    let nla_required =
+        !accepted.contains(&RdpSecurity::Rdp) && !accepted.contains(&RdpSecurity::Tls);
+    Some(RemoteDesktop::Rdp {
+        selected: RdpSecurity::from_protocol(selected),
+        accepted,
+        nla_required,
+    })
+}
+
+fn vnc_security_type(kind: u8) -> String {
+    match kind {
+        1 => "None".to_string(),
+        2 => "VNC Authentication".to_string(),
+        5 => "RA2".to_string(),
+        6 => "RA2ne".to_string(),
+        16 => "Tight".to_string(),
+        17 => "Ultra".to_string(),
+        18 => "TLS".to_string(),
+        19 => "VeNCrypt".to_string(),
+        20 => "SASL".to_string(),
+        22 => "XVP".to_string(),
+        30 => "Apple Remote Desktop".to_string(),
+        other => format!("type {}", other),
+    }
+}
+
+pub async fn vnc(address: IpAddr, port: u16, wait: Duration) -> Option<RemoteDesktop> {
+    let exchange = async {
+        let mut stream = connect(address, port, wait).await?;
+        let mut greeting = [0u8; 12];
+        stream.read_exact(&mut greeting).await.ok()?;
+        let greeting = std::str::from_utf8(&greeting).ok()?;
+        let (major, minor) = greeting.strip_prefix("RFB ")?.trim_end().split_once('.')?;
+        let (major, minor): (u32, u32) = (major.parse().ok()?, minor.parse().ok()?);
+
+        let types = if (major, minor) < (3, 7) {
+            stream.write_all(b"RFB 003.003\n").await.ok()?;
+            // 3.3 servers pick the type themselves; 0 means refused.
+            let mut kind = [0u8; 4];
+            stream.read_exact(&mut kind).await.ok()?;
+            match u32::from_be_bytes(kind) {
+                0 => Vec::new(),
+                kind => vec![kind.min(255) as u8],
+            }
+        } else {
+            let reply: &[u8] = if minor >= 8 || major > 3 {
+                b"RFB 003.008\n"
+            } else {
+                b"RFB 003.007\n"
+            };
+            stream.write_all(reply).await.ok()?;
+            let mut count = [0u8; 1];
+            stream.read_exact(&mut count).await.ok()?;
+            let mut types = vec![0u8; count[0] as usize];
+            stream.read_exact(&mut types).await.ok()?;
+            types
+        };
+
+        Some(RemoteDesktop::Vnc {
+            version: format!("{}.{}", major, minor),
+            no_auth: types.contains(&1),
+            security_types: types.into_iter().map(vnc_security_type).collect(),
+        })
+    };
+    timeout(wait, exchange).await.ok()?
+}
+
+pub struct RemoteDesktopFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+}
+
+impl RemoteDesktopFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        Self { config, sockets }
+    }
+}
+
+#[async_trait]
+impl Fetcher for RemoteDesktopFetcher {
+    fn id(&self) -> String {
+        "remote_desktop".to_string()
+    }
+
+    fn name(&self) -> String {
+        "Remote Desktop".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        // VNC on another port still shows its version in the banner.
+        let banners = subject.get_parameter::<Banners>(BANNERS);
+        let targets: Vec<(u16, bool)> = ports::open_ports(subject)
+            .into_iter()
+            .filter_map(|port| {
+                let vnc = VNC_PORTS.contains(&port)
+                    || banners
+                        .and_then(|banners| banners.get(&port))
+                        .is_some_and(|banner| banner.starts_with("RFB "));
+                (vnc || RDP_PORTS.contains(&port)).then_some((port, vnc))
+            })
+            .collect();
+        if targets.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        let mut probes = JoinSet::new();
+        for (port, vnc_port) in targets {
+            let sockets = self.sockets.clone();
+            probes.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                let desktop = if vnc_port {
+                    vnc(address, port, wait).await
+                } else {
+                    rdp(address, port, wait).await
+                };
+                (port, desktop)
+            });
+        }
+
+        let mut desktops = RemoteDesktops::new();
+        while let Some(probe) = probes.join_next().await {
+            if let Ok((port, Some(desktop))) = probe {
+                desktops.insert(port, desktop);
+            }
+        }
+
+        if desktops.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = desktops
+            .iter()
+            .map(|(port, desktop)| format!("{}: {}", port, desktop))
+            .collect::<Vec<_>>()
+            .join("; ");
+        subject.set_parameter(REMOTE_DESKTOPS.to_string(), desktops);
+        Ok(summary)
+    }
+}
@@ -0,0 +1,667 @@
+use async_trait::async_trait;
+use regex::bytes::{Captures, Regex, RegexBuilder};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+use tracing::warn;
+
+use super::ports::{self, PortState, PortStates, UDP_PORT_STATES};
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+use crate::network_utils;
+
+pub const SERVICE_FINGERPRINTS: &str = "service.fingerprints";
+
+const BUILTIN_PROBES: &str = include_str!("../assets/service-probes.txt");
+const MAX_RESPONSE_BYTES: usize = 16 * 1024;
+// Bounds the connections one port costs when nothing matches.
+const MAX_PROBES_PER_PORT: usize = 6;
+
+/// Names of well-known ports, the guess for ports no probe recognizes.
+const TCP_SERVICES: &[(u16, &str)] = &[
+    (21, "ftp"),
+    (22, "ssh"),
+    (23, "telnet"),
+    (25, "smtp"),
+    (53, "domain"),
+    (80, "http"),
+    (110, "pop3"),
+    (111, "rpcbind"),
+    (135, "msrpc"),
+    (139, "netbios-ssn"),
+    (143, "imap"),
+    (389, "ldap"),
+    (443, "https"),
+    (445, "microsoft-ds"),
+    (465, "smtps"),
+    (515, "printer"),
+    (587, "submission"),
+    (631, "ipp"),
+    (636, "ldaps"),
+    (873, "rsync"),
+    (993, "imaps"),
+    (995, "pop3s"),
+    (1433, "ms-sql-s"),
+    (1521, "oracle"),
+    (1883, "mqtt"),
+    (2049, "nfs"),
+    (3306, "mysql"),
+    (3389, "ms-wbt-server"),
+    (5432, "postgresql"),
+    (5900, "vnc"),
+    (5985, "wsman"),
+    (6379, "redis"),
+    (8080, "http-proxy"),
+    (8443, "https-alt"),
+    (9100, "jetdirect"),
+    (27017, "mongodb"),
+];
+
+const UDP_SERVICES: &[(u16, &str)] = &[
+    (53, "domain"),
+    (67, "dhcps"),
+    (69, "tftp"),
+    (123, "ntp"),
+    (137, "netbios-ns"),
+    (161, "snmp"),
+    (500, "isakmp"),
+    (514, "syslog"),
+    (1900, "upnp"),
+    (5353, "mdns"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ProbeProtocol {
+    Tcp,
+    Udp,
+}
+
+pub fn guess_service(protocol: ProbeProtocol, port: u16) -> Option<&'static str> {
+    let table = match protocol {
+        ProbeProtocol::Tcp => TCP_SERVICES,
+        ProbeProtocol::Udp => UDP_SERVICES,
+    };
+    table
+        .iter()
+        .find(|(known, _)| *known == port)
+        .map(|(_, name)| *name)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ServiceFingerprint {
+    pub service: String,
+    pub product: Option<String>,
+    pub version: Option<String>,
+    pub info: Option<String>,
+    pub hostname: Option<String>,
+    pub os: Option<String>,
+    pub device_type: Option<String>,
+    pub cpe: Vec<String>,
+    /// Matched a softmatch, which names the service but not the product.
+    pub soft: bool,
+    /// Nothing matched; the service is what usually runs on the port.
+    pub guessed: bool,
+}
+
+impl fmt::Display for ServiceFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.service)?;
+        if self.guessed {
+            return f.write_str("?");
+        }
+        if let Some(product) = &self.product {
+            write!(f, " {}", product)?;
+        }
+        if let Some(version) = &self.version {
+            write!(f, " {}", version)?;
+        }
+        if let Some(info) = &self.info {
+            write!(f, " ({})", info)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ServiceFingerprints {
+    pub tcp: BTreeMap<u16, ServiceFingerprint>,
+    pub udp: BTreeMap<u16, ServiceFingerprint>,
+}
+
+impl ServiceFingerprints {
+    pub fn is_empty(&self) -> bool {
+        self.tcp.is_empty() && self.udp.is_empty()
+    }
+}
+
+impl fmt::Display for ServiceFingerprints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tcp = self
+            .tcp
+            .iter()
+            .map(|(port, service)| format!("{}: {}", port, service));
+        let udp = self
+            .udp
+            .iter()
+            .map(|(port, service)| format!("{}/udp: {}", port, service));
+        f.write_str(&tcp.chain(udp).collect::<Vec<_>>().join("; "))
+    }
+}
+
+/// Version info fields of a match line, before `$n` substitution.
+#[derive(Debug, Clone, Default)]
+struct Template {
+    product: Option<String>,
+    version: Option<String>,
+    info: Option<String>,
+    hostname: Option<String>,
+    os: Option<String>,
+    device_type: Option<String>,
+    cpe: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    service: String,
+    pattern: Regex,
+    soft: bool,
+    template: Template,
+}
+
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pub protocol: ProbeProtocol,
+    pub name: String,
+    pub payload: Vec<u8>,
+    ports: Vec<RangeInclusive<u16>>,
+    rules: Vec<Rule>,
+}
+
+impl Probe {
+    /// Whether the database lists `port` as one this probe is meant for.
+    pub fn lists_port(&self, port: u16) -> bool {
+        self.ports.iter().any(|range| range.contains(&port))
+    }
+
+    /// Service behind `response`, from the first rule that matches it.
+    pub fn identify(&self, response: &[u8]) -> Option<ServiceFingerprint> {
+        self.rules.iter().find_map(|rule| {
+            let captures = rule.pattern.captures(response)?;
+            let fill = |field: &Option<String>| {
+                field
+                    .as_deref()
+                    .map(|template| substitute(template, &captures))
+                    .filter(|value| !value.is_empty())
+            };
+            Some(ServiceFingerprint {
+                service: rule.service.clone(),
+                product: fill(&rule.template.product),
+                version: fill(&rule.template.version),
+                info: fill(&rule.template.info),
+                hostname: fill(&rule.template.hostname),
+                os: fill(&rule.template.os),
+                device_type: fill(&rule.template.device_type),
+                cpe: rule
+                    .template
+                    .cpe
+                    .iter()
+                    .map(|cpe| format!("cpe:/{}", substitute(cpe, &captures)))
+                    .collect(),
+                soft: rule.soft,
+                guessed: false,
+            })
+        })
+    }
+}
+
+/// Replaces `$n` with capture group `n` and `$P(n)` with its printable
+/// bytes.
+fn substitute(template: &str, captures: &Captures) -> String {
+    let group = |n: u32| {
+        captures
+            .get(n as usize)
+            .map(|m| m.as_bytes())
+            .unwrap_or_default()
+    };
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(dollar) = rest.find('$') {
+        output.push_str(&rest[..dollar]);
+        let tail = &rest[dollar + 1..];
+        let printable = tail
+            .strip_prefix("P(")
+            .and_then(|inner| Some((inner.chars().next()?.to_digit(10)?, inner.get(1..)?)))
+            .and_then(|(n, after)| Some((n, after.strip_prefix(')')?)));
+        if let Some(n) = tail.chars().next().and_then(|c| c.to_digit(10)) {
+            output.push_str(&String::from_utf8_lossy(group(n)));
+            rest = &tail[1..];
+        } else if let Some((n, after)) = printable {
+            output.extend(
+                group(n)
+                    .iter()
+                    .filter(|b| (0x20..0x7f).contains(*b))
+                    .map(|&b| b as char),
+            );
+            rest = after;
+        } else {
+            output.push('$');
+            rest = tail;
+        }
+    }
+    output.push_str(rest);
+    output.trim().to_string()
+}
+
+/// Text between the delimiter `text` starts with and its next occurrence,
+/// and what follows.
+fn delimited(text: &str) -> Option<(&str, &str)> {
+    let delimiter = text.chars().next()?;
+    let body = &text[delimiter.len_utf8()..];
+    let end = body.find(delimiter)?;
+    Some((&body[..end], &body[end + delimiter.len_utf8()..]))
+}
+
+/// Decodes the C style escapes of a probe payload.
+fn unescape(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0u8; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('0') => bytes.push(0),
+            Some('a') => bytes.push(0x07),
+            Some('b') => bytes.push(0x08),
+            Some('f') => bytes.push(0x0c),
+            Some('n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('v') => bytes.push(0x0b),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                bytes.push(u8::from_str_radix(&hex, 16).unwrap_or(0));
+            }
+            Some(other) => bytes.push(other as u8),
+            None => bytes.push(b'\\'),
+        }
+    }
+    bytes
+}
+
+fn parse_ports(list: &str) -> Option<Vec<RangeInclusive<u16>>> {
+    list.split(',')
+        .map(|item| {
+            let item = item.trim();
+            match item.split_once('-') {
+                Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+                None => {
+                    let port = item.parse().ok()?;
+                    Some(port..=port)
+                }
+            }
+        })
+        .collect()
+}
+
+/// `Exclude` port list. `T:` and `U:` switch the protocol for the items
+/// that follow; items before either apply to both.
+fn parse_exclude(list: &str, database: &mut ProbeDatabase) -> Option<()> {
+    let mut protocols: &[ProbeProtocol] = &[ProbeProtocol::Tcp, ProbeProtocol::Udp];
+    for item in list.split(',') {
+        let item = item.trim();
+        let item = if let Some(rest) = item.strip_prefix("T:") {
+            protocols = &[ProbeProtocol::Tcp];
+            rest
+        } else if let Some(rest) = item.strip_prefix("U:") {
+            protocols = &[ProbeProtocol::Udp];
+            rest
+        } else {
+            item
+        };
+        for range in parse_ports(item)? {
+            for protocol in protocols {
+                database.excluded.push((*protocol, range.clone()));
+            }
+        }
+    }
+    Some(())
+}
+
+/// `Probe <TCP|UDP> <name> q|<payload>|`
+fn parse_probe(line: &str) -> Option<Probe> {
+    let (protocol, rest) = line.trim_start().split_once(' ')?;
+    let protocol = match protocol {
+        "TCP" => ProbeProtocol::Tcp,
+        "UDP" => ProbeProtocol::Udp,
+        _ => return None,
+    };
+    let (name, rest) = rest.trim_start().split_once(' ')?;
+    let (payload, _) = delimited(rest.trim_start().strip_prefix('q')?)?;
+    Some(Probe {
+        protocol,
+        name: name.to_string(),
+        payload: unescape(payload),
+        ports: Vec::new(),
+        rules: Vec::new(),
+    })
+}
+
+/// `<service> m|<regex>|[flags] [p/../] [v/../] ... [cpe:/../]`. The outer
+/// `None` is a malformed line, the inner one a regex we cannot compile.
+fn parse_rule(line: &str, soft: bool) -> Option<Option<Rule>> {
+    let (service, rest) = line.trim_start().split_once(' ')?;
+    let (pattern, rest) = delimited(rest.trim_start().strip_prefix('m')?)?;
+    let flags_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let (flags, mut rest) = rest.split_at(flags_end);
+
+    let mut template = Template::default();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let (field, after) = match rest.strip_prefix("cpe:") {
+            Some(after) => ("cpe", after),
+            None => rest.split_at(rest.chars().next()?.len_utf8()),
+        };
+        let (value, after) = delimited(after)?;
+        // Flags such as the `a` after a CPE do not concern us.
+        rest = after.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        let value = Some(value.to_string());
+        match field {
+            "p" => template.product = value,
+            "v" => template.version = value,
+            "i" => template.info = value,
+            "h" => template.hostname = value,
+            "o" => template.os = value,
+            "d" => template.device_type = value,
+            "cpe" => template.cpe.extend(value),
+            _ => {}
+        }
+    }
+
+    let pattern = RegexBuilder::new(pattern)
+        .unicode(false)
+        .case_insensitive(flags.contains('i'))
+        .dot_matches_new_line(flags.contains('s'))
+        .build();
+    Some(pattern.ok().map(|pattern| Rule {
+        service: service.to_string(),
+        pattern,
+        soft,
+        template,
+    }))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProbeDatabase {
+    pub probes: Vec<Probe>,
+    /// Match lines whose regex uses syntax we do not support, such as the
+    /// lookarounds of PCRE.
+    pub skipped: usize,
+    /// Ports never to probe, such as the raw printing ports that print
+    /// whatever they are sent.
+    pub excluded: Vec<(ProbeProtocol, RangeInclusive<u16>)>,
+}
+
+impl ProbeDatabase {
+    pub fn parse(text: &str) -> Result<Self, ScanError> {
+        let mut database = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || ScanError::ProbeDatabase(format!("line {}: {}", number + 1, line));
+            let (directive, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+            match directive {
+                "Probe" => {
+                    database.probes.push(parse_probe(rest).ok_or_else(invalid)?);
+                    continue;
+                }
+                "Exclude" => {
+                    parse_exclude(rest, &mut database).ok_or_else(invalid)?;
+                    continue;
+                }
+                _ => {}
+            }
+            let probe = database.probes.last_mut().ok_or_else(invalid)?;
+            match directive {
+                "ports" => probe.ports = parse_ports(rest).ok_or_else(invalid)?,
+                "match" | "softmatch" => {
+                    match parse_rule(rest, directive == "softmatch").ok_or_else(invalid)? {
+                        Some(rule) => probe.rules.push(rule),
+                        None => database.skipped += 1,
+                    }
+                }
+                // Timing, rarity and TLS hints of the nmap format.
+                "sslports" | "rarity" | "totalwaitms" | "tcpwrappedms" | "fallback" => {}
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(database)
+    }
+
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_PROBES).expect("the built-in probe database parses")
+    }
+
+    /// Adds the probes of `other`. Rules under a probe this database
+    /// already has go before its own, so users can override them.
+    pub fn extend(&mut self, other: ProbeDatabase) {
+        self.skipped += other.skipped;
+        self.excluded.extend(other.excluded);
+        for mut probe in other.probes {
+            match self
+                .probes
+                .iter_mut()
+                .find(|known| known.protocol == probe.protocol && known.name == probe.name)
+            {
+                Some(known) => {
+                    probe.rules.append(&mut known.rules);
+                    known.rules = probe.rules;
+                    known.ports.extend(probe.ports);
+                }
+                None => self.probes.push(probe),
+            }
+        }
+    }
+
+    /// The built-in database, extended by the file at `path` if given.
+    pub fn load(path: Option<&str>) -> Self {
+        let mut database = Self::builtin();
+        if let Some(path) = path {
+            match std::fs::read_to_string(path)
+                .map_err(ScanError::from)
+                .and_then(|text| Self::parse(&text))
+            {
+                Ok(extra) => database.extend(extra),
+                Err(e) => warn!("Ignoring probe database {}: {}", path, e),
+            }
+        }
+        database
+    }
+
+    pub fn excludes(&self, protocol: ProbeProtocol, port: u16) -> bool {
+        self.excluded
+            .iter()
+            .any(|(excluded, range)| *excluded == protocol && range.contains(&port))
+    }
+
+    /// Probes to try on `port`, in order: the NULL probe that only listens,
+    /// then the ones meant for the port, then the rest.
+    pub fn probes_for(&self, protocol: ProbeProtocol, port: u16) -> Vec<&Probe> {
+        let mut probes: Vec<&Probe> = self
+            .probes
+            .iter()
+            .filter(|probe| probe.protocol == protocol)
+            // UDP services do not speak first.
+            .filter(|probe| protocol == ProbeProtocol::Tcp || !probe.payload.is_empty())
+            .collect();
+        probes.sort_by_key(|probe| (!probe.payload.is_empty(), !probe.lists_port(port)));
+        probes.truncate(MAX_PROBES_PER_PORT);
+        probes
+    }
+}
+
+async fn send_tcp(address: IpAddr, port: u16, payload: &[u8], wait: Duration) -> Option<Vec<u8>> {
+    let mut stream = timeout(wait, TcpStream::connect((address, port)))
+        .await
+        .ok()?
+        .ok()?;
+    if !payload.is_empty() {
+        timeout(wait, stream.write_all(payload)).await.ok()?.ok()?;
+    }
+    let mut buffer = vec![0u8; MAX_RESPONSE_BYTES];
+    let len = network_utils::read_available(&mut stream, &mut buffer, wait).await;
+    buffer.truncate(len);
+    (len > 0).then_some(buffer)
+}
+
+async fn send_udp(address: IpAddr, port: u16, payload: &[u8], wait: Duration) -> Option<Vec<u8>> {
+    let bind: IpAddr = match address {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((bind, 0)).await.ok()?;
+    socket.connect((address, port)).await.ok()?;
+    socket.send(payload).await.ok()?;
+    let mut buffer = vec![0u8; MAX_RESPONSE_BYTES];
+    let len = timeout(wait, socket.recv(&mut buffer)).await.ok()?.ok()?;
+    buffer.truncate(len);
+    Some(buffer)
+}
+
+/// Runs the probes of `database` against `port` until one of them yields
+/// a full match. A softmatch, then the port's usual service, stand in when
+/// none does. `listen_wait` bounds the NULL probe, `wait` the others.
+pub async fn fingerprint(
+    database: &ProbeDatabase,
+    protocol: ProbeProtocol,
+    address: IpAddr,
+    port: u16,
+    listen_wait: Duration,
+    wait: Duration,
+) -> Option<ServiceFingerprint> {
+    let mut soft = None;
+    for probe in database.probes_for(protocol, port) {
+        let response = match protocol {
+            ProbeProtocol::Tcp if probe.payload.is_empty() => {
+                send_tcp(address, port, &probe.payload, listen_wait).await
+            }
+            ProbeProtocol::Tcp => send_tcp(address, port, &probe.payload, wait).await,
+            ProbeProtocol::Udp => send_udp(address, port, &probe.payload, wait).await,
+        };
+        match response.and_then(|response| probe.identify(&response)) {
+            Some(found) if !found.soft => return Some(found),
+            Some(found) => {
+                soft.get_or_insert(found);
+            }
+            None => {}
+        }
+    }
+    soft.or_else(|| {
+        guess_service(protocol, port).map(|service| ServiceFingerprint {
+            service: service.to_string(),
+            guessed: true,
+            ..Default::default()
+        })
+    })
+}
+
+pub struct ServiceFingerprintFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+    database: Arc<ProbeDatabase>,
+}
+
+impl ServiceFingerprintFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        let database = ProbeDatabase::load(config.service_probes_file.as_deref());
+        Self {
+            config,
+            sockets,
+            database: Arc::new(database),
+        }
+    }
+}
+
+#[async_trait]
+impl Fetcher for ServiceFingerprintFetcher {
+    fn id(&self) -> String {
+        "service_fingerprint".to_string()
+    }
+
+    fn name(&self) -> String {
+        "Services".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let udp_ports = subject
+            .get_parameter::<PortStates>(UDP_PORT_STATES)
+            .map(|states| ports::ports_in_state(states, PortState::Open))
+            .unwrap_or_default();
+        let targets: Vec<(ProbeProtocol, u16)> = ports::open_ports(subject)
+            .into_iter()
+            .map(|port| (ProbeProtocol::Tcp, port))
+            .chain(udp_ports.into_iter().map(|port| (ProbeProtocol::Udp, port)))
+            .filter(|(protocol, port)| !self.database.excludes(*protocol, *port))
+            .collect();
+        if targets.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let listen_wait = Duration::from_millis(self.config.banner_timeout_ms);
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        let mut probes = JoinSet::new();
+        for (protocol, port) in targets {
+            let database = self.database.clone();
+            let sockets = self.sockets.clone();
+            probes.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                let service =
+                    fingerprint(&database, protocol, address, port, listen_wait, wait).await;
+                (protocol, port, service)
+            });
+        }
+
+        let mut services = ServiceFingerprints::default();
+        while let Some(probe) = probes.join_next().await {
+            if let Ok((protocol, port, Some(service))) = probe {
+                match protocol {
+                    ProbeProtocol::Tcp => services.tcp.insert(port, service),
+                    ProbeProtocol::Udp => services.udp.insert(port, service),
+                };
+            }
+        }
+
+        if services.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = services.to_string();
+        subject.set_parameter(SERVICE_FINGERPRINTS.to_string(), services);
+        Ok(summary)
+    }
+}
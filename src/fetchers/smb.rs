@@ -0,0 +1,456 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::ports;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const SMB: &str = "smb.info";
+
+pub const SMB_PORT: u16 = 445;
+
+/// SMB2 dialect revisions and the names they go by.
+pub const SMB2_DIALECTS: [(u16, &str); 5] = [
+    (0x0202, "2.0.2"),
+    (0x0210, "2.1"),
+    (0x0300, "3.0"),
+    (0x0302, "3.0.2"),
+    (0x0311, "3.1.1"),
+];
+
+const SMB2_MAGIC: &[u8] = b"\xfeSMB";
+const SMB1_MAGIC: &[u8] = b"\xffSMB";
+const SMB2_NEGOTIATE: u16 = 0;
+const SMB2_SESSION_SETUP: u16 = 1;
+const SMB1_NEGOTIATE: u8 = 0x72;
+const STATUS_SUCCESS: u32 = 0;
+const STATUS_MORE_PROCESSING_REQUIRED: u32 = 0xc000_0016;
+const SIGNING_ENABLED: u16 = 0x01;
+const SIGNING_REQUIRED: u16 = 0x02;
+const NTLMSSP: &[u8] = b"NTLMSSP\0";
+const NTLMSSP_NEGOTIATE_VERSION: u32 = 0x0200_0000;
+const MAX_MESSAGE: usize = 65536;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SmbInfo {
+    /// SMB2 dialects the server accepts, lowest first.
+    pub dialects: Vec<String>,
+    /// Whether the server still speaks SMBv1; `None` when not probed.
+    pub smb1: Option<bool>,
+    pub signing_enabled: bool,
+    pub signing_required: bool,
+    pub netbios_computer_name: Option<String>,
+    pub netbios_domain_name: Option<String>,
+    pub dns_computer_name: Option<String>,
+    pub dns_domain_name: Option<String>,
+    /// Windows version and build from the NTLM challenge, e.g. `10.0.19041`.
+    pub os_version: Option<String>,
+}
+
+impl fmt::Display for SmbInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        match (&self.netbios_domain_name, &self.netbios_computer_name) {
+            (Some(domain), Some(name)) => parts.push(format!("{}\\{}", domain, name)),
+            (None, Some(name)) => parts.push(name.clone()),
+            _ => {}
+        }
+        if let Some(version) = &self.os_version {
+            parts.push(format!("Windows {}", version));
+        }
+        if !self.dialects.is_empty() {
+            parts.push(format!("SMB {}", self.dialects.join(", ")));
+        }
+        parts.push(
+            if self.signing_required {
+                "signing required"
+            } else if self.signing_enabled {
+                "signing enabled"
+            } else {
+                "signing disabled"
+            }
+            .to_string(),
+        );
+        if self.smb1 == Some(true) {
+            parts.push("SMBv1".to_string());
+        }
+        f.write_str(&parts.join("; "))
+    }
+}
+
+/// Answer to an SMB2 NEGOTIATE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Negotiated {
+    dialect: u16,
+    security_mode: u16,
+}
+
+/// Wraps `message` in a NetBIOS session service header.
+fn session_message(message: &[u8]) -> Vec<u8> {
+    let mut framed = (message.len() as u32).to_be_bytes().to_vec();
+    framed[0] = 0;
+    framed.extend_from_slice(message);
+    framed
+}
+
+async fn exchange(stream: &mut TcpStream, message: &[u8], wait: Duration) -> Option<Vec<u8>> {
+    let exchange = async {
+        stream.write_all(&session_message(message)).await.ok()?;
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await.ok()?;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        if len > MAX_MESSAGE {
+            return None;
+        }
+        let mut reply = vec![0u8; len];
+        stream.read_exact(&mut reply).await.ok()?;
+        Some(reply)
+    };
+    timeout(wait, exchange).await.ok()?
+}
+
+fn smb2_header(command: u16, message_id: u64) -> Vec<u8> {
+    let mut header = Vec::with_capacity(64);
+    header.extend_from_slice(SMB2_MAGIC);
+    header.extend_from_slice(&64u16.to_le_bytes());
+    header.extend_from_slice(&[0; 2]); // credit charge
+    header.extend_from_slice(&[0; 4]); // status
+    header.extend_from_slice(&command.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // credits requested
+    header.extend_from_slice(&[0; 4]); // flags
+    header.extend_from_slice(&[0; 4]); // next command
+    header.extend_from_slice(&message_id.to_le_bytes());
+    header.extend_from_slice(&0xfeffu32.to_le_bytes()); // process id
+    header.extend_from_slice(&[0; 4]); // tree id
+    header.extend_from_slice(&[0; 8]); // session id
+    header.extend_from_slice(&[0; 16]); // signature
+    header
+}
+
+/// Status and body of an SMB2 reply to `command`.
+fn smb2_reply(reply: &[u8], command: u16) -> Option<(u32, &[u8])> {
+    if reply.len() < 64 || &reply[..4] != SMB2_MAGIC {
+        return None;
+    }
+    let status = u32::from_le_bytes(reply[8..12].try_into().ok()?);
+    let reply_command = u16::from_le_bytes([reply[12], reply[13]]);
+    (reply_command == command).then_some((status, &reply[64..]))
+}
+
+fn negotiate_request(dialects: &[u16]) -> Vec<u8> {
+    let mut message = smb2_header(SMB2_NEGOTIATE, 0);
+    message.extend_from_slice(&36u16.to_le_bytes());
+    message.extend_from_slice(&(dialects.len() as u16).to_le_bytes());
+    message.extend_from_slice(&SIGNING_ENABLED.to_le_bytes());
+    message.extend_from_slice(&[0; 2]);
+    message.extend_from_slice(&[0; 4]); // capabilities
+    message.extend_from_slice(b"ipscan-rs-client"); // client guid
+
+    // 3.1.1 needs a preauth integrity context, which starts 8-byte aligned
+    // after the dialect list.
+    let smb311 = dialects.contains(&0x0311);
+    let contexts_offset = (100 + dialects.len() * 2).next_multiple_of(8);
+    if smb311 {
+        message.extend_from_slice(&(contexts_offset as u32).to_le_bytes());
+        message.extend_from_slice(&1u16.to_le_bytes());
+        message.extend_from_slice(&[0; 2]);
+    } else {
+        message.extend_from_slice(&[0; 8]); // client start time
+    }
+    for dialect in dialects {
+        message.extend_from_slice(&dialect.to_le_bytes());
+    }
+
+    if smb311 {
+        message.resize(contexts_offset, 0);
+        message.extend_from_slice(&1u16.to_le_bytes()); // preauth integrity
+        message.extend_from_slice(&38u16.to_le_bytes());
+        message.extend_from_slice(&[0; 4]);
+        message.extend_from_slice(&1u16.to_le_bytes()); // one hash algorithm
+        message.extend_from_slice(&32u16.to_le_bytes()); // salt length
+        message.extend_from_slice(&1u16.to_le_bytes()); // SHA-512
+        message.extend_from_slice(&[0x5a; 32]);
+    }
+    message
+}
+
+fn parse_negotiate(reply: &[u8]) -> Option<Negotiated> {
+    let (STATUS_SUCCESS, body) = smb2_reply(reply, SMB2_NEGOTIATE)? else {
+        return None;
+    };
+    let field = |at: usize| {
+        body.get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    Some(Negotiated {
+        security_mode: field(2)?,
+        dialect: field(4)?,
+    })
+}
+
+/// A session setup carrying an NTLMSSP NEGOTIATE, wrapped in SPNEGO.
+fn session_setup_request() -> Vec<u8> {
+    let mut ntlm = NTLMSSP.to_vec();
+    ntlm.extend_from_slice(&1u32.to_le_bytes());
+    // Unicode, NTLM, extended session security, target info and version.
+    ntlm.extend_from_slice(&0xe288_8215u32.to_le_bytes());
+    ntlm.extend_from_slice(&[0; 16]); // domain and workstation fields
+    ntlm.extend_from_slice(&[0; 8]); // version
+
+    let ntlm_oid = [
+        0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x0a,
+    ];
+    let mech_types = der(0xa0, &der(0x30, &ntlm_oid));
+    let mech_token = der(0xa2, &der(0x04, &ntlm));
+    let neg_token_init = der(0xa0, &der(0x30, &[mech_types, mech_token].concat()));
+    let spnego_oid = [0x06, 0x06, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x02];
+    let token = der(0x60, &[&spnego_oid[..], &neg_token_init].concat());
+
+    let mut message = smb2_header(SMB2_SESSION_SETUP, 1);
+    message.extend_from_slice(&25u16.to_le_bytes());
+    message.push(0); // flags
+    message.push(SIGNING_ENABLED as u8);
+    message.extend_from_slice(&[0; 4]); // capabilities
+    message.extend_from_slice(&[0; 4]); // channel
+    message.extend_from_slice(&88u16.to_le_bytes()); // security buffer offset
+    message.extend_from_slice(&(token.len() as u16).to_le_bytes());
+    message.extend_from_slice(&[0; 8]); // previous session id
+    message.extend_from_slice(&token);
+    message
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    if content.len() < 0x80 {
+        encoded.push(content.len() as u8);
+    } else {
+        encoded.push(0x82);
+        encoded.extend_from_slice(&(content.len() as u16).to_be_bytes());
+    }
+    encoded.extend_from_slice(content);
+    encoded
+}
+
+/// Fills in the names and version from an NTLMSSP CHALLENGE message.
+pub fn parse_challenge(challenge: &[u8], info: &mut SmbInfo) -> Option<()> {
+    if !challenge.starts_with(NTLMSSP) || challenge.get(8..12)? != 2u32.to_le_bytes() {
+        return None;
+    }
+    let u16_at = |at: usize| {
+        challenge
+            .get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    let flags = u32::from_le_bytes(challenge.get(20..24)?.try_into().ok()?);
+
+    if flags & NTLMSSP_NEGOTIATE_VERSION != 0
+        && let Some(version) = challenge.get(48..56)
+        && version[0] != 0
+    {
+        let build = u16::from_le_bytes([version[2], version[3]]);
+        info.os_version = Some(format!("{}.{}.{}", version[0], version[1], build));
+    }
+
+    let len = u16_at(40)? as usize;
+    let offset = u32::from_le_bytes(challenge.get(44..48)?.try_into().ok()?) as usize;
+    let mut pairs = challenge.get(offset..offset + len)?;
+    while pairs.len() >= 4 {
+        let id = u16::from_le_bytes([pairs[0], pairs[1]]);
+        let len = u16::from_le_bytes([pairs[2], pairs[3]]) as usize;
+        let value = pairs.get(4..4 + len)?;
+        pairs = &pairs[4 + len..];
+
+        let text = || {
+            let units: Vec<u16> = value
+                .chunks_exact(2)
+                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                .collect();
+            Some(String::from_utf16_lossy(&units)).filter(|text| !text.is_empty())
+        };
+        match id {
+            0 => break,
+            1 => info.netbios_computer_name = text(),
+            2 => info.netbios_domain_name = text(),
+            3 => info.dns_computer_name = text(),
+            4 => info.dns_domain_name = text(),
+            _ => {}
+        }
+    }
+    Some(())
+}
+
+async fn connect(address: IpAddr, port: u16, wait: Duration) -> Option<TcpStream> {
+    timeout(wait, TcpStream::connect((address, port)))
+        .await
+        .ok()?
+        .ok()
+}
+
+async fn negotiate(
+    address: IpAddr,
+    port: u16,
+    dialects: &[u16],
+    wait: Duration,
+) -> Option<Negotiated> {
+    let mut stream = connect(address, port, wait).await?;
+    let reply = exchange(&mut stream, &negotiate_request(dialects), wait).await?;
+    parse_negotiate(&reply).filter(|negotiated| dialects.contains(&negotiated.dialect))
+}
+
+/// Negotiates every dialect at once, then starts an NTLM session setup to
+/// get the challenge, which names the host without any credentials.
+async fn identify(address: IpAddr, port: u16, wait: Duration) -> Option<(Negotiated, SmbInfo)> {
+    let mut stream = connect(address, port, wait).await?;
+    let dialects = SMB2_DIALECTS.map(|(dialect, _)| dialect);
+    let reply = exchange(&mut stream, &negotiate_request(&dialects), wait).await?;
+    let negotiated = parse_negotiate(&reply)?;
+
+    let mut info = SmbInfo {
+        signing_enabled: negotiated.security_mode & SIGNING_ENABLED != 0,
+        signing_required: negotiated.security_mode & SIGNING_REQUIRED != 0,
+        ..Default::default()
+    };
+    if let Some(reply) = exchange(&mut stream, &session_setup_request(), wait).await
+        && let Some((STATUS_MORE_PROCESSING_REQUIRED, body)) =
+            smb2_reply(&reply, SMB2_SESSION_SETUP)
+        && let Some(start) = body.windows(NTLMSSP.len()).position(|w| w == NTLMSSP)
+    {
+        parse_challenge(&body[start..], &mut info);
+    }
+    Some((negotiated, info))
+}
+
+fn smb1_negotiate_request() -> Vec<u8> {
+    let mut message = SMB1_MAGIC.to_vec();
+    message.push(SMB1_NEGOTIATE);
+    message.extend_from_slice(&[0; 4]); // status
+    message.push(0x18); // flags: canonical, case insensitive paths
+    message.extend_from_slice(&0xc001u16.to_le_bytes()); // unicode, NT status, long names
+    message.extend_from_slice(&[0; 12]); // pid high, signature, reserved
+    message.extend_from_slice(&[0; 2]); // tree id
+    message.extend_from_slice(&0xfeffu16.to_le_bytes()); // process id
+    message.extend_from_slice(&[0; 4]); // user and multiplex ids
+    message.push(0); // word count
+    let dialect = b"\x02NT LM 0.12\0";
+    message.extend_from_slice(&(dialect.len() as u16).to_le_bytes());
+    message.extend_from_slice(dialect);
+    message
+}
+
+/// Whether the server accepts the SMBv1 `NT LM 0.12` dialect.
+pub async fn smb1_supported(address: IpAddr, port: u16, wait: Duration) -> bool {
+    let Some(mut stream) = connect(address, port, wait).await else {
+        return false;
+    };
+    let Some(reply) = exchange(&mut stream, &smb1_negotiate_request(), wait).await else {
+        return false;
+    };
+    // A server without SMBv1 either drops the connection, answers in SMB2
+    // or rejects every dialect with index 0xffff.
+    reply.len() >= 35
+        && reply.starts_with(SMB1_MAGIC)
+        && reply[4] == SMB1_NEGOTIATE
+        && reply[5..9] == [0; 4]
+        && reply[32] > 0
+        && reply[33..35] == [0, 0]
+}
+
+/// Identifies the server and the dialects it accepts. Every connection
+/// takes a permit of `sockets` for as long as it is open.
+pub async fn query(
+    address: IpAddr,
+    port: u16,
+    smb1: bool,
+    sockets: &Arc<Semaphore>,
+    wait: Duration,
+) -> Option<SmbInfo> {
+    let (negotiated, mut info) = {
+        let _socket = sockets.acquire().await.ok()?;
+        identify(address, port, wait).await?
+    };
+
+    // A server answers with its best dialect only, so the others each
+    // need a negotiation of their own.
+    let mut probes = JoinSet::new();
+    for (dialect, _) in SMB2_DIALECTS {
+        if dialect != negotiated.dialect {
+            let sockets = sockets.clone();
+            probes.spawn(async move {
+                let _socket = sockets.acquire_owned().await.ok()?;
+                negotiate(address, port, &[dialect], wait).await
+            });
+        }
+    }
+    let mut dialects = vec![negotiated.dialect];
+    while let Some(probe) = probes.join_next().await {
+        if let Ok(Some(other)) = probe {
+            dialects.push(other.dialect);
+        }
+    }
+    info.dialects = SMB2_DIALECTS
+        .iter()
+        .filter(|(dialect, _)| dialects.contains(dialect))
+        .map(|(_, name)| name.to_string())
+        .collect();
+
+    if smb1 {
+        let _socket = sockets.acquire().await.ok()?;
+        info.smb1 = Some(smb1_supported(address, port, wait).await);
+    }
+    Some(info)
+}
+
+pub struct SmbFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+}
+
+impl SmbFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        Self { config, sockets }
+    }
+}
+
+#[async_trait]
+impl Fetcher for SmbFetcher {
+    fn id(&self) -> String {
+        "smb".to_string()
+    }
+
+    fn name(&self) -> String {
+        "SMB".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        if !ports::open_ports(subject).contains(&SMB_PORT) {
+            return Ok("[n/a]".to_string());
+        }
+
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        let smb1 = self.config.smb1_probe;
+        match query(subject.address(), SMB_PORT, smb1, &self.sockets, wait).await {
+            Some(info) => {
+                let summary = info.to_string();
+                subject.set_parameter(SMB.to_string(), info);
+                Ok(summary)
+            }
+            None => Ok("[n/a]".to_string()),
+        }
+    }
+}
@@ -0,0 +1,474 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const SNMP: &str = "snmp.info";
+
+pub const SNMP_PORT: u16 = 161;
+
+pub const SYS_DESCR: &str = "1.3.6.1.2.1.1.1.0";
+pub const SYS_OBJECT_ID: &str = "1.3.6.1.2.1.1.2.0";
+pub const SYS_UPTIME: &str = "1.3.6.1.2.1.1.3.0";
+pub const SYS_CONTACT: &str = "1.3.6.1.2.1.1.4.0";
+pub const SYS_NAME: &str = "1.3.6.1.2.1.1.5.0";
+pub const SYS_LOCATION: &str = "1.3.6.1.2.1.1.6.0";
+
+const SYSTEM_OIDS: [&str; 6] = [
+    SYS_DESCR,
+    SYS_OBJECT_ID,
+    SYS_UPTIME,
+    SYS_CONTACT,
+    SYS_NAME,
+    SYS_LOCATION,
+];
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_IP_ADDRESS: u8 = 0x40;
+const TAG_COUNTER32: u8 = 0x41;
+const TAG_GAUGE32: u8 = 0x42;
+const TAG_TIMETICKS: u8 = 0x43;
+const TAG_COUNTER64: u8 = 0x46;
+const TAG_GET_REQUEST: u8 = 0xa0;
+const TAG_GET_RESPONSE: u8 = 0xa2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SnmpVersion {
+    #[serde(rename = "v1")]
+    V1,
+    #[serde(rename = "v2c")]
+    V2c,
+}
+
+impl SnmpVersion {
+    fn number(self) -> i64 {
+        match self {
+            SnmpVersion::V1 => 0,
+            SnmpVersion::V2c => 1,
+        }
+    }
+}
+
+impl fmt::Display for SnmpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SnmpVersion::V1 => "v1",
+            SnmpVersion::V2c => "v2c",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnmpValue {
+    Integer(i64),
+    String(Vec<u8>),
+    Oid(String),
+    IpAddress(Ipv4Addr),
+    Counter(u64),
+    TimeTicks(u32),
+    Null,
+    /// noSuchObject, noSuchInstance or endOfMibView.
+    Missing,
+}
+
+impl fmt::Display for SnmpValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnmpValue::Integer(n) => write!(f, "{}", n),
+            SnmpValue::String(bytes) => f.write_str(String::from_utf8_lossy(bytes).trim()),
+            SnmpValue::Oid(oid) => f.write_str(oid),
+            SnmpValue::IpAddress(ip) => write!(f, "{}", ip),
+            SnmpValue::Counter(n) => write!(f, "{}", n),
+            SnmpValue::TimeTicks(ticks) => write!(f, "{}", ticks),
+            SnmpValue::Null | SnmpValue::Missing => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnmpResponse {
+    pub request_id: i32,
+    pub error_status: i64,
+    pub varbinds: Vec<(String, SnmpValue)>,
+}
+
+impl SnmpResponse {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let (TAG_SEQUENCE, message, _) = read_tlv(bytes)? else {
+            return None;
+        };
+        let (TAG_INTEGER, _, rest) = read_tlv(message)? else {
+            return None;
+        };
+        let (TAG_OCTET_STRING, _, rest) = read_tlv(rest)? else {
+            return None;
+        };
+        let (TAG_GET_RESPONSE, pdu, _) = read_tlv(rest)? else {
+            return None;
+        };
+
+        let (TAG_INTEGER, request_id, rest) = read_tlv(pdu)? else {
+            return None;
+        };
+        let (TAG_INTEGER, error_status, rest) = read_tlv(rest)? else {
+            return None;
+        };
+        let (TAG_INTEGER, _, rest) = read_tlv(rest)? else {
+            return None;
+        };
+        let (TAG_SEQUENCE, mut list, _) = read_tlv(rest)? else {
+            return None;
+        };
+
+        let mut varbinds = Vec::new();
+        while !list.is_empty() {
+            let (TAG_SEQUENCE, varbind, rest) = read_tlv(list)? else {
+                return None;
+            };
+            list = rest;
+            let (TAG_OID, oid, value) = read_tlv(varbind)? else {
+                return None;
+            };
+            let (tag, value, _) = read_tlv(value)?;
+            varbinds.push((decode_oid(oid)?, decode_value(tag, value)?));
+        }
+
+        Some(Self {
+            request_id: decode_integer(request_id)? as i32,
+            error_status: decode_integer(error_status)?,
+            varbinds,
+        })
+    }
+
+    pub fn value(&self, oid: &str) -> Option<&SnmpValue> {
+        self.varbinds
+            .iter()
+            .find(|(name, value)| name == oid && *value != SnmpValue::Missing)
+            .map(|(_, value)| value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SnmpInfo {
+    pub version: SnmpVersion,
+    pub community: String,
+    pub sys_descr: Option<String>,
+    pub sys_object_id: Option<String>,
+    /// Hundredths of a second since the agent started.
+    pub sys_uptime: Option<u32>,
+    pub sys_contact: Option<String>,
+    pub sys_name: Option<String>,
+    pub sys_location: Option<String>,
+}
+
+impl SnmpInfo {
+    fn from_response(version: SnmpVersion, community: &str, response: &SnmpResponse) -> Self {
+        let text = |oid| {
+            response
+                .value(oid)
+                .map(|value| value.to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            version,
+            community: community.to_string(),
+            sys_descr: text(SYS_DESCR),
+            sys_object_id: text(SYS_OBJECT_ID),
+            sys_uptime: match response.value(SYS_UPTIME) {
+                Some(SnmpValue::TimeTicks(ticks)) => Some(*ticks),
+                _ => None,
+            },
+            sys_contact: text(SYS_CONTACT),
+            sys_name: text(SYS_NAME),
+            sys_location: text(SYS_LOCATION),
+        }
+    }
+}
+
+impl fmt::Display for SnmpInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.sys_name.as_deref().unwrap_or("?");
+        match &self.sys_descr {
+            // Descriptions are often several lines long.
+            Some(descr) => write!(f, "{}: {}", name, descr.lines().next().unwrap_or_default())?,
+            None => f.write_str(name)?,
+        }
+        write!(f, " ({} {})", self.version, self.community)
+    }
+}
+
+/// Encodes a GetRequest for `oids`.
+pub fn get_request(
+    version: SnmpVersion,
+    community: &str,
+    request_id: i32,
+    oids: &[&str],
+) -> Option<Vec<u8>> {
+    let mut varbinds = Vec::new();
+    for oid in oids {
+        let mut varbind = tlv(TAG_OID, &encode_oid(oid)?);
+        varbind.extend_from_slice(&tlv(TAG_NULL, &[]));
+        varbinds.extend_from_slice(&tlv(TAG_SEQUENCE, &varbind));
+    }
+
+    let mut pdu = tlv(TAG_INTEGER, &encode_integer(request_id.into()));
+    pdu.extend_from_slice(&tlv(TAG_INTEGER, &[0]));
+    pdu.extend_from_slice(&tlv(TAG_INTEGER, &[0]));
+    pdu.extend_from_slice(&tlv(TAG_SEQUENCE, &varbinds));
+
+    let mut message = tlv(TAG_INTEGER, &encode_integer(version.number()));
+    message.extend_from_slice(&tlv(TAG_OCTET_STRING, community.as_bytes()));
+    message.extend_from_slice(&tlv(TAG_GET_REQUEST, &pdu));
+    Some(tlv(TAG_SEQUENCE, &message))
+}
+
+fn next_request_id() -> i32 {
+    static NEXT: AtomicI32 = AtomicI32::new(1);
+    (NEXT.fetch_add(1, Ordering::Relaxed) ^ std::process::id() as i32) & 0x7fff_ffff
+}
+
+async fn get(
+    socket: &UdpSocket,
+    version: SnmpVersion,
+    community: &str,
+    oids: &[&str],
+    wait: Duration,
+) -> Option<SnmpResponse> {
+    let request_id = next_request_id();
+    let request = get_request(version, community, request_id, oids)?;
+    socket.send(&request).await.ok()?;
+
+    let receive = async {
+        let mut buffer = vec![0u8; 65535];
+        loop {
+            let len = socket.recv(&mut buffer).await.ok()?;
+            if let Some(response) = SnmpResponse::parse(&buffer[..len])
+                && response.request_id == request_id
+            {
+                return Some(response);
+            }
+        }
+    };
+    timeout(wait, receive).await.ok()?
+}
+
+/// Reads the system group with one community and version.
+pub async fn query(
+    address: IpAddr,
+    port: u16,
+    version: SnmpVersion,
+    community: &str,
+    wait: Duration,
+) -> Option<SnmpInfo> {
+    let bind: IpAddr = match address {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((bind, 0)).await.ok()?;
+    socket.connect((address, port)).await.ok()?;
+
+    let response = get(&socket, version, community, &SYSTEM_OIDS, wait).await?;
+    if response.error_status == 0 {
+        return Some(SnmpInfo::from_response(version, community, &response));
+    }
+
+    // A v1 agent fails the whole request with noSuchName if it lacks any
+    // one of the objects, so ask for them one by one.
+    let mut varbinds = Vec::new();
+    for oid in SYSTEM_OIDS {
+        if let Some(single) = get(&socket, version, community, &[oid], wait).await
+            && single.error_status == 0
+        {
+            varbinds.extend(single.varbinds);
+        }
+    }
+    let merged = SnmpResponse {
+        varbinds,
+        ..response
+    };
+    Some(SnmpInfo::from_response(version, community, &merged))
+}
+
+/// BER type-length-value, with the long length form when needed.
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        encoded.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        encoded.push(0x80 | (bytes.len() - skip) as u8);
+        encoded.extend_from_slice(&bytes[skip..]);
+    }
+    encoded.extend_from_slice(content);
+    encoded
+}
+
+/// Tag, content and remaining bytes of the first BER element; `None`
+/// when it is malformed or not complete yet.
+pub fn read_tlv(bytes: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *bytes.first()?;
+    let first = *bytes.get(1)? as usize;
+    let (len, start) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let count = first & 0x7f;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let len = bytes
+            .get(2..2 + count)?
+            .iter()
+            .fold(0usize, |len, &b| (len << 8) | b as usize);
+        (len, 2 + count)
+    };
+    let content = bytes.get(start..start + len)?;
+    Some((tag, content, &bytes[start + len..]))
+}
+
+fn encode_integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    // Drop leading bytes that only repeat the sign.
+    while start < 7
+        && ((bytes[start] == 0 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+fn decode_integer(content: &[u8]) -> Option<i64> {
+    if content.is_empty() || content.len() > 8 {
+        return None;
+    }
+    let sign = if content[0] & 0x80 != 0 { -1i64 } else { 0 };
+    Some(
+        content
+            .iter()
+            .fold(sign, |value, &b| (value << 8) | b as i64),
+    )
+}
+
+fn decode_unsigned(content: &[u8]) -> Option<u64> {
+    if content.len() > 9 {
+        return None;
+    }
+    Some(
+        content
+            .iter()
+            .fold(0u64, |value, &b| (value << 8) | b as u64),
+    )
+}
+
+fn encode_oid(oid: &str) -> Option<Vec<u8>> {
+    let arcs: Vec<u32> = oid
+        .split('.')
+        .map(|arc| arc.parse().ok())
+        .collect::<Option<_>>()?;
+    if arcs.len() < 2 {
+        return None;
+    }
+
+    let mut encoded = vec![(arcs[0] * 40 + arcs[1]) as u8];
+    for &arc in &arcs[2..] {
+        let mut chunk = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            chunk.push(0x80 | (rest & 0x7f) as u8);
+            rest >>= 7;
+        }
+        encoded.extend(chunk.iter().rev());
+    }
+    Some(encoded)
+}
+
+fn decode_oid(content: &[u8]) -> Option<String> {
+    let (&first, rest) = content.split_first()?;
+    let mut arcs = vec![(first / 40) as u32, (first % 40) as u32];
+    let mut arc = 0u32;
+    for &b in rest {
+        arc = arc.checked_mul(128)? | (b & 0x7f) as u32;
+        if b & 0x80 == 0 {
+            arcs.push(arc);
+            arc = 0;
+        }
+    }
+    Some(
+        arcs.iter()
+            .map(|arc| arc.to_string())
+            .collect::<Vec<_>>()
+            .join("."),
+    )
+}
+
+fn decode_value(tag: u8, content: &[u8]) -> Option<SnmpValue> {
+    Some(match tag {
+        TAG_INTEGER => SnmpValue::Integer(decode_integer(content)?),
+        TAG_OCTET_STRING => SnmpValue::String(content.to_vec()),
+        TAG_OID => SnmpValue::Oid(decode_oid(content)?),
+        TAG_NULL => SnmpValue::Null,
+        TAG_IP_ADDRESS => SnmpValue::IpAddress(Ipv4Addr::from(<[u8; 4]>::try_from(content).ok()?)),
+        TAG_COUNTER32 | TAG_GAUGE32 | TAG_COUNTER64 => {
+            SnmpValue::Counter(decode_unsigned(content)?)
+        }
+        TAG_TIMETICKS => SnmpValue::TimeTicks(decode_unsigned(content)? as u32),
+        0x80..=0x82 => SnmpValue::Missing,
+        _ => SnmpValue::String(content.to_vec()),
+    })
+}
+
+pub struct SnmpFetcher {
+    config: Arc<ScannerConfig>,
+}
+
+impl SnmpFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Fetcher for SnmpFetcher {
+    fn id(&self) -> String {
+        "snmp".to_string()
+    }
+
+    fn name(&self) -> String {
+        "SNMP".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let address = subject.address();
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+
+        // Agents ignore requests with a wrong community or an unsupported
+        // version, so each combination costs a timeout.
+        for community in &self.config.snmp_communities {
+            for version in [SnmpVersion::V2c, SnmpVersion::V1] {
+                if let Some(info) = query(address, SNMP_PORT, version, community, wait).await {
+                    let summary = info.to_string();
+                    subject.set_parameter(SNMP.to_string(), info);
+                    return Ok(summary);
+                }
+            }
+        }
+        Ok("[n/a]".to_string())
+    }
+}
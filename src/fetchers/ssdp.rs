@@ -0,0 +1,227 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::timeout;
+
+use super::tls;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+use crate::network_utils;
+
+pub const SSDP: &str = "ssdp.info";
+
+pub const SSDP_PORT: u16 = 1900;
+
+const MAX_DESCRIPTION_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SsdpInfo {
+    /// `SERVER` header of the search response, e.g. `Linux/3.14 UPnP/1.0 miniupnpd/2.0`.
+    pub server: Option<String>,
+    pub location: Option<String>,
+    pub friendly_name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model_name: Option<String>,
+    pub model_number: Option<String>,
+    pub device_type: Option<String>,
+}
+
+impl SsdpInfo {
+    /// Reads the root device of a UPnP device description.
+    pub fn parse_description(&mut self, xml: &str) {
+        // Embedded devices follow the root device's own fields.
+        let root = match xml.find("<deviceList") {
+            Some(end) => &xml[..end],
+            None => xml,
+        };
+        self.friendly_name = xml_text(root, "friendlyName");
+        self.manufacturer = xml_text(root, "manufacturer");
+        self.model_name = xml_text(root, "modelName");
+        self.model_number = xml_text(root, "modelNumber");
+        self.device_type = xml_text(root, "deviceType");
+    }
+}
+
+impl fmt::Display for SsdpInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let model: Vec<&str> = [&self.manufacturer, &self.model_name, &self.model_number]
+            .into_iter()
+            .filter_map(|field| field.as_deref())
+            .collect();
+        match (&self.friendly_name, model.is_empty()) {
+            (Some(name), true) => f.write_str(name),
+            (Some(name), false) => write!(f, "{} ({})", name, model.join(" ")),
+            (None, false) => f.write_str(&model.join(" ")),
+            (None, true) => f.write_str(self.server.as_deref().unwrap_or("?")),
+        }
+    }
+}
+
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+    let text = xml[start..end]
+        .trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    (!text.is_empty()).then_some(text)
+}
+
+fn header(response: &str, name: &str) -> Option<String> {
+    response.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// Splits an `http(s)://host[:port]/path` URL into TLS flag, Host header
+/// value, port and path.
+fn split_url(url: &str) -> Option<(bool, &str, u16, &str)> {
+    let (tls, rest) = if let Some(rest) = url.strip_prefix("http://") {
+        (false, rest)
+    } else {
+        (true, url.strip_prefix("https://")?)
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/"),
+    };
+    let port = match authority.rsplit_once(':') {
+        // The last colon of a bare IPv6 literal is not a port separator.
+        Some((_, port)) if !port.ends_with(']') => port.parse().ok()?,
+        _ if tls => 443,
+        _ => 80,
+    };
+    Some((tls, authority, port, path))
+}
+
+async fn get<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    host: &str,
+    path: &str,
+    wait: Duration,
+) -> Option<String> {
+    // HTTP/1.0 keeps servers from answering with a chunked body.
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: ipscan-rs\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    timeout(wait, stream.write_all(request.as_bytes()))
+        .await
+        .ok()?
+        .ok()?;
+
+    let mut buffer = vec![0u8; MAX_DESCRIPTION_BYTES];
+    let len = network_utils::read_available(stream, &mut buffer, wait).await;
+    let response = String::from_utf8_lossy(&buffer[..len]);
+    if !response.starts_with("HTTP/") || response.split_whitespace().nth(1) != Some("200") {
+        return None;
+    }
+    let body = response.find("\r\n\r\n")? + 4;
+    Some(response[body..].to_string())
+}
+
+/// Fetches the description from the scanned host itself, whatever host the
+/// URL names, so that a reply cannot point the scanner elsewhere.
+async fn fetch_description(address: IpAddr, url: &str, wait: Duration) -> Option<String> {
+    let (tls, host, port, path) = split_url(url)?;
+    if tls {
+        let mut stream = tls::connect(address, port, wait).await.ok()?;
+        get(&mut stream, host, path, wait).await
+    } else {
+        let mut stream = timeout(wait, TcpStream::connect((address, port)))
+            .await
+            .ok()?
+            .ok()?;
+        get(&mut stream, host, path, wait).await
+    }
+}
+
+/// Sends a unicast M-SEARCH and reads the description the host points to.
+pub async fn query(address: IpAddr, port: u16, wait: Duration) -> Option<SsdpInfo> {
+    let bind: IpAddr = match address {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((bind, 0)).await.ok()?;
+    socket.connect((address, port)).await.ok()?;
+    let host = match address {
+        IpAddr::V4(v4) => format!("{}:{}", v4, port),
+        IpAddr::V6(v6) => format!("[{}]:{}", v6, port),
+    };
+    let search = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: 1\r\nST: upnp:rootdevice\r\n\r\n",
+        host
+    );
+    socket.send(search.as_bytes()).await.ok()?;
+
+    let receive = async {
+        let mut buffer = vec![0u8; 2048];
+        loop {
+            let len = socket.recv(&mut buffer).await.ok()?;
+            let response = String::from_utf8_lossy(&buffer[..len]).into_owned();
+            if response.starts_with("HTTP/1.1 200") {
+                return Some(response);
+            }
+        }
+    };
+    let response = timeout(wait, receive).await.ok()??;
+
+    let mut info = SsdpInfo {
+        server: header(&response, "SERVER"),
+        location: header(&response, "LOCATION"),
+        ..Default::default()
+    };
+    if let Some(location) = &info.location
+        && let Some(xml) = fetch_description(address, location, wait).await
+    {
+        info.parse_description(&xml);
+    }
+    Some(info)
+}
+
+pub struct SsdpFetcher {
+    config: Arc<ScannerConfig>,
+}
+
+impl SsdpFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Fetcher for SsdpFetcher {
+    fn id(&self) -> String {
+        "ssdp".to_string()
+    }
+
+    fn name(&self) -> String {
+        "UPnP".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        match query(subject.address(), SSDP_PORT, wait).await {
+            Some(info) => {
+                let summary = info.to_string();
+                subject.set_parameter(SSDP.to_string(), info);
+                Ok(summary)
+            }
+            None => Ok("[n/a]".to_string()),
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::banner::{BANNERS, Banners};
+use super::ports;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const SSH_SERVERS: &str = "ssh.servers";
+
+pub const SSH_PORTS: [u16; 3] = [22, 2222, 22222];
+
+const CLIENT_IDENT: &[u8] = b"SSH-2.0-ipscan_rs\r\n";
+const MSG_KEXINIT: u8 = 20;
+const MSG_KEX_INIT: u8 = 30;
+const MSG_KEX_REPLY: u8 = 31;
+const MAX_PACKET: usize = 35000;
+
+// Key exchanges we can start without doing any real cryptography: any 32
+// bytes are a valid curve25519 public key, and servers only check that a
+// DH value lies in range with enough bits set. We never derive the shared
+// secret, so neither needs a private key behind it.
+const CURVE25519: [&str; 2] = ["curve25519-sha256", "curve25519-sha256@libssh.org"];
+const DH_GROUP14: [&str; 2] = [
+    "diffie-hellman-group14-sha256",
+    "diffie-hellman-group14-sha1",
+];
+
+/// Algorithms considered broken or too weak, matched by prefix.
+const WEAK_ALGORITHMS: [&str; 16] = [
+    "diffie-hellman-group1-",
+    "diffie-hellman-group14-sha1",
+    "diffie-hellman-group-exchange-sha1",
+    "gss-gex-sha1-",
+    "gss-group1-sha1-",
+    "ssh-dss",
+    "ssh-rsa",
+    "3des-",
+    "arcfour",
+    "blowfish-",
+    "cast128-",
+    "aes128-cbc",
+    "aes192-cbc",
+    "aes256-cbc",
+    "hmac-md5",
+    "hmac-sha1-96",
+];
+
+pub type SshServers = BTreeMap<u16, SshServer>;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SshServer {
+    pub ident: String,
+    pub kex_algorithms: Vec<String>,
+    pub host_key_algorithms: Vec<String>,
+    pub ciphers: Vec<String>,
+    pub macs: Vec<String>,
+    pub host_key_type: Option<String>,
+    /// `SHA256:` followed by the unpadded base64 digest, as `ssh-keygen -l`
+    /// prints it.
+    pub fingerprint: Option<String>,
+    pub weak_algorithms: Vec<String>,
+}
+
+impl SshServer {
+    fn summary(&self) -> String {
+        let mut summary = self.ident.clone();
+        if let Some(fingerprint) = &self.fingerprint {
+            summary.push_str(&format!(" {}", fingerprint));
+        }
+        if !self.weak_algorithms.is_empty() {
+            summary.push_str(&format!(" (weak: {})", self.weak_algorithms.join(",")));
+        }
+        summary
+    }
+}
+
+pub fn is_weak_algorithm(name: &str) -> bool {
+    WEAK_ALGORITHMS.iter().any(|weak| name.starts_with(weak))
+}
+
+/// The algorithm lists of a KEXINIT payload, in protocol order.
+pub fn parse_kexinit(payload: &[u8]) -> Option<Vec<Vec<String>>> {
+    if payload.first() != Some(&MSG_KEXINIT) {
+        return None;
+    }
+    let mut rest = payload.get(17..)?;
+    let mut lists = Vec::with_capacity(10);
+    for _ in 0..10 {
+        let (list, tail) = read_string(rest)?;
+        let list = String::from_utf8_lossy(list);
+        lists.push(
+            list.split(',')
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+        );
+        rest = tail;
+    }
+    Some(lists)
+}
+
+/// SHA256 fingerprint of a host key blob.
+pub fn fingerprint(host_key: &[u8]) -> String {
+    format!(
+        "SHA256:{}",
+        STANDARD_NO_PAD.encode(Sha256::digest(host_key))
+    )
+}
+
+fn read_string(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let len = u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?) as usize;
+    let value = bytes.get(4..4 + len)?;
+    Some((value, &bytes[4 + len..]))
+}
+
+fn write_string(buffer: &mut Vec<u8>, value: &[u8]) {
+    buffer.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(value);
+}
+
+/// Wraps a payload in an unencrypted binary packet.
+fn packet(payload: &[u8]) -> Vec<u8> {
+    let mut padding = 8 - (5 + payload.len()) % 8;
+    if padding < 4 {
+        padding += 8;
+    }
+    let mut packet = Vec::with_capacity(5 + payload.len() + padding);
+    packet.extend_from_slice(&((1 + payload.len() + padding) as u32).to_be_bytes());
+    packet.push(padding as u8);
+    packet.extend_from_slice(payload);
+    packet.resize(packet.len() + padding, 0);
+    packet
+}
+
+async fn read_packet<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let len = reader.read_u32().await? as usize;
+    if !(5..=MAX_PACKET).contains(&len) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "bad packet length",
+        ));
+    }
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body).await?;
+    let padding = body[0] as usize;
+    body.get(1..len.saturating_sub(padding))
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad padding length"))
+}
+
+/// Our KEXINIT: the key exchanges we can start, and the server's own lists
+/// for everything else so that negotiation cannot fail on them.
+fn client_kexinit(server_lists: &[Vec<String>]) -> Vec<u8> {
+    let mut payload = vec![MSG_KEXINIT];
+    payload.extend_from_slice(&[0u8; 16]);
+    let kex = CURVE25519
+        .iter()
+        .chain(DH_GROUP14.iter())
+        .copied()
+        .collect::<Vec<_>>();
+    write_string(&mut payload, kex.join(",").as_bytes());
+    for list in &server_lists[1..8] {
+        write_string(&mut payload, list.join(",").as_bytes());
+    }
+    write_string(&mut payload, b"");
+    write_string(&mut payload, b"");
+    payload.push(0);
+    payload.extend_from_slice(&0u32.to_be_bytes());
+    payload
+}
+
+/// Unpredictable enough bytes for a public value nobody needs to break.
+fn random_bytes(len: usize) -> Vec<u8> {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    (0..len.div_ceil(32) as u32)
+        .flat_map(|block| {
+            Sha256::new()
+                .chain_update(seed.to_be_bytes())
+                .chain_update(block.to_be_bytes())
+                .finalize()
+        })
+        .take(len)
+        .collect()
+}
+
+fn kex_init(kex: &str) -> Option<Vec<u8>> {
+    let mut payload = vec![MSG_KEX_INIT];
+    if CURVE25519.contains(&kex) {
+        write_string(&mut payload, &random_bytes(32));
+    } else if DH_GROUP14.contains(&kex) {
+        // A positive mpint of about 2040 bits, below the 2048-bit prime.
+        let mut e = random_bytes(255);
+        e[0] = (e[0] & 0x7f) | 0x40;
+        write_string(&mut payload, &e);
+    } else {
+        return None;
+    }
+    Some(payload)
+}
+
+async fn handshake(stream: TcpStream) -> io::Result<SshServer> {
+    let mut reader = BufReader::new(stream);
+    reader.get_mut().write_all(CLIENT_IDENT).await?;
+
+    // Servers may send other lines before the identification string.
+    let mut ident = String::new();
+    for _ in 0..10 {
+        ident.clear();
+        if reader.read_line(&mut ident).await? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        if ident.starts_with("SSH-") {
+            break;
+        }
+    }
+    if !ident.starts_with("SSH-") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not an SSH server",
+        ));
+    }
+
+    let mut server = SshServer {
+        ident: ident.trim_end().to_string(),
+        ..Default::default()
+    };
+
+    let lists = parse_kexinit(&read_packet(&mut reader).await?)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad KEXINIT"))?;
+    server.kex_algorithms = lists[0].clone();
+    server.host_key_algorithms = lists[1].clone();
+    server.ciphers = lists[2].clone();
+    server.macs = lists[4].clone();
+    server.weak_algorithms = lists[..5]
+        .iter()
+        .flatten()
+        .filter(|name| is_weak_algorithm(name))
+        .cloned()
+        .collect();
+    server.weak_algorithms.sort();
+    server.weak_algorithms.dedup();
+
+    // The client's preference wins, so the first of ours the server has.
+    let Some(kex) = CURVE25519
+        .iter()
+        .chain(DH_GROUP14.iter())
+        .find(|kex| server.kex_algorithms.iter().any(|k| k == *kex))
+        .and_then(|kex| kex_init(kex))
+    else {
+        return Ok(server);
+    };
+
+    let stream = reader.get_mut();
+    stream.write_all(&packet(&client_kexinit(&lists))).await?;
+    stream.write_all(&packet(&kex)).await?;
+
+    let reply = read_packet(&mut reader).await?;
+    if reply.first() == Some(&MSG_KEX_REPLY)
+        && let Some((host_key, _)) = read_string(&reply[1..])
+    {
+        server.host_key_type = read_string(host_key)
+            .map(|(key_type, _)| String::from_utf8_lossy(key_type).into_owned());
+        server.fingerprint = Some(fingerprint(host_key));
+    }
+    Ok(server)
+}
+
+pub async fn inspect(address: IpAddr, port: u16, wait: Duration) -> Option<SshServer> {
+    let exchange = async {
+        let stream = TcpStream::connect((address, port)).await?;
+        handshake(stream).await
+    };
+    timeout(wait, exchange).await.ok()?.ok()
+}
+
+pub struct SshFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+}
+
+impl SshFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        Self { config, sockets }
+    }
+}
+
+#[async_trait]
+impl Fetcher for SshFetcher {
+    fn id(&self) -> String {
+        "ssh".to_string()
+    }
+
+    fn name(&self) -> String {
+        "SSH".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        // SSH on an unusual port still shows up in the banner.
+        let banners = subject.get_parameter::<Banners>(BANNERS);
+        let ssh_ports: Vec<u16> = ports::open_ports(subject)
+            .into_iter()
+            .filter(|port| {
+                SSH_PORTS.contains(port)
+                    || banners
+                        .and_then(|banners| banners.get(port))
+                        .is_some_and(|banner| banner.starts_with("SSH-"))
+            })
+            .collect();
+        if ssh_ports.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        let mut handshakes = JoinSet::new();
+        for port in ssh_ports {
+            let sockets = self.sockets.clone();
+            handshakes.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                (port, inspect(address, port, wait).await)
+            });
+        }
+
+        let mut servers = SshServers::new();
+        while let Some(handshake) = handshakes.join_next().await {
+            if let Ok((port, Some(server))) = handshake {
+                servers.insert(port, server);
+            }
+        }
+
+        if servers.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = servers
+            .iter()
+            .map(|(port, server)| format!("{}: {}", port, server.summary()))
+            .collect::<Vec<_>>()
+            .join("; ");
+        subject.set_parameter(SSH_SERVERS.to_string(), servers);
+        Ok(summary)
+    }
+}
@@ -0,0 +1,205 @@
+use pnet::packet::Packet;
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::tcp::{MutableTcpPacket, TcpFlags, TcpOption, ipv4_checksum};
+use pnet::transport::{
+    TransportChannelType, TransportProtocol, TransportReceiver, TransportSender, transport_channel,
+};
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, TcpListener};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+
+use super::ports::PortState;
+use crate::network_utils;
+
+type Pending = Arc<Mutex<HashMap<(Ipv4Addr, u16), oneshot::Sender<SynReply>>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SynReply {
+    pub state: PortState,
+    pub ttl: u8,
+}
+
+/// Half-open TCP scanner sharing one raw socket pair across a whole scan.
+///
+/// The kernel knows nothing about our SYNs, so it answers every SYN-ACK with
+/// a RST of its own, which tears down the half-open connection for us. The
+/// source port is bound for the scanner's lifetime so that no real local
+/// connection can be handed the same port and have its traffic misread.
+pub struct SynScanner {
+    sender: Mutex<TransportSender>,
+    pending: Pending,
+    source_port: u16,
+    sequence: u32,
+    running: Arc<AtomicBool>,
+    _source_port_guard: TcpListener,
+}
+
+impl SynScanner {
+    /// Fails with `PermissionDenied` when the process lacks CAP_NET_RAW.
+    pub fn new() -> io::Result<Self> {
+        if !cfg!(unix) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "SYN scan requires raw TCP sockets",
+            ));
+        }
+
+        let (sender, _) = transport_channel(
+            4096,
+            TransportChannelType::Layer4(TransportProtocol::Ipv4(IpNextHeaderProtocols::Tcp)),
+        )?;
+        // A layer 3 channel hands us the IP header too, which carries the TTL.
+        let (_, receiver) = transport_channel(
+            65535,
+            TransportChannelType::Layer3(IpNextHeaderProtocols::Tcp),
+        )?;
+
+        let source_port_guard = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        let source_port = source_port_guard.local_addr()?.port();
+        let sequence = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or_default()
+            ^ std::process::id();
+
+        let pending = Pending::default();
+        let running = Arc::new(AtomicBool::new(true));
+        spawn_receiver(
+            receiver,
+            pending.clone(),
+            running.clone(),
+            source_port,
+            sequence,
+        );
+
+        Ok(Self {
+            sender: Mutex::new(sender),
+            pending,
+            source_port,
+            sequence,
+            running,
+            _source_port_guard: source_port_guard,
+        })
+    }
+
+    /// Sends a SYN and waits for the answer. `None` means nothing came back.
+    pub async fn probe(
+        &self,
+        target: Ipv4Addr,
+        port: u16,
+        wait: Duration,
+    ) -> io::Result<Option<SynReply>> {
+        let source = match network_utils::local_address_for(IpAddr::V4(target)) {
+            Some(IpAddr::V4(source)) => source,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrNotAvailable,
+                    "no IPv4 route to target",
+                ));
+            }
+        };
+
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.pending
+            .lock()
+            .unwrap()
+            .insert((target, port), reply_tx);
+
+        let mut buffer = [0u8; 24];
+        let mut syn = MutableTcpPacket::new(&mut buffer).unwrap();
+        syn.set_source(self.source_port);
+        syn.set_destination(port);
+        syn.set_sequence(self.sequence);
+        syn.set_data_offset(6);
+        syn.set_flags(TcpFlags::SYN);
+        syn.set_window(1024);
+        syn.set_options(&[TcpOption::mss(1460)]);
+        syn.set_checksum(ipv4_checksum(&syn.to_immutable(), &source, &target));
+
+        let sent = self.sender.lock().unwrap().send_to(syn, IpAddr::V4(target));
+        if let Err(e) = sent {
+            self.pending.lock().unwrap().remove(&(target, port));
+            return Err(e);
+        }
+
+        match timeout(wait, reply_rx).await {
+            Ok(Ok(reply)) => Ok(Some(reply)),
+            _ => {
+                self.pending.lock().unwrap().remove(&(target, port));
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Drop for SynScanner {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(unix)]
+fn spawn_receiver(
+    mut receiver: TransportReceiver,
+    pending: Pending,
+    running: Arc<AtomicBool>,
+    source_port: u16,
+    sequence: u32,
+) {
+    use pnet::packet::tcp::TcpPacket;
+    use pnet::transport::ipv4_packet_iter;
+
+    std::thread::spawn(move || {
+        let mut packets = ipv4_packet_iter(&mut receiver);
+
+        while running.load(Ordering::Relaxed) {
+            let Ok(Some((ip, _))) = packets.next_with_timeout(Duration::from_millis(100)) else {
+                continue;
+            };
+            let Some(tcp) = TcpPacket::new(ip.payload()) else {
+                continue;
+            };
+
+            // Both SYN-ACK and RST acknowledge our SYN; anything else on the
+            // port is stale or spoofed.
+            if tcp.get_destination() != source_port
+                || tcp.get_acknowledgement() != sequence.wrapping_add(1)
+            {
+                continue;
+            }
+
+            let flags = tcp.get_flags();
+            let state = if flags & (TcpFlags::SYN | TcpFlags::ACK) == TcpFlags::SYN | TcpFlags::ACK
+            {
+                PortState::Open
+            } else if flags & TcpFlags::RST != 0 {
+                PortState::Closed
+            } else {
+                continue;
+            };
+
+            let key = (ip.get_source(), tcp.get_source());
+            if let Some(reply) = pending.lock().unwrap().remove(&key) {
+                let _ = reply.send(SynReply {
+                    state,
+                    ttl: ip.get_ttl(),
+                });
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn spawn_receiver(
+    _receiver: TransportReceiver,
+    _pending: Pending,
+    _running: Arc<AtomicBool>,
+    _source_port: u16,
+    _sequence: u32,
+) {
+}
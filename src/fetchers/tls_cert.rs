@@ -0,0 +1,349 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+use tokio_rustls::rustls::{CipherSuite, ProtocolVersion};
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+use super::ports;
+use super::tls;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const TLS_CERTIFICATES: &str = "tls.certificates";
+
+/// Ports that speak TLS from the first byte: HTTPS, LDAPS, SMTPS, IMAPS,
+/// POP3S, FTPS, DNS over TLS, SIP-TLS and WinRM over HTTPS.
+pub const TLS_PORTS: [u16; 12] = [
+    443, 465, 636, 853, 990, 993, 995, 4443, 5061, 5986, 8443, 9443,
+];
+
+pub type TlsCertificates = BTreeMap<u16, TlsCertificate>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TlsCertificate {
+    pub protocol: String,
+    pub cipher: String,
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub serial: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    /// Issued by its own subject. The signature itself is not checked.
+    pub self_signed: bool,
+}
+
+impl TlsCertificate {
+    /// Parses a DER certificate. The handshake details are left empty.
+    pub fn from_der(der: &[u8]) -> Option<Self> {
+        let (_, cert) = X509Certificate::from_der(der).ok()?;
+        let validity = cert.validity();
+
+        let sans = match cert.subject_alternative_name() {
+            Ok(Some(extension)) => extension
+                .value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(dns) => Some(dns.to_string()),
+                    GeneralName::IPAddress(bytes) => san_ip(bytes).map(|ip| ip.to_string()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Some(Self {
+            protocol: String::new(),
+            cipher: String::new(),
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            sans,
+            serial: cert.raw_serial_as_string(),
+            not_before: DateTime::from_timestamp(validity.not_before.timestamp(), 0)?,
+            not_after: DateTime::from_timestamp(validity.not_after.timestamp(), 0)?,
+            self_signed: cert.subject().as_raw() == cert.issuer().as_raw(),
+        })
+    }
+
+    /// Negative once the certificate has expired.
+    pub fn days_until_expiry(&self, now: DateTime<Utc>) -> i64 {
+        (self.not_after - now).num_days()
+    }
+
+    pub fn expires_within(&self, days: i64, now: DateTime<Utc>) -> bool {
+        self.days_until_expiry(now) < days
+    }
+}
+
+impl fmt::Display for TlsCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, expires {}",
+            self.subject,
+            self.protocol,
+            self.not_after.format("%Y-%m-%d")
+        )?;
+        if self.self_signed {
+            f.write_str(", self-signed")?;
+        }
+        f.write_str(")")
+    }
+}
+
+fn san_ip(bytes: &[u8]) -> Option<IpAddr> {
+    match bytes.len() {
+        4 => Some(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?).into()),
+        16 => Some(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?).into()),
+        _ => None,
+    }
+}
+
+fn protocol_name(version: ProtocolVersion) -> String {
+    match version {
+        ProtocolVersion::SSLv3 => "SSLv3".to_string(),
+        ProtocolVersion::TLSv1_0 => "TLSv1.0".to_string(),
+        ProtocolVersion::TLSv1_1 => "TLSv1.1".to_string(),
+        ProtocolVersion::TLSv1_2 => "TLSv1.2".to_string(),
+        ProtocolVersion::TLSv1_3 => "TLSv1.3".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Certificate of the TLS service on `port`. Servers that only speak
+/// TLS 1.0 or 1.1, which rustls refuses, are asked again with a raw
+/// handshake that stops at their Certificate message.
+pub async fn inspect(address: IpAddr, port: u16, wait: Duration) -> Option<TlsCertificate> {
+    let stream = match tls::connect(address, port, wait).await {
+        Ok(stream) => stream,
+        Err(error) if error.kind() == io::ErrorKind::TimedOut => return None,
+        Err(_) => return legacy_inspect(address, port, wait).await,
+    };
+    let (_, connection) = stream.get_ref();
+
+    let der = connection.peer_certificates()?.first()?;
+    let mut certificate = TlsCertificate::from_der(der)?;
+    if let Some(version) = connection.protocol_version() {
+        certificate.protocol = protocol_name(version);
+    }
+    if let Some(suite) = connection.negotiated_cipher_suite() {
+        certificate.cipher = format!("{:?}", suite.suite());
+    }
+    Some(certificate)
+}
+
+const HANDSHAKE: u8 = 22;
+const SERVER_HELLO: u8 = 2;
+const CERTIFICATE: u8 = 11;
+const SERVER_HELLO_DONE: u8 = 14;
+// Handshake messages in front of the Certificate are small, but a chain
+// can be long.
+const MAX_HANDSHAKE_BYTES: usize = 64 * 1024;
+
+/// CBC, 3DES and RC4 suites that TLS 1.0 appliances still pick from.
+/// rustls only names the suites it implements, so these carry their own.
+const LEGACY_SUITES: [(u16, &str); 11] = [
+    (0xc014, "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA"),
+    (0xc013, "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA"),
+    (0xc00a, "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA"),
+    (0xc009, "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA"),
+    (0x0039, "TLS_DHE_RSA_WITH_AES_256_CBC_SHA"),
+    (0x0033, "TLS_DHE_RSA_WITH_AES_128_CBC_SHA"),
+    (0x0035, "TLS_RSA_WITH_AES_256_CBC_SHA"),
+    (0x002f, "TLS_RSA_WITH_AES_128_CBC_SHA"),
+    (0x000a, "TLS_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0x0005, "TLS_RSA_WITH_RC4_128_SHA"),
+    (0x0004, "TLS_RSA_WITH_RC4_128_MD5"),
+];
+
+fn legacy_cipher_name(suite: u16) -> String {
+    LEGACY_SUITES
+        .iter()
+        .find(|(code, _)| *code == suite)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("{:?}", CipherSuite::from(suite)))
+}
+
+/// TLS 1.1 ClientHello with the legacy suites, the NIST curves and
+/// uncompressed points, which a TLS 1.0 server answers with its own version.
+fn legacy_client_hello() -> Vec<u8> {
+    let mut body = vec![3, 2];
+    // The random only matters for keys that are never derived.
+    body.extend_from_slice(&[0u8; 32]);
+    body.push(0);
+    body.extend_from_slice(&((LEGACY_SUITES.len() * 2) as u16).to_be_bytes());
+    for (suite, _) in LEGACY_SUITES {
+        body.extend_from_slice(&suite.to_be_bytes());
+    }
+    body.extend_from_slice(&[1, 0]);
+    let extensions: [u8; 21] = [
+        0x00, 0x0a, 0x00, 0x06, 0x00, 0x04, 0x00, 0x17, 0x00, 0x18, // supported_groups
+        0x00, 0x0b, 0x00, 0x02, 0x01, 0x00, // ec_point_formats
+        0xff, 0x01, 0x00, 0x01, 0x00, // renegotiation_info
+    ];
+    body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+    body.extend_from_slice(&extensions);
+
+    let mut handshake = vec![1];
+    handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    handshake.extend_from_slice(&body);
+
+    let mut record = vec![HANDSHAKE, 3, 1];
+    record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+    record.extend_from_slice(&handshake);
+    record
+}
+
+/// Reads handshake records until the server has sent its Certificate.
+/// Alerts and anything that is not a handshake end the exchange.
+async fn read_legacy_handshake(stream: &mut TcpStream) -> io::Result<Option<TlsCertificate>> {
+    let mut messages = Vec::new();
+    let mut hello = None;
+    loop {
+        let mut header = [0u8; 5];
+        stream.read_exact(&mut header).await?;
+        if header[0] != HANDSHAKE {
+            return Ok(None);
+        }
+        let len = u16::from_be_bytes([header[3], header[4]]) as usize;
+        if messages.len() + len > MAX_HANDSHAKE_BYTES {
+            return Ok(None);
+        }
+        let start = messages.len();
+        messages.resize(start + len, 0);
+        stream.read_exact(&mut messages[start..]).await?;
+
+        while messages.len() >= 4 {
+            let len = u32::from_be_bytes([0, messages[1], messages[2], messages[3]]) as usize;
+            if messages.len() < 4 + len {
+                break;
+            }
+            let message: Vec<u8> = messages.drain(..4 + len).collect();
+            let body = &message[4..];
+            match message[0] {
+                SERVER_HELLO => hello = parse_server_hello(body),
+                CERTIFICATE => {
+                    let Some((version, suite)) = hello else {
+                        return Ok(None);
+                    };
+                    let Some(mut certificate) =
+                        first_certificate(body).and_then(TlsCertificate::from_der)
+                    else {
+                        return Ok(None);
+                    };
+                    certificate.protocol = protocol_name(ProtocolVersion::from(version));
+                    certificate.cipher = legacy_cipher_name(suite);
+                    return Ok(Some(certificate));
+                }
+                SERVER_HELLO_DONE => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Version and cipher suite the server picked.
+fn parse_server_hello(body: &[u8]) -> Option<(u16, u16)> {
+    let version = u16::from_be_bytes([*body.first()?, *body.get(1)?]);
+    let session_len = *body.get(34)? as usize;
+    let suite = body.get(35 + session_len..37 + session_len)?;
+    Some((version, u16::from_be_bytes([suite[0], suite[1]])))
+}
+
+/// The leaf of a Certificate message.
+fn first_certificate(body: &[u8]) -> Option<&[u8]> {
+    let len = u32::from_be_bytes([0, *body.get(3)?, *body.get(4)?, *body.get(5)?]) as usize;
+    body.get(6..6 + len)
+}
+
+/// Handshake up to the server's Certificate, which is still sent in the
+/// clear before TLS 1.3.
+async fn legacy_inspect(address: IpAddr, port: u16, wait: Duration) -> Option<TlsCertificate> {
+    let exchange = async {
+        let mut stream = TcpStream::connect((address, port)).await?;
+        stream.write_all(&legacy_client_hello()).await?;
+        read_legacy_handshake(&mut stream).await
+    };
+    timeout(wait, exchange).await.ok()?.ok()?
+}
+
+pub struct TlsCertFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+}
+
+impl TlsCertFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        Self { config, sockets }
+    }
+}
+
+#[async_trait]
+impl Fetcher for TlsCertFetcher {
+    fn id(&self) -> String {
+        "tls_cert".to_string()
+    }
+
+    fn name(&self) -> String {
+        "TLS Certificate".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        let tls_ports: Vec<u16> = ports::open_ports(subject)
+            .into_iter()
+            .filter(|port| TLS_PORTS.contains(port))
+            .collect();
+        if tls_ports.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let wait = Duration::from_millis(self.config.service_timeout_ms);
+        let mut handshakes = JoinSet::new();
+        for port in tls_ports {
+            let sockets = self.sockets.clone();
+            handshakes.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                (port, inspect(address, port, wait).await)
+            });
+        }
+
+        let mut certificates = TlsCertificates::new();
+        while let Some(handshake) = handshakes.join_next().await {
+            if let Ok((port, Some(certificate))) = handshake {
+                certificates.insert(port, certificate);
+            }
+        }
+
+        if certificates.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = certificates
+            .iter()
+            .map(|(port, certificate)| format!("{}: {}", port, certificate))
+            .collect::<Vec<_>>()
+            .join("; ");
+        subject.set_parameter(TLS_CERTIFICATES.to_string(), certificates);
+        Ok(summary)
+    }
+}
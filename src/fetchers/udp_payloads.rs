@@ -0,0 +1,46 @@
+// Probe payloads for UDP services that stay silent on an empty datagram.
+
+/// DNS query for the root NS records.
+pub const DNS_QUERY: &[u8] = &[
+    0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00,
+    0x01,
+];
+
+/// SNMPv1 GetRequest for sysDescr.0 with community "public".
+pub const SNMP_GET_SYSDESCR: &[u8] = &[
+    0x30, 0x27, 0x02, 0x01, 0x00, 0x04, 0x06, 0x70, 0x75, 0x62, 0x6c, 0x69, 0x63, 0xa0, 0x1a, 0x02,
+    0x02, 0x12, 0x34, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x0e, 0x30, 0x0c, 0x06, 0x08, 0x2b,
+    0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00, 0x05, 0x00,
+];
+
+/// NTPv4 client request: LI 3, VN 4, mode 3, everything else zero.
+pub const NTP_REQUEST: &[u8] = &[
+    0xe3, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// NetBIOS node status (NBSTAT) query for the wildcard name "*".
+pub const NETBIOS_NBSTAT: &[u8] = &[
+    0x13, 0x37, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, b'C', b'K', b'A',
+    b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A',
+    b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', b'A', 0x00, 0x00, 0x21,
+    0x00, 0x01,
+];
+
+pub const SSDP_MSEARCH: &[u8] = b"M-SEARCH * HTTP/1.1\r\n\
+HOST: 239.255.255.250:1900\r\n\
+MAN: \"ssdp:discover\"\r\n\
+MX: 1\r\n\
+ST: ssdp:all\r\n\r\n";
+
+pub fn payload_for(port: u16) -> &'static [u8] {
+    match port {
+        53 | 5353 => DNS_QUERY,
+        123 => NTP_REQUEST,
+        137 => NETBIOS_NBSTAT,
+        161 => SNMP_GET_SYSDESCR,
+        1900 => SSDP_MSEARCH,
+        _ => &[],
+    }
+}
@@ -0,0 +1,558 @@
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::warn;
+
+use super::http::{self, HTTP_RESPONSES, HttpResponses, TLS_WEB_PORTS, WEB_PORTS};
+use super::ports;
+use super::traits::Fetcher;
+use crate::config::ScannerConfig;
+use crate::core::subject::ScanningSubject;
+use crate::errors::ScanError;
+
+pub const WEB_TECHNOLOGIES: &str = "web.technologies";
+
+const BUILTIN_RULES: &str = include_str!("../assets/web-tech.json");
+// Login pages of appliances are usually one or two redirects away.
+const MAX_REDIRECTS: usize = 2;
+
+/// MurmurHash3, x86 32-bit variant.
+pub fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let mix = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+    let mut hash = seed;
+    let mut blocks = data.chunks_exact(4);
+    for block in blocks.by_ref() {
+        hash ^= mix(u32::from_le_bytes(block.try_into().unwrap_or_default()));
+        hash = hash
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe654_6b64);
+    }
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .rev()
+            .fold(0u32, |k, &byte| (k << 8) | byte as u32);
+        hash ^= mix(k);
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^ (hash >> 16)
+}
+
+/// Favicon hash as Shodan computes it: MurmurHash3 of the base64 text,
+/// wrapped at 76 columns with a newline after every line.
+pub fn favicon_hash(icon: &[u8]) -> i32 {
+    let encoded = STANDARD.encode(icon);
+    let mut wrapped = Vec::with_capacity(encoded.len() + encoded.len() / 76 + 1);
+    for line in encoded.as_bytes().chunks(76) {
+        wrapped.extend_from_slice(line);
+        wrapped.push(b'\n');
+    }
+    murmur3_32(&wrapped, 0) as i32
+}
+
+/// An HTTP response with every header and the decoded body.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebPage {
+    pub status: u16,
+    /// Names in lower case, in the order the server sent them.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl WebPage {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let end = bytes.windows(4).position(|w| w == b"\r\n\r\n")?;
+        let head = String::from_utf8_lossy(&bytes[..end]);
+        let mut lines = head.lines();
+        let status_line = lines.next()?;
+        if !status_line.starts_with("HTTP/") {
+            return None;
+        }
+        let status = status_line.split_whitespace().nth(1)?.parse().ok()?;
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            })
+            .collect();
+
+        let mut page = WebPage {
+            status,
+            headers,
+            body: bytes[end + 4..].to_vec(),
+        };
+        if page
+            .header("transfer-encoding")
+            .is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked"))
+        {
+            page.body = dechunk(&page.body);
+        }
+        Some(page)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Name and value of each `Set-Cookie`.
+    pub fn cookies(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .iter()
+            .filter(|(name, _)| name == "set-cookie")
+            .filter_map(|(_, value)| {
+                let pair = value.split(';').next()?;
+                let (name, value) = pair.split_once('=')?;
+                Some((name.trim(), value.trim()))
+            })
+    }
+
+    /// `name` or `property` and `content` of each `<meta>` tag, the former
+    /// in lower case.
+    pub fn meta(&self) -> Vec<(String, String)> {
+        let body = String::from_utf8_lossy(&self.body);
+        let lower = body.to_ascii_lowercase();
+        let mut tags = Vec::new();
+        let mut from = 0;
+        while let Some(start) = lower[from..].find("<meta") {
+            let start = from + start;
+            let Some(len) = lower[start..].find('>') else {
+                break;
+            };
+            let tag = &body[start..start + len];
+            from = start + len;
+            let key = attribute(tag, "name").or_else(|| attribute(tag, "property"));
+            if let (Some(key), Some(content)) = (key, attribute(tag, "content")) {
+                tags.push((key.to_ascii_lowercase(), content.to_string()));
+            }
+        }
+        tags
+    }
+}
+
+/// Quoted value of attribute `name` inside an HTML tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(found) = lower[from..].find(name) {
+        let at = from + found;
+        from = at + name.len();
+        // Skip matches inside longer names such as `http-equiv-name`.
+        if !lower[..at].ends_with(|c: char| c.is_whitespace()) {
+            continue;
+        }
+        let Some(rest) = tag[from..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &rest[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+fn dechunk(body: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(line_end) = rest.windows(2).position(|w| w == b"\r\n") {
+        let size_text = String::from_utf8_lossy(&rest[..line_end]);
+        let size_text = size_text.split(';').next().unwrap_or_default().trim();
+        let Ok(size) = usize::from_str_radix(size_text, 16) else {
+            break;
+        };
+        if size == 0 {
+            break;
+        }
+        let chunk = &rest[line_end + 2..];
+        // The read may have cut the last chunk short.
+        let take = size.min(chunk.len());
+        decoded.extend_from_slice(&chunk[..take]);
+        rest = chunk.get(take + 2..).unwrap_or_default();
+    }
+    decoded
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WebTechnology {
+    pub name: String,
+    pub category: Option<String>,
+    pub version: Option<String>,
+}
+
+impl fmt::Display for WebTechnology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if let Some(version) = &self.version {
+            write!(f, " {}", version)?;
+        }
+        Ok(())
+    }
+}
+
+pub type WebTechnologies = BTreeMap<u16, Vec<WebTechnology>>;
+
+/// One entry of the rules file. Patterns are regexes; the first capture
+/// group that matches is the version.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RuleSpec {
+    category: Option<String>,
+    headers: BTreeMap<String, String>,
+    cookies: BTreeMap<String, String>,
+    meta: BTreeMap<String, String>,
+    body: Vec<String>,
+    favicon: Vec<i32>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    name: String,
+    category: Option<String>,
+    headers: Vec<(String, Regex)>,
+    cookies: Vec<(String, Regex)>,
+    meta: Vec<(String, Regex)>,
+    body: Vec<Regex>,
+    favicon: Vec<i32>,
+}
+
+/// Whether `pattern` matches `text`, taking the version from its first
+/// capture group if none was found yet.
+fn matches(pattern: &Regex, text: &str, version: &mut Option<String>) -> bool {
+    let Some(captures) = pattern.captures(text) else {
+        return false;
+    };
+    if version.is_none() {
+        *version = captures
+            .iter()
+            .skip(1)
+            .flatten()
+            .map(|m| m.as_str().to_string())
+            .find(|v| !v.is_empty());
+    }
+    true
+}
+
+impl Rule {
+    fn detect(&self, pages: &[WebPage], favicon: Option<i32>) -> Option<WebTechnology> {
+        let mut version = None;
+        let mut found = favicon.is_some_and(|hash| self.favicon.contains(&hash));
+        for page in pages {
+            for (name, pattern) in &self.headers {
+                for (header, value) in &page.headers {
+                    found |= header == name && matches(pattern, value, &mut version);
+                }
+            }
+            for (name, pattern) in &self.cookies {
+                for (cookie, value) in page.cookies() {
+                    found |= cookie == name && matches(pattern, value, &mut version);
+                }
+            }
+            if !self.meta.is_empty() {
+                for (key, content) in page.meta() {
+                    for (name, pattern) in &self.meta {
+                        found |= key == *name && matches(pattern, &content, &mut version);
+                    }
+                }
+            }
+            if !self.body.is_empty() {
+                let body = String::from_utf8_lossy(&page.body);
+                for pattern in &self.body {
+                    found |= matches(pattern, &body, &mut version);
+                }
+            }
+        }
+        found.then(|| WebTechnology {
+            name: self.name.clone(),
+            category: self.category.clone(),
+            version,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WebTechRules {
+    rules: Vec<Rule>,
+    /// Patterns using regex syntax we do not support, such as lookarounds.
+    pub skipped: usize,
+}
+
+impl WebTechRules {
+    /// Parses a rules file: a JSON object from technology name to its
+    /// `category`, `headers`, `cookies`, `meta`, `body` and `favicon` rules.
+    pub fn parse(json: &str) -> Result<Self, ScanError> {
+        let specs: BTreeMap<String, RuleSpec> =
+            serde_json::from_str(json).map_err(|e| ScanError::WebTechRules(e.to_string()))?;
+        let mut rules = Self::default();
+        for (name, spec) in specs {
+            let mut compile = |pattern: &str| {
+                let compiled = Regex::new(pattern).ok();
+                rules.skipped += compiled.is_none() as usize;
+                compiled
+            };
+            let mut named = |patterns: BTreeMap<String, String>| -> Vec<(String, Regex)> {
+                patterns
+                    .into_iter()
+                    .filter_map(|(key, pattern)| Some((key, compile(&pattern)?)))
+                    .collect()
+            };
+            let headers = named(spec.headers)
+                .into_iter()
+                .map(|(header, pattern)| (header.to_ascii_lowercase(), pattern))
+                .collect();
+            let cookies = named(spec.cookies);
+            let meta = named(spec.meta)
+                .into_iter()
+                .map(|(key, pattern)| (key.to_ascii_lowercase(), pattern))
+                .collect();
+            let body = spec.body.iter().filter_map(|p| compile(p)).collect();
+            rules.rules.push(Rule {
+                name,
+                category: spec.category,
+                headers,
+                cookies,
+                meta,
+                body,
+                favicon: spec.favicon,
+            });
+        }
+        Ok(rules)
+    }
+
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_RULES).expect("the built-in web technology rules parse")
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Adds the rules of `other`, which replace ours of the same name.
+    pub fn extend(&mut self, other: WebTechRules) {
+        self.skipped += other.skipped;
+        for rule in other.rules {
+            self.rules.retain(|known| known.name != rule.name);
+            self.rules.push(rule);
+        }
+    }
+
+    /// The built-in rules, extended by the file at `path` if given.
+    pub fn load(path: Option<&str>) -> Self {
+        let mut rules = Self::builtin();
+        if let Some(path) = path {
+            match std::fs::read_to_string(path)
+                .map_err(ScanError::from)
+                .and_then(|json| Self::parse(&json))
+            {
+                Ok(extra) => rules.extend(extra),
+                Err(e) => warn!("Ignoring web technology rules {}: {}", path, e),
+            }
+        }
+        rules
+    }
+
+    fn uses_favicons(&self) -> bool {
+        self.rules.iter().any(|rule| !rule.favicon.is_empty())
+    }
+
+    /// Technologies found in `pages` or by the favicon hash, by name.
+    pub fn detect(&self, pages: &[WebPage], favicon: Option<i32>) -> Vec<WebTechnology> {
+        let mut found: Vec<WebTechnology> = self
+            .rules
+            .iter()
+            .filter_map(|rule| rule.detect(pages, favicon))
+            .collect();
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        found
+    }
+}
+
+/// Path to follow from a redirect, when it stays on the same server. An
+/// absolute URL must keep the scheme, host and port, with the default port
+/// of the scheme when it names none, so an upgrade to https is not followed.
+pub fn redirect_path(location: &str, address: IpAddr, port: u16, tls: bool) -> Option<String> {
+    if location.starts_with('/') && !location.starts_with("//") {
+        return Some(location.to_string());
+    }
+    let scheme = if tls { "https://" } else { "http://" };
+    let rest = location
+        .get(..scheme.len())
+        .filter(|prefix| prefix.eq_ignore_ascii_case(scheme))
+        .map(|_| &location[scheme.len()..])?;
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+
+    // Brackets set an IPv6 host apart from the port that may follow it.
+    let (host, explicit_port) = match authority.strip_prefix('[') {
+        Some(bracketed) => bracketed.split_once(']')?,
+        None => authority.split_at(authority.find(':').unwrap_or(authority.len())),
+    };
+    let target_port = match explicit_port {
+        "" if tls => 443,
+        "" => 80,
+        explicit => explicit.strip_prefix(':')?.parse().ok()?,
+    };
+    let same_server = host.parse::<IpAddr>().ok()? == address && target_port == port;
+    same_server.then(|| format!("/{}", path.trim_start_matches('/')))
+}
+
+/// Fetches `/` with up to two same-server redirects, and the favicon when
+/// `favicon` is set, then applies `rules` to all of it.
+pub async fn identify(
+    rules: &WebTechRules,
+    address: IpAddr,
+    port: u16,
+    tls: bool,
+    favicon: bool,
+    wait: Duration,
+) -> Option<Vec<WebTechnology>> {
+    let mut pages = Vec::new();
+    let mut path = "/".to_string();
+    for _ in 0..=MAX_REDIRECTS {
+        let Some(page) = http::get_raw(address, port, tls, &path, wait)
+            .await
+            .and_then(|response| WebPage::parse(&response))
+        else {
+            break;
+        };
+        let next = (300..400)
+            .contains(&page.status)
+            .then(|| page.header("location"))
+            .flatten()
+            .and_then(|location| redirect_path(location, address, port, tls));
+        pages.push(page);
+        match next {
+            Some(next) if next != path => path = next,
+            _ => break,
+        }
+    }
+    if pages.is_empty() {
+        return None;
+    }
+
+    let favicon_hash = if favicon {
+        http::get_raw(address, port, tls, "/favicon.ico", wait)
+            .await
+            .and_then(|response| WebPage::parse(&response))
+            .filter(|icon| icon.status == 200 && !icon.body.is_empty())
+            .map(|icon| favicon_hash(&icon.body))
+    } else {
+        None
+    };
+    Some(rules.detect(&pages, favicon_hash))
+}
+
+pub struct WebTechFetcher {
+    config: Arc<ScannerConfig>,
+    sockets: Arc<Semaphore>,
+    rules: Arc<WebTechRules>,
+}
+
+impl WebTechFetcher {
+    pub fn new(config: Arc<ScannerConfig>) -> Self {
+        let sockets = ports::socket_limit(&config);
+        Self::with_sockets(config, sockets)
+    }
+
+    pub fn with_sockets(config: Arc<ScannerConfig>, sockets: Arc<Semaphore>) -> Self {
+        let rules = WebTechRules::load(config.web_tech_rules_file.as_deref());
+        Self {
+            config,
+            sockets,
+            rules: Arc::new(rules),
+        }
+    }
+}
+
+#[async_trait]
+impl Fetcher for WebTechFetcher {
+    fn id(&self) -> String {
+        "web_tech".to_string()
+    }
+
+    fn name(&self) -> String {
+        "Web Technologies".to_string()
+    }
+
+    async fn scan(&self, subject: &mut ScanningSubject) -> Result<String, ScanError> {
+        // The HTTP fetcher already knows which scheme each port speaks;
+        // without it, guess from the port as it does.
+        let targets: Vec<(u16, bool)> = match subject.get_parameter::<HttpResponses>(HTTP_RESPONSES)
+        {
+            Some(responses) => responses
+                .iter()
+                .map(|(port, response)| (*port, response.tls))
+                .collect(),
+            None => ports::open_ports(subject)
+                .into_iter()
+                .filter(|port| WEB_PORTS.contains(port))
+                .map(|port| (port, TLS_WEB_PORTS.contains(&port)))
+                .collect(),
+        };
+        if targets.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let address = subject.address();
+        let wait = Duration::from_millis(self.config.http_timeout_ms);
+        let favicon = self.rules.uses_favicons();
+        let mut lookups = JoinSet::new();
+        for (port, tls) in targets {
+            let rules = self.rules.clone();
+            let sockets = self.sockets.clone();
+            lookups.spawn(async move {
+                let _socket = sockets.acquire_owned().await.unwrap();
+                let found = identify(&rules, address, port, tls, favicon, wait).await;
+                (port, found)
+            });
+        }
+
+        let mut technologies = WebTechnologies::new();
+        while let Some(lookup) = lookups.join_next().await {
+            if let Ok((port, Some(found))) = lookup
+                && !found.is_empty()
+            {
+                technologies.insert(port, found);
+            }
+        }
+
+        if technologies.is_empty() {
+            return Ok("[n/a]".to_string());
+        }
+
+        let summary = technologies
+            .iter()
+            .map(|(port, found)| {
+                let names: Vec<String> = found.iter().map(|t| t.to_string()).collect();
+                format!("{}: {}", port, names.join(", "))
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        subject.set_parameter(WEB_TECHNOLOGIES.to_string(), technologies);
+        Ok(summary)
+    }
+}
@@ -1,10 +1,35 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 use eframe::egui;
 use egui_extras::{Column, TableBuilder};
+use ipscan_rs::fetchers::banner::{BANNERS, Banners};
+use ipscan_rs::fetchers::capabilities::{Capabilities, SERVICE_CAPABILITIES};
+use ipscan_rs::fetchers::database::{DATABASE_SERVERS, DatabaseServers};
+use ipscan_rs::fetchers::dns_server::{DNS_SERVER, DnsServerInfo};
+use ipscan_rs::fetchers::http::{HTTP_RESPONSES, HttpResponses};
+use ipscan_rs::fetchers::industrial::{INDUSTRIAL, IndustrialInfo};
+use ipscan_rs::fetchers::ldap::{LDAP_SERVERS, LdapServers};
+use ipscan_rs::fetchers::llmnr::LLMNR_NAME;
+use ipscan_rs::fetchers::mdns::{MDNS, MdnsInfo};
+use ipscan_rs::fetchers::netbios::{NETBIOS, NetBiosInfo};
+use ipscan_rs::fetchers::ping::PING_STATS;
+use ipscan_rs::fetchers::ports::{
+    PORT_STATES, PortState, PortStates, UDP_PORT_STATES, format_ports, ports_in_state,
+};
+use ipscan_rs::fetchers::printer::{PRINTERS, Printers};
+use ipscan_rs::fetchers::remote_desktop::{REMOTE_DESKTOPS, RemoteDesktop, RemoteDesktops};
+use ipscan_rs::fetchers::service_fingerprint::{SERVICE_FINGERPRINTS, ServiceFingerprints};
+use ipscan_rs::fetchers::smb::{SMB, SmbInfo};
+use ipscan_rs::fetchers::snmp::{SNMP, SnmpInfo};
+use ipscan_rs::fetchers::ssdp::{SSDP, SsdpInfo};
+use ipscan_rs::fetchers::ssh::{SSH_SERVERS, SshServers};
+use ipscan_rs::fetchers::tls_cert::{TLS_CERTIFICATES, TlsCertificates};
+use ipscan_rs::fetchers::web_tech::{WEB_TECHNOLOGIES, WebTechnologies};
 use ipscan_rs::{
-    Feeder, FetcherRegistry, RangeFeeder, ResultType, ScannerConfig, ScanningResult,
-    ScanningSubject, network_utils,
+    Feeder, FetcherRegistry, PingStats, PortScanMode, RangeFeeder, ResultType, ScannerConfig,
+    ScanningResult, ScanningSubject, network_utils, resolver,
 };
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::HashMap;
 use std::net::IpAddr;
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -18,11 +43,154 @@ struct ScanResult {
     address: String,
     hostname: String,
     ping: String,
+    ping_stats: Option<PingStats>,
     ports: String,
+    port_states: PortStates,
+    udp_states: PortStates,
     mac: String,
+    banners: Banners,
+    http: HttpResponses,
+    certificates: TlsCertificates,
+    ssh: SshServers,
+    netbios: Option<NetBiosInfo>,
+    mdns: Option<MdnsInfo>,
+    llmnr: Option<String>,
+    snmp: Option<SnmpInfo>,
+    ssdp: Option<SsdpInfo>,
+    smb: Option<SmbInfo>,
+    databases: DatabaseServers,
+    capabilities: Capabilities,
+    remote_desktops: RemoteDesktops,
+    printers: Printers,
+    industrial: Option<IndustrialInfo>,
+    ldap: LdapServers,
+    dns_server: Option<DnsServerInfo>,
+    services: ServiceFingerprints,
+    web_technologies: WebTechnologies,
+    extra: HashMap<String, String>,
     status: ResultType,
 }
 
+impl ScanResult {
+    fn has_open_ports(&self) -> bool {
+        self.ports != "[n/a]" && self.ports != "[n/s]"
+    }
+
+    fn has_open_tcp_port(&self, port: u16) -> bool {
+        self.port_states.get(&port) == Some(&PortState::Open)
+    }
+
+    fn has_expiring_certificate(&self, days: u32) -> bool {
+        let now = chrono::Utc::now();
+        self.certificates
+            .values()
+            .any(|cert| cert.expires_within(days.into(), now))
+    }
+
+    fn has_unauthenticated_database(&self) -> bool {
+        self.databases.values().any(|server| server.unauthenticated)
+    }
+
+    fn has_weak_remote_desktop(&self) -> bool {
+        self.remote_desktops.values().any(RemoteDesktop::is_weak)
+    }
+
+    fn has_exposed_dns_server(&self) -> bool {
+        self.dns_server
+            .as_ref()
+            .is_some_and(DnsServerInfo::is_exposed)
+    }
+
+    fn has_anonymous_mqtt(&self) -> bool {
+        self.industrial
+            .as_ref()
+            .is_some_and(IndustrialInfo::has_anonymous_mqtt)
+    }
+
+    /// Port of a detected RDP server, or 3389 when it is merely open.
+    fn rdp_port(&self) -> Option<u16> {
+        self.remote_desktops
+            .iter()
+            .find(|(_, desktop)| matches!(desktop, RemoteDesktop::Rdp { .. }))
+            .map(|(port, _)| *port)
+            .or_else(|| self.has_open_tcp_port(3389).then_some(3389))
+    }
+
+    fn ports_in(&self, state: PortState) -> String {
+        format_ports(&ports_in_state(&self.port_states, state))
+    }
+
+    fn udp_ports_in(&self, state: PortState) -> String {
+        format_ports(&ports_in_state(&self.udp_states, state))
+    }
+
+    fn ports_text(&self, show_closed: bool, show_filtered: bool) -> String {
+        let mut parts = Vec::new();
+        if self.has_open_ports() {
+            parts.push(self.ports.clone());
+        }
+        for (show, state) in [
+            (show_closed, PortState::Closed),
+            (show_filtered, PortState::Filtered),
+        ] {
+            let ports = self.ports_in(state);
+            if show && !ports.is_empty() {
+                parts.push(format!("{}: {}", state, ports));
+            }
+        }
+        for (show, state) in [
+            (show_closed, PortState::Closed),
+            (show_filtered, PortState::OpenFiltered),
+            (show_filtered, PortState::Filtered),
+        ] {
+            let ports = self.udp_ports_in(state);
+            if show && !ports.is_empty() {
+                parts.push(format!("udp {}: {}", state, ports));
+            }
+        }
+
+        if parts.is_empty() {
+            self.ports.clone()
+        } else {
+            parts.join(" | ")
+        }
+    }
+}
+
+// Fetchers with a dedicated column; every other selected fetcher gets a
+// column of its own after these.
+const CORE_FETCHERS: [&str; 4] = ["ping", "hostname", "ports", "mac"];
+
+#[derive(Clone)]
+struct FetcherChoice {
+    id: String,
+    name: String,
+    enabled: bool,
+}
+
+fn default_fetcher_choices() -> Vec<FetcherChoice> {
+    let mut registry = FetcherRegistry::new();
+    registry.register_default_fetchers(Arc::new(ScannerConfig::default()));
+    registry
+        .fetchers()
+        .iter()
+        .filter(|f| !CORE_FETCHERS.contains(&f.id().as_str()))
+        .map(|f| FetcherChoice {
+            id: f.id(),
+            name: f.name(),
+            enabled: registry.is_selected(&f.id()),
+        })
+        .collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 #[allow(dead_code)]
 enum ScanState {
     Idle,
@@ -40,6 +208,64 @@ enum ScanState {
 #[derive(Clone, Copy, PartialEq)]
 enum SortColumn {
     IpAddress,
+    Ping,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PingMetric {
+    Average,
+    Minimum,
+    Maximum,
+    StdDev,
+    Jitter,
+    Loss,
+    Ttl,
+}
+
+impl PingMetric {
+    const ALL: [PingMetric; 7] = [
+        PingMetric::Average,
+        PingMetric::Minimum,
+        PingMetric::Maximum,
+        PingMetric::StdDev,
+        PingMetric::Jitter,
+        PingMetric::Loss,
+        PingMetric::Ttl,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            PingMetric::Average => "Ping Avg",
+            PingMetric::Minimum => "Ping Min",
+            PingMetric::Maximum => "Ping Max",
+            PingMetric::StdDev => "Ping StdDev",
+            PingMetric::Jitter => "Ping Jitter",
+            PingMetric::Loss => "Packet Loss",
+            PingMetric::Ttl => "TTL",
+        }
+    }
+
+    fn value(self, stats: &PingStats) -> Option<f64> {
+        match self {
+            PingMetric::Loss => Some(stats.loss_percent),
+            PingMetric::Ttl => stats.ttl.map(f64::from),
+            _ if !stats.is_alive() => None,
+            PingMetric::Average => Some(stats.avg_ms),
+            PingMetric::Minimum => Some(stats.min_ms),
+            PingMetric::Maximum => Some(stats.max_ms),
+            PingMetric::StdDev => Some(stats.stddev_ms),
+            PingMetric::Jitter => Some(stats.jitter_ms),
+        }
+    }
+
+    fn format(self, stats: Option<&PingStats>) -> String {
+        match (self, stats.and_then(|s| self.value(s))) {
+            (_, None) => "[n/a]".to_string(),
+            (PingMetric::Loss, Some(v)) => format!("{:.0}%", v),
+            (PingMetric::Ttl, Some(v)) => format!("{}", v),
+            (_, Some(v)) => format!("{:.1} ms", v),
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -63,6 +289,8 @@ struct IpScanApp {
     runtime: Arc<Runtime>,
     filter_text: String,
     show_dead: bool,
+    show_closed: bool,
+    show_filtered: bool,
 
     total_hosts: usize,
     alive_hosts: usize,
@@ -81,6 +309,7 @@ struct IpScanApp {
 
     sort_column: Option<SortColumn>,
     sort_order: SortOrder,
+    ping_metric: PingMetric,
 
     scan_cancellation: Option<Arc<AtomicBool>>,
 }
@@ -93,9 +322,31 @@ struct Settings {
     port_timeout: u64,
     min_port_timeout: u64,
     adapt_port_timeout: bool,
+    port_concurrency: usize,
+    max_sockets: usize,
+    syn_scan: bool,
+    banner_timeout: u64,
+    banner_max_bytes: usize,
+    banner_print_ports: bool,
+    http_timeout: u64,
+    netbios_timeout: u64,
+    service_timeout: u64,
+    cert_warning_days: u32,
+    dns_servers: String,
+    dns_timeout: u64,
+    dns_retries: u32,
+    dns_confirm: bool,
+    snmp_communities: String,
+    smb1_probe: bool,
+    ftp_anonymous: bool,
+    industrial_passive_only: bool,
+    axfr_zone: String,
+    service_probes_file: String,
+    web_tech_rules_file: String,
     scan_dead: bool,
     auto_save_results: bool,
     theme: Theme,
+    fetchers: Vec<FetcherChoice>,
 }
 
 #[derive(Clone, PartialEq)]
@@ -113,9 +364,31 @@ impl Default for Settings {
             port_timeout: 500,
             min_port_timeout: 100,
             adapt_port_timeout: true,
+            port_concurrency: 50,
+            max_sockets: 500,
+            syn_scan: false,
+            banner_timeout: 2000,
+            banner_max_bytes: 256,
+            banner_print_ports: false,
+            http_timeout: 3000,
+            netbios_timeout: 500,
+            service_timeout: 3000,
+            cert_warning_days: 30,
+            dns_servers: String::new(),
+            dns_timeout: 1000,
+            dns_retries: 1,
+            dns_confirm: false,
+            snmp_communities: "public".to_string(),
+            smb1_probe: false,
+            ftp_anonymous: false,
+            industrial_passive_only: true,
+            axfr_zone: String::new(),
+            service_probes_file: String::new(),
+            web_tech_rules_file: String::new(),
             scan_dead: false,
             auto_save_results: false,
             theme: Theme::Dark,
+            fetchers: default_fetcher_choices(),
         }
     }
 }
@@ -148,6 +421,8 @@ impl Default for IpScanApp {
             runtime: Arc::new(Runtime::new().unwrap()),
             filter_text: String::new(),
             show_dead: false,
+            show_closed: false,
+            show_filtered: false,
 
             total_hosts: 0,
             alive_hosts: 0,
@@ -166,6 +441,7 @@ impl Default for IpScanApp {
 
             sort_column: None,
             sort_order: SortOrder::Ascending,
+            ping_metric: PingMetric::Average,
 
             scan_cancellation: None,
         }
@@ -313,17 +589,42 @@ impl IpScanApp {
         Some((first_ip, last_ip, total_hosts))
     }
 
+    fn toggle_sort(&mut self, column: SortColumn) {
+        if self.sort_column == Some(column) {
+            self.sort_order = match self.sort_order {
+                SortOrder::Ascending => SortOrder::Descending,
+                SortOrder::Descending => SortOrder::Ascending,
+            };
+        } else {
+            self.sort_column = Some(column);
+            self.sort_order = SortOrder::Ascending;
+        }
+        self.sort_results();
+    }
+
     fn sort_results(&mut self) {
-        if self.sort_column.is_some() {
+        if let Some(column) = self.sort_column {
             let mut results = self.results.lock().unwrap();
+            let metric = self.ping_metric;
 
             results.sort_by(|a, b| {
-                let cmp = match (
-                    network_utils::parse_ip_for_sorting(&a.address),
-                    network_utils::parse_ip_for_sorting(&b.address),
-                ) {
-                    (Some(ip_a), Some(ip_b)) => ip_a.cmp(&ip_b),
-                    _ => a.address.cmp(&b.address),
+                let cmp = match column {
+                    SortColumn::IpAddress => match (
+                        network_utils::parse_ip_for_sorting(&a.address),
+                        network_utils::parse_ip_for_sorting(&b.address),
+                    ) {
+                        (Some(ip_a), Some(ip_b)) => ip_a.cmp(&ip_b),
+                        _ => a.address.cmp(&b.address),
+                    },
+                    SortColumn::Ping => match (
+                        a.ping_stats.as_ref().and_then(|s| metric.value(s)),
+                        b.ping_stats.as_ref().and_then(|s| metric.value(s)),
+                    ) {
+                        (Some(va), Some(vb)) => va.total_cmp(&vb),
+                        (Some(_), None) => CmpOrdering::Less,
+                        (None, Some(_)) => CmpOrdering::Greater,
+                        (None, None) => CmpOrdering::Equal,
+                    },
                 };
 
                 match self.sort_order {
@@ -363,6 +664,14 @@ impl IpScanApp {
             }
         };
 
+        let dns_servers = match resolver::parse_servers(&self.settings_temp.dns_servers) {
+            Ok(servers) => servers,
+            Err(e) => {
+                *self.status_message.lock().unwrap() = e;
+                return;
+            }
+        };
+
         if let Some(cancel) = &self.scan_cancellation {
             cancel.store(true, Ordering::Relaxed);
         }
@@ -393,18 +702,64 @@ impl IpScanApp {
             port_timeout_ms: self.settings_temp.port_timeout,
             min_port_timeout_ms: self.settings_temp.min_port_timeout,
             adapt_port_timeout: self.settings_temp.adapt_port_timeout,
+            max_port_concurrency: self.settings_temp.port_concurrency,
+            max_sockets: self.settings_temp.max_sockets,
+            port_scan_mode: if self.settings_temp.syn_scan {
+                PortScanMode::Syn
+            } else {
+                PortScanMode::Connect
+            },
+            banner_timeout_ms: self.settings_temp.banner_timeout,
+            banner_max_bytes: self.settings_temp.banner_max_bytes,
+            banner_print_ports: self.settings_temp.banner_print_ports,
+            http_timeout_ms: self.settings_temp.http_timeout,
+            netbios_timeout_ms: self.settings_temp.netbios_timeout,
+            service_timeout_ms: self.settings_temp.service_timeout,
+            dns_servers,
+            dns_timeout_ms: self.settings_temp.dns_timeout,
+            dns_retries: self.settings_temp.dns_retries,
+            dns_confirm_forward: self.settings_temp.dns_confirm,
+            snmp_communities: self
+                .settings_temp
+                .snmp_communities
+                .split(',')
+                .map(|community| community.trim().to_string())
+                .filter(|community| !community.is_empty())
+                .collect(),
+            smb1_probe: self.settings_temp.smb1_probe,
+            ftp_anonymous_check: self.settings_temp.ftp_anonymous,
+            industrial_passive_only: self.settings_temp.industrial_passive_only,
+            dns_axfr_zone: Some(self.settings_temp.axfr_zone.trim().to_string())
+                .filter(|zone| !zone.is_empty()),
+            service_probes_file: Some(self.settings_temp.service_probes_file.trim().to_string())
+                .filter(|path| !path.is_empty()),
+            web_tech_rules_file: Some(self.settings_temp.web_tech_rules_file.trim().to_string())
+                .filter(|path| !path.is_empty()),
         });
 
+        let selected_fetchers: Vec<String> = CORE_FETCHERS
+            .iter()
+            .map(|id| id.to_string())
+            .chain(
+                self.settings_temp
+                    .fetchers
+                    .iter()
+                    .filter(|f| f.enabled)
+                    .map(|f| f.id.clone()),
+            )
+            .collect();
+
         let results = self.results.clone();
         let scan_state = self.scan_state.clone();
         let status_message = self.status_message.clone();
 
         self.runtime.spawn(async move {
             let fetcher_registry = Arc::new(RwLock::new(FetcherRegistry::new()));
-            fetcher_registry
-                .write()
-                .await
-                .register_default_fetchers(config.clone());
+            {
+                let mut registry = fetcher_registry.write().await;
+                registry.register_default_fetchers(config.clone());
+                registry.select(&selected_fetchers);
+            }
 
             match RangeFeeder::new(start_ip, end_ip) {
                 Ok(mut feeder) => {
@@ -437,6 +792,7 @@ impl IpScanApp {
                         let scan_state = scan_state.clone();
                         let status_message = status_message.clone();
                         let scanned = scanned.clone();
+                        let selected_fetchers = selected_fetchers.clone();
 
                         tasks.spawn(async move {
                             let _permit = permit; // Hold permit until task completes
@@ -456,6 +812,7 @@ impl IpScanApp {
                             }
 
                             result.set_type(subject.result_type());
+                            result.set_parameters(subject.into_parameters());
 
                             let current_scanned = {
                                 let mut sc = scanned.lock().unwrap();
@@ -490,14 +847,88 @@ impl IpScanApp {
                                         .get_value("ping")
                                         .unwrap_or(&"[n/a]".to_string())
                                         .clone(),
+                                    ping_stats: result
+                                        .get_parameter::<PingStats>(PING_STATS)
+                                        .copied(),
                                     ports: result
                                         .get_value("ports")
                                         .unwrap_or(&"[n/a]".to_string())
                                         .clone(),
+                                    port_states: result
+                                        .get_parameter::<PortStates>(PORT_STATES)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    udp_states: result
+                                        .get_parameter::<PortStates>(UDP_PORT_STATES)
+                                        .cloned()
+                                        .unwrap_or_default(),
                                     mac: result
                                         .get_value("mac")
                                         .unwrap_or(&"[n/a]".to_string())
                                         .clone(),
+                                    banners: result
+                                        .get_parameter::<Banners>(BANNERS)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    http: result
+                                        .get_parameter::<HttpResponses>(HTTP_RESPONSES)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    certificates: result
+                                        .get_parameter::<TlsCertificates>(TLS_CERTIFICATES)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    ssh: result
+                                        .get_parameter::<SshServers>(SSH_SERVERS)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    netbios: result.get_parameter::<NetBiosInfo>(NETBIOS).cloned(),
+                                    mdns: result.get_parameter::<MdnsInfo>(MDNS).cloned(),
+                                    llmnr: result.get_parameter::<String>(LLMNR_NAME).cloned(),
+                                    snmp: result.get_parameter::<SnmpInfo>(SNMP).cloned(),
+                                    ssdp: result.get_parameter::<SsdpInfo>(SSDP).cloned(),
+                                    smb: result.get_parameter::<SmbInfo>(SMB).cloned(),
+                                    databases: result
+                                        .get_parameter::<DatabaseServers>(DATABASE_SERVERS)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    capabilities: result
+                                        .get_parameter::<Capabilities>(SERVICE_CAPABILITIES)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    remote_desktops: result
+                                        .get_parameter::<RemoteDesktops>(REMOTE_DESKTOPS)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    printers: result
+                                        .get_parameter::<Printers>(PRINTERS)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    industrial: result
+                                        .get_parameter::<IndustrialInfo>(INDUSTRIAL)
+                                        .cloned(),
+                                    ldap: result
+                                        .get_parameter::<LdapServers>(LDAP_SERVERS)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    dns_server: result
+                                        .get_parameter::<DnsServerInfo>(DNS_SERVER)
+                                        .cloned(),
+                                    services: result
+                                        .get_parameter::<ServiceFingerprints>(SERVICE_FINGERPRINTS)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    web_technologies: result
+                                        .get_parameter::<WebTechnologies>(WEB_TECHNOLOGIES)
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    extra: selected_fetchers
+                                        .iter()
+                                        .filter(|id| !CORE_FETCHERS.contains(&id.as_str()))
+                                        .filter_map(|id| {
+                                            result.get_value(id).map(|v| (id.clone(), v.clone()))
+                                        })
+                                        .collect(),
                                     status: result.result_type(),
                                 };
 
@@ -551,16 +982,85 @@ impl IpScanApp {
 
     fn export_results(&self, format: &str) {
         let results = self.results.lock().unwrap();
+        let ping_columns: Vec<&str> = PingMetric::ALL.iter().map(|m| m.label()).collect();
+        let ping_values = |r: &ScanResult| -> Vec<String> {
+            PingMetric::ALL
+                .iter()
+                .map(|m| m.format(r.ping_stats.as_ref()))
+                .collect()
+        };
+
+        let extra_fetchers: Vec<&FetcherChoice> = self
+            .settings_temp
+            .fetchers
+            .iter()
+            .filter(|f| f.enabled)
+            .collect();
+        let extra_values = |r: &ScanResult| -> Vec<String> {
+            extra_fetchers
+                .iter()
+                .map(|f| r.extra.get(&f.id).cloned().unwrap_or("[n/a]".to_string()))
+                .collect()
+        };
+
+        let visible_states = |states: &PortStates| -> serde_json::Map<String, serde_json::Value> {
+            states
+                .iter()
+                .filter(|(_, state)| match state {
+                    PortState::Closed => self.show_closed,
+                    PortState::Filtered | PortState::OpenFiltered => self.show_filtered,
+                    _ => true,
+                })
+                .map(|(port, state)| (port.to_string(), serde_json::json!(state)))
+                .collect()
+        };
 
         let content = match format {
             "csv" => {
-                let mut csv =
-                    String::from("IP Address,Hostname,Ping,MAC Address,Open Ports,Status\n");
+                let mut header = vec!["IP Address", "Hostname", "Ping"];
+                header.extend(ping_columns.iter());
+                header.extend(["MAC Address", "Open Ports"]);
+                if self.show_closed {
+                    header.push("Closed Ports");
+                }
+                if self.show_filtered {
+                    header.push("Filtered Ports");
+                }
+                header.push("UDP Ports");
+                if self.show_closed {
+                    header.push("Closed UDP Ports");
+                }
+                if self.show_filtered {
+                    header.push("Open|Filtered UDP Ports");
+                }
+                header.extend(extra_fetchers.iter().map(|f| f.name.as_str()));
+                header.push("Status");
+
+                let mut csv = header.join(",");
+                csv.push('\n');
                 for r in results.iter() {
-                    csv.push_str(&format!(
-                        "{},{},{},{},{},{:?}\n",
-                        r.address, r.hostname, r.ping, r.mac, r.ports, r.status
-                    ));
+                    let mut fields = vec![r.address.clone(), r.hostname.clone(), r.ping.clone()];
+                    fields.extend(ping_values(r));
+                    fields.extend([r.mac.clone(), r.ports.clone()]);
+                    if self.show_closed {
+                        fields.push(r.ports_in(PortState::Closed));
+                    }
+                    if self.show_filtered {
+                        fields.push(r.ports_in(PortState::Filtered));
+                    }
+                    fields.push(r.udp_ports_in(PortState::Open));
+                    if self.show_closed {
+                        fields.push(r.udp_ports_in(PortState::Closed));
+                    }
+                    if self.show_filtered {
+                        fields.push(r.udp_ports_in(PortState::OpenFiltered));
+                    }
+                    fields.extend(extra_values(r));
+                    fields.push(format!("{:?}", r.status));
+
+                    let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                    csv.push_str(&fields.join(","));
+                    csv.push('\n');
                 }
                 csv
             }
@@ -568,28 +1068,110 @@ impl IpScanApp {
                 &results
                     .iter()
                     .map(|r| {
-                        serde_json::json!({
+                        let mut entry = serde_json::json!({
                             "address": r.address,
                             "hostname": r.hostname,
                             "ping": r.ping,
+                            "ping_stats": r.ping_stats,
                             "mac": r.mac,
                             "ports": r.ports,
+                            "port_states": visible_states(&r.port_states),
+                            "udp_port_states": visible_states(&r.udp_states),
                             "status": format!("{:?}", r.status)
-                        })
+                        });
+                        for (fetcher, value) in extra_fetchers.iter().zip(extra_values(r)) {
+                            entry[fetcher.id.as_str()] = serde_json::Value::String(value);
+                        }
+                        if !r.banners.is_empty() {
+                            entry["banners"] = serde_json::json!(r.banners);
+                        }
+                        if !r.http.is_empty() {
+                            entry["http_responses"] = serde_json::json!(r.http);
+                        }
+                        if !r.certificates.is_empty() {
+                            entry["tls_certificates"] = serde_json::json!(r.certificates);
+                        }
+                        if !r.ssh.is_empty() {
+                            entry["ssh_servers"] = serde_json::json!(r.ssh);
+                        }
+                        if let Some(netbios) = &r.netbios {
+                            entry["netbios"] = serde_json::json!(netbios);
+                        }
+                        if let Some(mdns) = &r.mdns {
+                            entry["mdns"] = serde_json::json!(mdns);
+                        }
+                        if let Some(llmnr) = &r.llmnr {
+                            entry["llmnr_name"] = serde_json::json!(llmnr);
+                        }
+                        if let Some(snmp) = &r.snmp {
+                            entry["snmp"] = serde_json::json!(snmp);
+                        }
+                        if let Some(ssdp) = &r.ssdp {
+                            entry["upnp"] = serde_json::json!(ssdp);
+                        }
+                        if let Some(smb) = &r.smb {
+                            entry["smb"] = serde_json::json!(smb);
+                        }
+                        if !r.databases.is_empty() {
+                            entry["databases"] = serde_json::json!(r.databases);
+                        }
+                        if !r.capabilities.is_empty() {
+                            entry["capabilities"] = serde_json::json!(r.capabilities);
+                        }
+                        if !r.remote_desktops.is_empty() {
+                            entry["remote_desktops"] = serde_json::json!(r.remote_desktops);
+                        }
+                        if !r.printers.is_empty() {
+                            entry["printers"] = serde_json::json!(r.printers);
+                        }
+                        if let Some(industrial) = &r.industrial {
+                            entry["industrial"] = serde_json::json!(industrial);
+                        }
+                        if !r.ldap.is_empty() {
+                            entry["ldap"] = serde_json::json!(r.ldap);
+                        }
+                        if let Some(dns_server) = &r.dns_server {
+                            entry["dns_server"] = serde_json::json!(dns_server);
+                        }
+                        if !r.services.is_empty() {
+                            entry["services"] = serde_json::json!(r.services);
+                        }
+                        if !r.web_technologies.is_empty() {
+                            entry["web_technologies"] = serde_json::json!(r.web_technologies);
+                        }
+                        entry
                     })
                     .collect::<Vec<_>>(),
             )
             .unwrap(),
             _ => {
-                let mut text = String::new();
-                text.push_str("IP Address\tHostname\tPing\tMAC Address\tOpen Ports\tStatus\n");
+                let extra_columns: Vec<&str> =
+                    extra_fetchers.iter().map(|f| f.name.as_str()).collect();
+                let mut text = format!(
+                    "IP Address\tHostname\tPing\t{}\tMAC Address\tOpen Ports\t{}Status\n",
+                    ping_columns.join("\t"),
+                    extra_columns
+                        .iter()
+                        .map(|c| format!("{}\t", c))
+                        .collect::<String>()
+                );
                 text.push_str(
                     "------------------------------------------------------------------------\n",
                 );
                 for r in results.iter() {
                     text.push_str(&format!(
-                        "{}\t{}\t{}\t{}\t{}\t{:?}\n",
-                        r.address, r.hostname, r.ping, r.mac, r.ports, r.status
+                        "{}\t{}\t{}\t{}\t{}\t{}\t{}{:?}\n",
+                        r.address,
+                        r.hostname,
+                        r.ping,
+                        ping_values(r).join("\t"),
+                        r.mac,
+                        r.ports_text(self.show_closed, self.show_filtered),
+                        extra_values(r)
+                            .iter()
+                            .map(|v| format!("{}\t", v))
+                            .collect::<String>(),
+                        r.status
                     ));
                 }
                 text
@@ -673,6 +1255,80 @@ impl eframe::App for IpScanApp {
                             ui.add(egui::Slider::new(&mut self.settings_temp.min_port_timeout, 10..=1000)
                                 .text("ms"));
                             ui.end_row();
+
+                            ui.label("Ports probed in parallel per host:");
+                            ui.add(egui::Slider::new(&mut self.settings_temp.port_concurrency, 1..=500)
+                                .text("ports"));
+                            ui.end_row();
+
+                            ui.label("Max open sockets:");
+                            ui.add(egui::Slider::new(&mut self.settings_temp.max_sockets, 10..=5000)
+                                .text("sockets"));
+                            ui.end_row();
+
+                            ui.label("Banner timeout (ms):");
+                            ui.add(egui::Slider::new(&mut self.settings_temp.banner_timeout, 100..=10000)
+                                .text("ms"));
+                            ui.end_row();
+
+                            ui.label("Max banner size:");
+                            ui.add(egui::Slider::new(&mut self.settings_temp.banner_max_bytes, 16..=4096)
+                                .text("bytes"));
+                            ui.end_row();
+
+                            ui.label("HTTP timeout (ms):");
+                            ui.add(egui::Slider::new(&mut self.settings_temp.http_timeout, 100..=10000)
+                                .text("ms"));
+                            ui.end_row();
+
+                            ui.label("NetBIOS timeout (ms):");
+                            ui.add(egui::Slider::new(&mut self.settings_temp.netbios_timeout, 100..=5000)
+                                .text("ms"));
+                            ui.end_row();
+
+                            ui.label("Service probe timeout (ms):");
+                            ui.add(egui::Slider::new(&mut self.settings_temp.service_timeout, 100..=10000)
+                                .text("ms"));
+                            ui.end_row();
+
+                            ui.label("Highlight certificates expiring within:");
+                            ui.add(egui::Slider::new(&mut self.settings_temp.cert_warning_days, 1..=365)
+                                .text("days"));
+                            ui.end_row();
+
+                            ui.label("DNS servers:");
+                            ui.add(egui::TextEdit::singleline(&mut self.settings_temp.dns_servers)
+                                .hint_text("system default"));
+                            ui.end_row();
+
+                            ui.label("DNS timeout (ms):");
+                            ui.add(egui::Slider::new(&mut self.settings_temp.dns_timeout, 100..=10000)
+                                .text("ms"));
+                            ui.end_row();
+
+                            ui.label("DNS retries:");
+                            ui.add(egui::Slider::new(&mut self.settings_temp.dns_retries, 0..=5));
+                            ui.end_row();
+
+                            ui.label("SNMP communities:");
+                            ui.add(egui::TextEdit::singleline(&mut self.settings_temp.snmp_communities)
+                                .hint_text("public,private"));
+                            ui.end_row();
+
+                            ui.label("AXFR zone:");
+                            ui.add(egui::TextEdit::singleline(&mut self.settings_temp.axfr_zone)
+                                .hint_text("example.com"));
+                            ui.end_row();
+
+                            ui.label("Service probes file:");
+                            ui.add(egui::TextEdit::singleline(&mut self.settings_temp.service_probes_file)
+                                .hint_text("nmap-service-probes"));
+                            ui.end_row();
+
+                            ui.label("Web tech rules file:");
+                            ui.add(egui::TextEdit::singleline(&mut self.settings_temp.web_tech_rules_file)
+                                .hint_text("web-tech.json"));
+                            ui.end_row();
                         });
 
                     ui.separator();
@@ -680,11 +1336,32 @@ impl eframe::App for IpScanApp {
 
                     ui.checkbox(&mut self.settings_temp.adapt_port_timeout,
                         "Adapt port timeout to ping RTT");
+                    ui.checkbox(&mut self.settings_temp.syn_scan,
+                        "SYN scan (needs root/CAP_NET_RAW, else connect scan)");
+                    ui.checkbox(&mut self.settings_temp.dns_confirm,
+                        "Only keep hostnames that resolve back to the address");
+                    ui.checkbox(&mut self.settings_temp.banner_print_ports,
+                        "Grab banners from raw printing ports 9100-9107 (may print pages)");
+                    ui.checkbox(&mut self.settings_temp.smb1_probe,
+                        "Check whether SMB servers still accept SMBv1");
+                    ui.checkbox(&mut self.settings_temp.ftp_anonymous,
+                        "Try anonymous FTP logins");
+                    ui.checkbox(&mut self.settings_temp.industrial_passive_only,
+                        "Only send identification requests to industrial devices");
                     ui.checkbox(&mut self.settings_temp.scan_dead,
                         "Continue scanning dead hosts");
                     ui.checkbox(&mut self.settings_temp.auto_save_results,
                         "Auto-save results after scan");
 
+                    ui.separator();
+                    ui.heading("Fetchers");
+
+                    ui.horizontal_wrapped(|ui| {
+                        for fetcher in self.settings_temp.fetchers.iter_mut() {
+                            ui.checkbox(&mut fetcher.enabled, &fetcher.name);
+                        }
+                    });
+
                     ui.separator();
                     ui.heading("Appearance");
 
@@ -977,6 +1654,8 @@ impl eframe::App for IpScanApp {
 
                 ui.checkbox(&mut self.scan_dead, "Scan dead hosts");
                 ui.checkbox(&mut self.show_dead, "Show dead hosts");
+                ui.checkbox(&mut self.show_closed, "Show closed ports");
+                ui.checkbox(&mut self.show_filtered, "Show filtered ports");
             });
 
             ui.add_space(5.0);
@@ -1032,6 +1711,22 @@ impl eframe::App for IpScanApp {
                 if ui.button("Clear").clicked() {
                     self.filter_text.clear();
                 }
+
+                ui.separator();
+
+                ui.label("Ping column:");
+                let previous_metric = self.ping_metric;
+                egui::ComboBox::from_id_salt("ping_metric")
+                    .selected_text(self.ping_metric.label())
+                    .show_ui(ui, |ui| {
+                        for metric in PingMetric::ALL {
+                            ui.selectable_value(&mut self.ping_metric, metric, metric.label());
+                        }
+                    });
+                if self.ping_metric != previous_metric && self.sort_column == Some(SortColumn::Ping)
+                {
+                    self.sort_results();
+                }
             });
 
             ui.separator();
@@ -1045,12 +1740,21 @@ impl eframe::App for IpScanApp {
                             && (self.filter_text.is_empty()
                                 || r.address.contains(&self.filter_text)
                                 || r.hostname.contains(&self.filter_text)
-                                || r.ports.contains(&self.filter_text))
+                                || r.ports.contains(&self.filter_text)
+                                || r.extra.values().any(|v| v.contains(&self.filter_text)))
                     })
                     .cloned()
                     .collect()
             };
 
+            let extra_fetchers: Vec<FetcherChoice> = self
+                .settings_temp
+                .fetchers
+                .iter()
+                .filter(|f| f.enabled)
+                .cloned()
+                .collect();
+
             let table = TableBuilder::new(ui)
                 .striped(true)
                 .resizable(true)
@@ -1059,6 +1763,7 @@ impl eframe::App for IpScanApp {
                 .column(Column::initial(80.0).at_least(60.0)) // Ping
                 .column(Column::initial(200.0).at_least(150.0)) // Hostname
                 .column(Column::initial(150.0).at_least(120.0)) // MAC
+                .columns(Column::initial(160.0).at_least(80.0), extra_fetchers.len())
                 .column(Column::remainder()) // Ports
                 .header(25.0, |mut header| {
                     header.col(|ui| {
@@ -1072,20 +1777,24 @@ impl eframe::App for IpScanApp {
                         }
 
                         if response.clicked() {
-                            if self.sort_column == Some(SortColumn::IpAddress) {
-                                self.sort_order = match self.sort_order {
-                                    SortOrder::Ascending => SortOrder::Descending,
-                                    SortOrder::Descending => SortOrder::Ascending,
-                                };
-                            } else {
-                                self.sort_column = Some(SortColumn::IpAddress);
-                                self.sort_order = SortOrder::Ascending;
-                            }
-                            self.sort_results();
+                            self.toggle_sort(SortColumn::IpAddress);
                         }
                     });
                     header.col(|ui| {
-                        ui.strong("Ping");
+                        let response = ui.add(
+                            egui::Label::new(
+                                egui::RichText::new(self.ping_metric.label()).strong(),
+                            )
+                            .sense(egui::Sense::click()),
+                        );
+
+                        if response.hovered() {
+                            ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+                        }
+
+                        if response.clicked() {
+                            self.toggle_sort(SortColumn::Ping);
+                        }
                     });
                     header.col(|ui| {
                         ui.strong("Hostname");
@@ -1093,6 +1802,11 @@ impl eframe::App for IpScanApp {
                     header.col(|ui| {
                         ui.strong("MAC Address");
                     });
+                    for fetcher in &extra_fetchers {
+                        header.col(|ui| {
+                            ui.strong(&fetcher.name);
+                        });
+                    }
                     header.col(|ui| {
                         ui.strong("Ports [3+]");
                     });
@@ -1128,22 +1842,22 @@ impl eframe::App for IpScanApp {
                                 ui.separator();
 
                                 if result.ports != "[n/a]" && result.ports != "[n/s]" {
-                                    let has_http = result.ports.contains("80")
-                                        || result.ports.contains("8080");
-                                    let has_https = result.ports.contains("443")
-                                        || result.ports.contains("8443");
+                                    let has_http = result.has_open_tcp_port(80)
+                                        || result.has_open_tcp_port(8080);
+                                    let has_https = result.has_open_tcp_port(443)
+                                        || result.has_open_tcp_port(8443);
 
                                     if (has_http || has_https)
                                         && ui.button("🌐 Open in Browser").clicked()
                                     {
                                         let protocol = if has_https { "https" } else { "http" };
                                         let port = if has_https {
-                                            if result.ports.contains("443") {
+                                            if result.has_open_tcp_port(443) {
                                                 ""
                                             } else {
                                                 ":8443"
                                             }
-                                        } else if result.ports.contains("80") {
+                                        } else if result.has_open_tcp_port(80) {
                                             ""
                                         } else {
                                             ":8080"
@@ -1154,7 +1868,7 @@ impl eframe::App for IpScanApp {
                                         ui.close();
                                     }
 
-                                    if result.ports.contains("22")
+                                    if result.has_open_tcp_port(22)
                                         && ui.button("🔐 SSH Connect").clicked()
                                     {
                                         self.execute_terminal_command(&format!(
@@ -1164,14 +1878,19 @@ impl eframe::App for IpScanApp {
                                         ui.close();
                                     }
 
-                                    if result.ports.contains("3389")
+                                    if let Some(rdp_port) = result.rdp_port()
                                         && ui.button("🖥 RDP Connect").clicked()
                                     {
+                                        let target = if rdp_port == 3389 {
+                                            result.address.clone()
+                                        } else {
+                                            format!("{}:{}", result.address, rdp_port)
+                                        };
                                         #[cfg(target_os = "windows")]
                                         {
                                             let _ = Command::new("mstsc")
                                                 .arg("/v")
-                                                .arg(&result.address)
+                                                .arg(&target)
                                                 .spawn();
                                         }
                                         #[cfg(target_os = "linux")]
@@ -1184,7 +1903,7 @@ impl eframe::App for IpScanApp {
                                             {
                                                 let _ = Command::new("remmina")
                                                     .arg("-c")
-                                                    .arg(format!("rdp://{}", result.address))
+                                                    .arg(format!("rdp://{}", target))
                                                     .spawn();
                                             } else if Command::new("which")
                                                 .arg("xfreerdp")
@@ -1194,24 +1913,21 @@ impl eframe::App for IpScanApp {
                                             {
                                                 self.execute_terminal_command(&format!(
                                                     "xfreerdp /v:{}",
-                                                    result.address
+                                                    target
                                                 ));
                                             }
                                         }
                                         #[cfg(target_os = "macos")]
                                         {
                                             let _ = Command::new("open")
-                                                .arg(format!(
-                                                    "rdp://full%20address=s:{}",
-                                                    result.address
-                                                ))
+                                                .arg(format!("rdp://full%20address=s:{}", target))
                                                 .spawn();
                                         }
                                         ui.close();
                                     }
 
-                                    if (result.ports.contains("445")
-                                        || result.ports.contains("139"))
+                                    if (result.has_open_tcp_port(445)
+                                        || result.has_open_tcp_port(139))
                                         && ui.button("📁 SMB Browse").clicked()
                                     {
                                         #[cfg(target_os = "windows")]
@@ -1372,20 +2088,33 @@ impl eframe::App for IpScanApp {
 
                         // Ping column with color coding
                         row.col(|ui| {
-                            let ping_text = &result.ping;
-                            let color = if ping_text == "[n/a]" {
-                                egui::Color32::from_gray(128)
-                            } else if let Ok(ms) = ping_text.trim_end_matches(" ms").parse::<u32>()
-                            {
-                                if ms < 50 {
-                                    egui::Color32::from_rgb(0, 200, 0) // Green for fast
-                                } else if ms < 200 {
-                                    egui::Color32::from_rgb(200, 200, 0) // Yellow for medium
-                                } else {
-                                    egui::Color32::from_rgb(200, 0, 0) // Red for slow
+                            let metric = self.ping_metric;
+                            let ping_text = match result.ping_stats.as_ref() {
+                                Some(stats) => metric.format(Some(stats)),
+                                None => result.ping.clone(),
+                            };
+                            let value = result.ping_stats.as_ref().and_then(|s| metric.value(s));
+                            let color = match (metric, value) {
+                                (_, None) => egui::Color32::from_gray(128),
+                                (PingMetric::Ttl, Some(_)) => egui::Color32::from_gray(180),
+                                (PingMetric::Loss, Some(loss)) => {
+                                    if loss == 0.0 {
+                                        egui::Color32::from_rgb(0, 200, 0)
+                                    } else if loss < 100.0 {
+                                        egui::Color32::from_rgb(200, 200, 0)
+                                    } else {
+                                        egui::Color32::from_rgb(200, 0, 0)
+                                    }
+                                }
+                                (_, Some(ms)) => {
+                                    if ms < 50.0 {
+                                        egui::Color32::from_rgb(0, 200, 0) // Green for fast
+                                    } else if ms < 200.0 {
+                                        egui::Color32::from_rgb(200, 200, 0) // Yellow for medium
+                                    } else {
+                                        egui::Color32::from_rgb(200, 0, 0) // Red for slow
+                                    }
                                 }
-                            } else {
-                                egui::Color32::from_gray(180)
                             };
 
                             ui.label(egui::RichText::new(ping_text).color(color));
@@ -1413,12 +2142,44 @@ impl eframe::App for IpScanApp {
                             ));
                         });
 
+                        for fetcher in &extra_fetchers {
+                            row.col(|ui| {
+                                let value = result
+                                    .extra
+                                    .get(&fetcher.id)
+                                    .map(String::as_str)
+                                    .unwrap_or("[n/a]");
+                                let expiring = fetcher.id == "tls_cert"
+                                    && result.has_expiring_certificate(
+                                        self.settings_temp.cert_warning_days,
+                                    );
+                                let unprotected = (fetcher.id == "database"
+                                    && result.has_unauthenticated_database())
+                                    || (fetcher.id == "remote_desktop"
+                                        && result.has_weak_remote_desktop())
+                                    || (fetcher.id == "industrial" && result.has_anonymous_mqtt())
+                                    || (fetcher.id == "dns_server"
+                                        && result.has_exposed_dns_server());
+                                ui.label(egui::RichText::new(value).color(if value == "[n/a]" {
+                                    egui::Color32::from_gray(128)
+                                } else if expiring {
+                                    egui::Color32::from_rgb(255, 165, 0) // Orange for expiring certs
+                                } else if unprotected {
+                                    egui::Color32::from_rgb(220, 50, 50) // Red for services open without auth
+                                } else {
+                                    egui::Color32::from_gray(200)
+                                }))
+                                .on_hover_text(value);
+                            });
+                        }
+
                         row.col(|ui| {
-                            let ports_text = &result.ports;
-                            let color = if ports_text == "[n/a]" || ports_text == "[n/s]" {
+                            let ports_text =
+                                result.ports_text(self.show_closed, self.show_filtered);
+                            let color = if !result.has_open_ports() {
                                 egui::Color32::from_gray(128)
                             } else {
-                                let port_count = ports_text.split(',').count();
+                                let port_count = result.ports.split(',').count();
                                 if port_count >= 3 {
                                     egui::Color32::from_rgb(255, 100, 0) // Orange for many ports
                                 } else {
@@ -1,6 +1,8 @@
 pub mod config;
+pub mod dns;
 pub mod errors;
 pub mod network_utils;
+pub mod resolver;
 
 pub mod core {
     pub mod port_iterator;
@@ -10,12 +12,35 @@ pub mod core {
 }
 
 pub mod fetchers {
+    pub mod banner;
+    pub mod capabilities;
+    pub mod database;
+    pub mod dns_server;
     pub mod hostname;
+    pub mod http;
+    pub mod industrial;
+    pub mod ldap;
+    pub mod llmnr;
     pub mod mac;
+    pub mod mdns;
+    pub mod netbios;
+    pub mod os_guess;
     pub mod ping;
     pub mod ports;
+    pub mod printer;
     pub mod registry;
+    pub mod remote_desktop;
+    pub mod service_fingerprint;
+    pub mod smb;
+    pub mod snmp;
+    pub mod ssdp;
+    pub mod ssh;
+    pub mod syn_scan;
+    pub mod tls;
+    pub mod tls_cert;
     pub mod traits;
+    pub mod udp_payloads;
+    pub mod web_tech;
 }
 
 pub mod feeders {
@@ -23,14 +48,16 @@ pub mod feeders {
     pub mod traits;
 }
 
-pub use config::ScannerConfig;
+pub use config::{PortScanMode, ScannerConfig};
 pub use core::result::{ResultType, ScanningResult};
 pub use core::scanner::Scanner;
 pub use core::subject::ScanningSubject;
 pub use errors::ScanError;
 pub use feeders::range::RangeFeeder;
 pub use feeders::traits::Feeder;
+pub use fetchers::ping::PingStats;
 pub use fetchers::registry::FetcherRegistry;
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
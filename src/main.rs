@@ -1,5 +1,5 @@
 use clap::Parser;
-use ipscan_rs::{FetcherRegistry, RangeFeeder, Scanner, ScannerConfig};
+use ipscan_rs::{FetcherRegistry, PortScanMode, RangeFeeder, Scanner, ScannerConfig, resolver};
 use std::net::IpAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -24,6 +24,69 @@ struct Args {
     #[arg(long, default_value = "2000")]
     timeout: u64,
 
+    #[arg(long, default_value = "50")]
+    port_concurrency: usize,
+
+    #[arg(long, default_value = "500")]
+    max_sockets: usize,
+
+    #[arg(long)]
+    syn: bool,
+
+    #[arg(long)]
+    fetchers: Option<String>,
+
+    #[arg(long, default_value = "2000")]
+    banner_timeout: u64,
+
+    #[arg(long, default_value = "256")]
+    banner_max_bytes: usize,
+
+    #[arg(long)]
+    banner_print_ports: bool,
+
+    #[arg(long, default_value = "3000")]
+    http_timeout: u64,
+
+    #[arg(long, default_value = "500")]
+    netbios_timeout: u64,
+
+    #[arg(long, default_value = "3000")]
+    service_timeout: u64,
+
+    #[arg(long)]
+    dns_servers: Option<String>,
+
+    #[arg(long, default_value = "1000")]
+    dns_timeout: u64,
+
+    #[arg(long, default_value = "1")]
+    dns_retries: u32,
+
+    #[arg(long)]
+    dns_confirm: bool,
+
+    #[arg(long, default_value = "public")]
+    snmp_communities: String,
+
+    #[arg(long)]
+    smb1: bool,
+
+    #[arg(long)]
+    ftp_anonymous: bool,
+
+    #[arg(long)]
+    industrial_active: bool,
+
+    #[arg(long)]
+    axfr_zone: Option<String>,
+
+    #[arg(long)]
+    service_probes: Option<String>,
+
+    #[arg(long)]
+    web_tech_rules: Option<String>,
+
     #[arg(long)]
     scan_dead: bool,
 
@@ -53,13 +116,60 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         port_timeout_ms: 500,
         min_port_timeout_ms: 100,
         adapt_port_timeout: true,
+        max_port_concurrency: args.port_concurrency,
+        max_sockets: args.max_sockets,
+        port_scan_mode: if args.syn {
+            PortScanMode::Syn
+        } else {
+            PortScanMode::Connect
+        },
+        banner_timeout_ms: args.banner_timeout,
+        banner_max_bytes: args.banner_max_bytes,
+        banner_print_ports: args.banner_print_ports,
+        http_timeout_ms: args.http_timeout,
+        netbios_timeout_ms: args.netbios_timeout,
+        service_timeout_ms: args.service_timeout,
+        dns_servers: match &args.dns_servers {
+            Some(list) => resolver::parse_servers(list)?,
+            None => Vec::new(),
+        },
+        dns_timeout_ms: args.dns_timeout,
+        dns_retries: args.dns_retries,
+        dns_confirm_forward: args.dns_confirm,
+        snmp_communities: args
+            .snmp_communities
+            .split(',')
+            .map(|community| community.trim().to_string())
+            .filter(|community| !community.is_empty())
+            .collect(),
+        smb1_probe: args.smb1,
+        ftp_anonymous_check: args.ftp_anonymous,
+        industrial_passive_only: !args.industrial_active,
+        dns_axfr_zone: args.axfr_zone,
+        service_probes_file: args.service_probes,
+        web_tech_rules_file: args.web_tech_rules,
     });
 
-    let fetcher_registry = Arc::new(RwLock::new(FetcherRegistry::new()));
-    fetcher_registry
-        .write()
-        .await
-        .register_default_fetchers(config.clone());
+    let mut registry = FetcherRegistry::new();
+    registry.register_default_fetchers(config.clone());
+    if let Some(list) = &args.fetchers {
+        let ids: Vec<String> = list
+            .split(',')
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .collect();
+        for id in &ids {
+            if !registry
+                .fetchers()
+                .iter()
+                .any(|fetcher| fetcher.id() == *id)
+            {
+                return Err(format!("Unknown fetcher: {}", id).into());
+            }
+        }
+        registry.select(&ids);
+    }
+    let fetcher_registry = Arc::new(RwLock::new(registry));
 
     let scanner = Scanner::new(fetcher_registry.clone(), config.clone());
 
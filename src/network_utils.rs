@@ -1,4 +1,10 @@
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::time::{Instant, timeout_at};
+
+// Once some data has arrived, a pause this long means the peer is done.
+const READ_IDLE_GAP: Duration = Duration::from_millis(250);
 
 pub fn get_local_network() -> Option<(String, String, String)> {
     use pnet::datalink;
@@ -35,3 +41,39 @@ pub fn get_local_network() -> Option<(String, String, String)> {
 pub fn parse_ip_for_sorting(ip_str: &str) -> Option<IpAddr> {
     ip_str.parse().ok()
 }
+
+/// Local address the OS would use to reach `target`. Nothing is sent.
+pub fn local_address_for(target: IpAddr) -> Option<IpAddr> {
+    let bind: IpAddr = match target {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((bind, 0)).ok()?;
+    socket.connect((target, 9)).ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+/// Reads into `buffer` until it is full, the peer closes or goes quiet, or
+/// `wait` runs out. Returns the number of bytes read.
+pub async fn read_available<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut [u8],
+    wait: Duration,
+) -> usize {
+    let deadline = Instant::now() + wait;
+    let mut len = 0;
+
+    while len < buffer.len() {
+        let until = if len == 0 {
+            deadline
+        } else {
+            deadline.min(Instant::now() + READ_IDLE_GAP)
+        };
+        match timeout_at(until, reader.read(&mut buffer[len..])).await {
+            Ok(Ok(0)) | Ok(Err(_)) | Err(_) => break,
+            Ok(Ok(n)) => len += n,
+        }
+    }
+
+    len
+}
@@ -0,0 +1,260 @@
+use dns_lookup::{lookup_addr, lookup_host};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use crate::config::ScannerConfig;
+use crate::dns;
+
+const DNS_PORT: u16 = 53;
+// Long enough to carry over to a rescan, short enough to see DNS changes.
+const CACHE_TTL: Duration = Duration::from_secs(300);
+// A /16 worth of addresses; past that, expired answers are dropped first.
+const MAX_CACHE_ENTRIES: usize = 65536;
+// Threads a hung system resolver can hold at once.
+const MAX_SYSTEM_LOOKUPS: usize = 16;
+
+type Cache = Mutex<HashMap<(Arc<[SocketAddr]>, bool, IpAddr), (Instant, Option<String>)>>;
+
+/// Answers shared by every resolver of the process, so that each scan does
+/// not start over. Negative answers are cached as well, timeouts are not.
+fn cache() -> &'static Cache {
+    static CACHE: OnceLock<Cache> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn system_lookups() -> Arc<Semaphore> {
+    static LOOKUPS: OnceLock<Arc<Semaphore>> = OnceLock::new();
+    LOOKUPS
+        .get_or_init(|| Arc::new(Semaphore::new(MAX_SYSTEM_LOOKUPS)))
+        .clone()
+}
+
+/// Asynchronous reverse resolver with its own server list, so one slow
+/// server costs a timeout instead of a blocked thread. Without configured
+/// servers it asks those of `/etc/resolv.conf`, after `/etc/hosts`.
+pub struct Resolver {
+    servers: Arc<[SocketAddr]>,
+    hosts: HashMap<IpAddr, String>,
+    timeout: Duration,
+    retries: u32,
+    confirm_forward: bool,
+}
+
+impl Resolver {
+    pub fn new(
+        servers: Vec<SocketAddr>,
+        timeout: Duration,
+        retries: u32,
+        confirm_forward: bool,
+    ) -> Self {
+        Self {
+            servers: servers.into(),
+            hosts: HashMap::new(),
+            timeout,
+            retries,
+            confirm_forward,
+        }
+    }
+
+    /// Uses the configured servers, or the system ones when none are set.
+    pub fn from_config(config: &ScannerConfig) -> Self {
+        let configured = !config.dns_servers.is_empty();
+        let servers = if configured {
+            config.dns_servers.clone()
+        } else {
+            system_servers()
+        };
+        Self {
+            hosts: if configured {
+                HashMap::new()
+            } else {
+                hosts_file()
+            },
+            ..Self::new(
+                servers,
+                Duration::from_millis(config.dns_timeout_ms),
+                config.dns_retries,
+                config.dns_confirm_forward,
+            )
+        }
+    }
+
+    pub fn servers(&self) -> &[SocketAddr] {
+        &self.servers
+    }
+
+    /// PTR name of `address`, if it has one (and it resolves back to the
+    /// address when forward confirmation is on).
+    pub async fn reverse(&self, address: IpAddr) -> Option<String> {
+        if let Some(name) = self.hosts.get(&address) {
+            return Some(name.clone());
+        }
+        let key = (self.servers.clone(), self.confirm_forward, address);
+        if let Some((at, name)) = cache().lock().unwrap().get(&key)
+            && at.elapsed() < CACHE_TTL
+        {
+            return name.clone();
+        }
+
+        let name = self.lookup(address).await?;
+        let mut cache = cache().lock().unwrap();
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                cache.clear();
+            }
+        }
+        cache.insert(key, (Instant::now(), name.clone()));
+        name
+    }
+
+    /// The answer, or `None` when no server gave one in time.
+    async fn lookup(&self, address: IpAddr) -> Option<Option<String>> {
+        if self.servers.is_empty() {
+            return self.system_lookup(address).await;
+        }
+
+        let message = self
+            .query(&dns::reverse_name(address), dns::TYPE_PTR)
+            .await?;
+        let name = message.answer_names(dns::TYPE_PTR).into_iter().next();
+        match name {
+            Some(ptr) if self.confirm_forward => {
+                let confirmed = self.resolves_to(&ptr, address).await?;
+                Some(confirmed.then_some(ptr))
+            }
+            name => Some(name),
+        }
+    }
+
+    /// Where there is no `/etc/resolv.conf` to read servers from. The
+    /// blocking call keeps its thread until the system gives up, so only a
+    /// few may be stuck at a time; the scan does not wait for them.
+    async fn system_lookup(&self, address: IpAddr) -> Option<Option<String>> {
+        let confirm_forward = self.confirm_forward;
+        for _ in 0..=self.retries {
+            let attempt = async {
+                let permit = system_lookups().acquire_owned().await.ok()?;
+                tokio::task::spawn_blocking(move || {
+                    let _permit = permit;
+                    let name = lookup_addr(&address).ok()?;
+                    if confirm_forward {
+                        let confirmed = lookup_host(&name)
+                            .map(|mut addresses| addresses.any(|ip| ip == address))
+                            .unwrap_or(false);
+                        return Some(confirmed.then_some(name));
+                    }
+                    Some(Some(name))
+                })
+                .await
+                .ok()
+            };
+            match tokio::time::timeout(self.timeout, attempt).await {
+                Ok(Some(answer)) => return Some(answer.unwrap_or_default()),
+                _ => continue,
+            }
+        }
+        None
+    }
+
+    async fn resolves_to(&self, name: &str, address: IpAddr) -> Option<bool> {
+        let record_type = match address {
+            IpAddr::V4(_) => dns::TYPE_A,
+            IpAddr::V6(_) => dns::TYPE_AAAA,
+        };
+        let message = self.query(name, record_type).await?;
+        Some(message.answers.iter().any(|record| match record.data {
+            dns::RecordData::A(v4) => IpAddr::V4(v4) == address,
+            dns::RecordData::Aaaa(v6) => IpAddr::V6(v6) == address,
+            _ => false,
+        }))
+    }
+
+    /// Tries every server in turn, `retries` more times after the first
+    /// round. Only a definite answer stops early: an NXDOMAIN is not retried.
+    async fn query(&self, name: &str, record_type: u16) -> Option<dns::Message> {
+        for _ in 0..=self.retries {
+            for server in self.servers.iter() {
+                let query = dns::query(dns::next_id(), name, record_type, dns::CLASS_IN, true);
+                let Ok(response) = dns::exchange(*server, &query, self.timeout).await else {
+                    continue;
+                };
+                match dns::Message::parse(&response) {
+                    Some(message)
+                        if matches!(message.rcode(), dns::RCODE_NOERROR | dns::RCODE_NXDOMAIN) =>
+                    {
+                        return Some(message);
+                    }
+                    _ => continue,
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Parses a comma separated list of `ip` or `ip:port` entries.
+pub fn parse_servers(list: &str) -> Result<Vec<SocketAddr>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .parse::<SocketAddr>()
+                .or_else(|_| {
+                    entry
+                        .parse::<IpAddr>()
+                        .map(|ip| SocketAddr::new(ip, DNS_PORT))
+                })
+                .map_err(|_| format!("Invalid DNS server: {}", entry))
+        })
+        .collect()
+}
+
+/// Name servers from `/etc/resolv.conf`. Empty where there is none, in
+/// which case lookups go through the system resolver instead.
+pub fn system_servers() -> Vec<SocketAddr> {
+    std::fs::read_to_string("/etc/resolv.conf")
+        .map(|conf| parse_resolv_conf(&conf))
+        .unwrap_or_default()
+}
+
+pub fn parse_resolv_conf(conf: &str) -> Vec<SocketAddr> {
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .filter_map(|server| {
+            // Drop an IPv6 zone, which `IpAddr` cannot parse.
+            let server = server.trim().split('%').next()?;
+            server.parse::<IpAddr>().ok()
+        })
+        .map(|ip| SocketAddr::new(ip, DNS_PORT))
+        .collect()
+}
+
+/// Names of `/etc/hosts`, which the system resolver would find before
+/// asking any server.
+fn hosts_file() -> HashMap<IpAddr, String> {
+    std::fs::read_to_string("/etc/hosts")
+        .map(|hosts| parse_hosts(&hosts))
+        .unwrap_or_default()
+}
+
+/// Address to first name of each line of a hosts file. The first line of
+/// an address wins, as with the system resolver.
+pub fn parse_hosts(hosts: &str) -> HashMap<IpAddr, String> {
+    let mut names = HashMap::new();
+    for line in hosts.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let (Some(address), Some(name)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if let Ok(address) = address.split('%').next().unwrap_or(address).parse() {
+            names.entry(address).or_insert_with(|| name.to_string());
+        }
+    }
+    names
+}
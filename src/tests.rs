@@ -1,20 +1,50 @@
 #[cfg(test)]
 mod tests {
-    use crate::config::ScannerConfig;
-    use crate::core::port_iterator::PortIterator;
+    use crate::config::{PortScanMode, ScannerConfig};
+    use crate::core::port_iterator::{PortIterator, Protocol};
     use crate::core::result::{ResultType, ScanningResult};
     use crate::core::subject::ScanningSubject;
+    use crate::dns;
     use crate::errors::ScanError;
     use crate::feeders::range::RangeFeeder;
     use crate::feeders::traits::Feeder;
+    use crate::fetchers::banner::{self, BANNERS, BannerFetcher, Banners, sanitize_banner};
+    use crate::fetchers::capabilities::{self, TextProtocol};
+    use crate::fetchers::database::{self, DatabaseKind};
+    use crate::fetchers::dns_server::{self, DnsServerInfo, ZoneTransfer};
     use crate::fetchers::hostname::HostnameFetcher;
+    use crate::fetchers::http::{self, HttpFetcher, HttpResponse};
+    use crate::fetchers::industrial::{self, BacnetInfo, IndustrialInfo};
+    use crate::fetchers::ldap::{self, LdapInfo};
+    use crate::fetchers::llmnr;
     use crate::fetchers::mac::MacFetcher;
-    use crate::fetchers::ping::PingFetcher;
-    use crate::fetchers::ports::PortsFetcher;
+    use crate::fetchers::mdns::{self, MDNS, MdnsInfo};
+    use crate::fetchers::netbios::{self, NETBIOS, NetBiosFetcher, NetBiosInfo};
+    use crate::fetchers::os_guess::{
+        Confidence, OS_GUESS, OsFamily, OsGuess, OsGuessFetcher, TCP_TTL,
+    };
+    use crate::fetchers::ping::{PING_STATS, PingFetcher, PingStats};
+    use crate::fetchers::ports::{
+        PORT_STATES, PortState, PortStates, PortsFetcher, UDP_PORT_STATES,
+    };
+    use crate::fetchers::printer::{self, IppValue, PrinterProtocol};
     use crate::fetchers::registry::FetcherRegistry;
+    use crate::fetchers::remote_desktop::{self, RdpSecurity, RemoteDesktop};
+    use crate::fetchers::service_fingerprint::{
+        self, ProbeDatabase, ProbeProtocol, ServiceFingerprint, ServiceFingerprintFetcher,
+    };
+    use crate::fetchers::smb::{self, SmbInfo};
+    use crate::fetchers::snmp::{self, SnmpResponse, SnmpValue, SnmpVersion};
+    use crate::fetchers::ssdp::{self, SsdpInfo};
+    use crate::fetchers::ssh::{self, SSH_SERVERS, SshFetcher, SshServers};
+    use crate::fetchers::tls_cert::{self, TlsCertFetcher};
     use crate::fetchers::traits::Fetcher;
+    use crate::fetchers::udp_payloads;
+    use crate::fetchers::web_tech::{self, WebPage, WebTechRules, WebTechnology};
+    use crate::resolver::{self, Resolver};
     use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
     use std::sync::Arc;
+    use std::time::Duration;
 
     #[test]
     fn test_port_iterator_single_port() {
@@ -79,6 +109,28 @@ mod tests {
         assert!(PortIterator::new("65536").is_err());
     }
 
+    #[test]
+    fn test_port_iterator_protocol_prefixes() {
+        let spec = "T:22,80,U:53,161,t:443,u:1000-1002";
+        let tcp: Vec<u16> = PortIterator::for_protocol(spec, Protocol::Tcp)
+            .unwrap()
+            .collect();
+        let udp: Vec<u16> = PortIterator::for_protocol(spec, Protocol::Udp)
+            .unwrap()
+            .collect();
+        assert_eq!(tcp, vec![22, 80, 443]);
+        assert_eq!(udp, vec![53, 161, 1000, 1001, 1002]);
+
+        let tcp_only: Vec<u16> = PortIterator::new("22,U:53").unwrap().collect();
+        assert_eq!(tcp_only, vec![22]);
+        assert!(PortIterator::new("U:abc").is_err());
+        assert!(
+            PortIterator::for_protocol("80", Protocol::Udp)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
     #[tokio::test]
     async fn test_range_feeder_ipv4() {
         let start = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1));
@@ -251,6 +303,53 @@ mod tests {
         assert_eq!(subject.adapted_port_timeout(), 200);
     }
 
+    #[test]
+    fn test_scanning_result_parameters_from_subject() {
+        let config = Arc::new(ScannerConfig::default());
+        let addr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config);
+        subject.set_parameter("test_key".to_string(), 7u16);
+
+        let mut result = ScanningResult::new(addr);
+        assert_eq!(result.get_parameter::<u16>("test_key"), None);
+
+        result.set_parameters(subject.into_parameters());
+        assert_eq!(result.get_parameter::<u16>("test_key"), Some(&7u16));
+        assert_eq!(result.get_parameter::<u32>("test_key"), None);
+    }
+
+    #[test]
+    fn test_ping_stats_from_samples() {
+        let rtts = [
+            Duration::from_millis(10),
+            Duration::from_millis(20),
+            Duration::from_millis(30),
+        ];
+        let stats = PingStats::from_samples(4, &rtts, Some(64));
+
+        assert!(stats.is_alive());
+        assert_eq!(stats.sent, 4);
+        assert_eq!(stats.received, 3);
+        assert_eq!(stats.min_ms, 10.0);
+        assert_eq!(stats.max_ms, 30.0);
+        assert_eq!(stats.avg_ms, 20.0);
+        assert!((stats.stddev_ms - 8.165).abs() < 0.001);
+        assert_eq!(stats.jitter_ms, 10.0);
+        assert_eq!(stats.loss_percent, 25.0);
+        assert_eq!(stats.ttl, Some(64));
+    }
+
+    #[test]
+    fn test_ping_stats_no_replies() {
+        let stats = PingStats::from_samples(3, &[], None);
+
+        assert!(!stats.is_alive());
+        assert_eq!(stats.received, 0);
+        assert_eq!(stats.loss_percent, 100.0);
+        assert_eq!(stats.avg_ms, 0.0);
+        assert_eq!(stats.ttl, None);
+    }
+
     #[test]
     fn test_scanner_config_default() {
         let config = ScannerConfig::default();
@@ -262,6 +361,26 @@ mod tests {
         assert_eq!(config.port_timeout_ms, 500);
         assert_eq!(config.min_port_timeout_ms, 100);
         assert!(config.adapt_port_timeout);
+        assert_eq!(config.max_port_concurrency, 50);
+        assert_eq!(config.max_sockets, 500);
+        assert_eq!(config.port_scan_mode, PortScanMode::Connect);
+        assert_eq!(config.banner_timeout_ms, 2000);
+        assert_eq!(config.banner_max_bytes, 256);
+        assert!(!config.banner_print_ports);
+        assert_eq!(config.http_timeout_ms, 3000);
+        assert_eq!(config.netbios_timeout_ms, 500);
+        assert_eq!(config.service_timeout_ms, 3000);
+        assert!(config.dns_servers.is_empty());
+        assert_eq!(config.dns_timeout_ms, 1000);
+        assert_eq!(config.dns_retries, 1);
+        assert!(!config.dns_confirm_forward);
+        assert_eq!(config.snmp_communities, vec!["public".to_string()]);
+        assert!(!config.smb1_probe);
+        assert!(!config.ftp_anonymous_check);
+        assert!(config.industrial_passive_only);
+        assert_eq!(config.dns_axfr_zone, None);
+        assert_eq!(config.service_probes_file, None);
+        assert_eq!(config.web_tech_rules_file, None);
     }
 
     #[test]
@@ -276,6 +395,26 @@ mod tests {
             port_timeout_ms: 1000,
             min_port_timeout_ms: 50,
             adapt_port_timeout: false,
+            max_port_concurrency: 10,
+            max_sockets: 100,
+            port_scan_mode: PortScanMode::Syn,
+            banner_timeout_ms: 500,
+            banner_max_bytes: 64,
+            banner_print_ports: true,
+            http_timeout_ms: 1500,
+            netbios_timeout_ms: 250,
+            service_timeout_ms: 1000,
+            dns_servers: vec!["192.0.2.53:53".parse().unwrap()],
+            dns_timeout_ms: 500,
+            dns_retries: 3,
+            dns_confirm_forward: true,
+            snmp_communities: vec!["private".to_string()],
+            smb1_probe: true,
+            ftp_anonymous_check: true,
+            industrial_passive_only: false,
+            dns_axfr_zone: Some("corp.example".to_string()),
+            service_probes_file: Some("/etc/ipscan/probes".to_string()),
+            web_tech_rules_file: Some("/etc/ipscan/web-tech.json".to_string()),
         };
 
         assert_eq!(config.max_threads, 50);
@@ -287,6 +426,32 @@ mod tests {
         assert_eq!(config.port_timeout_ms, 1000);
         assert_eq!(config.min_port_timeout_ms, 50);
         assert!(!config.adapt_port_timeout);
+        assert_eq!(config.max_port_concurrency, 10);
+        assert_eq!(config.max_sockets, 100);
+        assert_eq!(config.port_scan_mode, PortScanMode::Syn);
+        assert_eq!(config.banner_timeout_ms, 500);
+        assert_eq!(config.banner_max_bytes, 64);
+        assert!(config.banner_print_ports);
+        assert_eq!(config.http_timeout_ms, 1500);
+        assert_eq!(config.netbios_timeout_ms, 250);
+        assert_eq!(config.service_timeout_ms, 1000);
+        assert_eq!(config.dns_servers.len(), 1);
+        assert_eq!(config.dns_timeout_ms, 500);
+        assert_eq!(config.dns_retries, 3);
+        assert!(config.dns_confirm_forward);
+        assert_eq!(config.snmp_communities, vec!["private".to_string()]);
+        assert!(config.smb1_probe);
+        assert!(config.ftp_anonymous_check);
+        assert!(!config.industrial_passive_only);
+        assert_eq!(config.dns_axfr_zone.as_deref(), Some("corp.example"));
+        assert_eq!(
+            config.service_probes_file.as_deref(),
+            Some("/etc/ipscan/probes")
+        );
+        assert_eq!(
+            config.web_tech_rules_file.as_deref(),
+            Some("/etc/ipscan/web-tech.json")
+        );
     }
 
     #[test]
@@ -343,7 +508,118 @@ mod tests {
         assert_eq!(registry.get_selected_fetchers().len(), 0);
 
         registry.register_default_fetchers(config);
-        assert!(registry.get_selected_fetchers().len() > 0);
+        assert!(!registry.get_selected_fetchers().is_empty());
+    }
+
+    #[test]
+    fn test_fetcher_registry_select() {
+        let config = Arc::new(ScannerConfig::default());
+        let mut registry = FetcherRegistry::new();
+        registry.register_default_fetchers(config);
+        assert!(registry.is_selected("os_guess"));
+        assert!(registry.is_selected("netbios"));
+
+        registry.select(&["ports".to_string(), "ping".to_string()]);
+        let ids: Vec<String> = registry
+            .get_selected_fetchers()
+            .iter()
+            .map(|f| f.id())
+            .collect();
+        assert_eq!(ids, vec!["ping", "ports"]);
+        assert!(!registry.is_selected("os_guess"));
+        assert!(registry.fetchers().len() > ids.len());
+    }
+
+    #[test]
+    fn test_fetcher_registry_service_probes_opt_in() {
+        let mut registry = FetcherRegistry::new();
+        registry.register_default_fetchers(Arc::new(ScannerConfig::default()));
+        assert!(registry.fetchers().iter().any(|f| f.id() == "banner"));
+        assert!(!registry.is_selected("banner"));
+        assert!(!registry.is_selected("http"));
+        assert!(!registry.is_selected("tls_cert"));
+        assert!(!registry.is_selected("ssh"));
+        assert!(!registry.is_selected("mdns"));
+        assert!(!registry.is_selected("llmnr"));
+        assert!(!registry.is_selected("snmp"));
+        assert!(!registry.is_selected("ssdp"));
+        assert!(!registry.is_selected("smb"));
+        assert!(!registry.is_selected("database"));
+        assert!(!registry.is_selected("capabilities"));
+        assert!(!registry.is_selected("remote_desktop"));
+        assert!(!registry.is_selected("printer"));
+        assert!(!registry.is_selected("industrial"));
+        assert!(!registry.is_selected("ldap"));
+        assert!(!registry.is_selected("dns_server"));
+        assert!(!registry.is_selected("service_fingerprint"));
+        assert!(!registry.is_selected("web_tech"));
+    }
+
+    #[test]
+    fn test_os_guess_from_ttl() {
+        let linux = OsGuess::from_ttl(61).unwrap();
+        assert_eq!(linux.family, OsFamily::UnixLike);
+        assert_eq!(linux.initial_ttl, 64);
+        assert_eq!(linux.hops, 3);
+        assert_eq!(linux.confidence, Confidence::Medium);
+
+        let windows = OsGuess::from_ttl(128).unwrap();
+        assert_eq!(windows.family, OsFamily::Windows);
+        assert_eq!(windows.hops, 0);
+
+        let router = OsGuess::from_ttl(250).unwrap();
+        assert_eq!(router.family, OsFamily::NetworkDevice);
+        assert_eq!(router.initial_ttl, 255);
+
+        let far = OsGuess::from_ttl(100).unwrap();
+        assert_eq!(far.family, OsFamily::Windows);
+        assert_eq!(far.hops, 28);
+        assert_eq!(far.confidence, Confidence::Low);
+
+        assert_eq!(OsGuess::from_ttl(0), None);
+        assert_eq!(OsGuess::from_ttls(Some(0), None), None);
+        assert_eq!(OsGuess::from_ttls(Some(0), Some(61)), OsGuess::from_ttl(61));
+    }
+
+    #[test]
+    fn test_os_guess_from_ttls() {
+        assert_eq!(OsGuess::from_ttls(None, None), None);
+
+        let agree = OsGuess::from_ttls(Some(126), Some(126)).unwrap();
+        assert_eq!(agree.family, OsFamily::Windows);
+        assert_eq!(agree.confidence, Confidence::High);
+
+        let disagree = OsGuess::from_ttls(Some(255), Some(63)).unwrap();
+        assert_eq!(disagree.family, OsFamily::UnixLike);
+        assert_eq!(disagree.confidence, Confidence::Low);
+
+        let icmp_only = OsGuess::from_ttls(Some(64), None).unwrap();
+        assert_eq!(icmp_only.family, OsFamily::UnixLike);
+        assert_eq!(icmp_only.confidence, Confidence::Medium);
+    }
+
+    #[tokio::test]
+    async fn test_os_guess_fetcher() {
+        let fetcher = OsGuessFetcher::new();
+        assert_eq!(fetcher.id(), "os_guess");
+        assert_eq!(fetcher.name(), "OS Guess");
+
+        let config = Arc::new(ScannerConfig::default());
+        let addr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config);
+        assert_eq!(fetcher.scan(&mut subject).await.unwrap(), "[n/a]");
+
+        subject.set_parameter(
+            PING_STATS.to_string(),
+            PingStats::from_samples(1, &[Duration::from_millis(1)], Some(118)),
+        );
+        subject.set_parameter(TCP_TTL.to_string(), 118u8);
+        let value = fetcher.scan(&mut subject).await.unwrap();
+        assert_eq!(value, "Windows (TTL 128, 10 hops, high)");
+        assert_eq!(
+            subject.get_parameter::<OsGuess>(OS_GUESS).map(|g| g.family),
+            Some(OsFamily::Windows)
+        );
     }
 
     #[tokio::test]
@@ -391,22 +667,2849 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_ping_fetcher() {
+    async fn test_ports_fetcher_concurrent_probes() {
+        let listeners = [
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap(),
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap(),
+        ];
+        let mut open: Vec<u16> = listeners
+            .iter()
+            .map(|l| l.local_addr().unwrap().port())
+            .collect();
+        open.sort_unstable();
+
         let config = Arc::new(ScannerConfig {
-            ping_count: 1,
-            ping_timeout_ms: 100,
-            adapt_port_timeout: false,
+            port_string: format!("{},{}", open[1], open[0]),
+            port_timeout_ms: 500,
+            max_port_concurrency: 2,
+            max_sockets: 1,
             ..Default::default()
         });
-        let fetcher = PingFetcher::new(config.clone());
+        let fetcher = PortsFetcher::new(config.clone());
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config);
 
-        assert_eq!(fetcher.id(), "ping");
-        assert_eq!(fetcher.name(), "Ping");
+        let result = fetcher.scan(&mut subject).await.unwrap();
+        let reported: Vec<u16> = result
+            .split(',')
+            .flat_map(|part| match part.split_once('-') {
+                Some((a, b)) => (a.parse().unwrap()..=b.parse().unwrap()).collect::<Vec<u16>>(),
+                None => vec![part.parse().unwrap()],
+            })
+            .collect();
+        assert_eq!(reported, open);
+        assert_eq!(subject.result_type(), ResultType::WithPorts);
+    }
+
+    #[test]
+    fn test_port_state_from_connect_error() {
+        use std::io::{Error, ErrorKind};
+
+        let state = |kind| PortState::from_connect_error(&Error::from(kind));
+        assert_eq!(state(ErrorKind::ConnectionRefused), PortState::Closed);
+        assert_eq!(state(ErrorKind::TimedOut), PortState::Filtered);
+        assert_eq!(state(ErrorKind::HostUnreachable), PortState::Filtered);
+        assert_eq!(state(ErrorKind::NetworkUnreachable), PortState::Filtered);
+        assert_eq!(state(ErrorKind::PermissionDenied), PortState::Error);
+    }
+
+    #[tokio::test]
+    async fn test_ports_fetcher_closed_port_marks_host_alive() {
+        let closed_port = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().port()
+        };
 
+        let config = Arc::new(ScannerConfig {
+            port_string: closed_port.to_string(),
+            port_timeout_ms: 500,
+            ..Default::default()
+        });
+        let fetcher = PortsFetcher::new(config.clone());
         let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
         let mut subject = ScanningSubject::new(addr, config);
+        subject.set_result_type(ResultType::Dead);
 
-        let _ = fetcher.scan(&mut subject).await;
+        assert_eq!(fetcher.scan(&mut subject).await.unwrap(), "[n/a]");
+        assert_eq!(subject.result_type(), ResultType::Alive);
+
+        let states = subject.get_parameter::<PortStates>(PORT_STATES).unwrap();
+        assert_eq!(states.get(&closed_port), Some(&PortState::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_ports_fetcher_syn_mode() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open_port = listener.local_addr().unwrap().port();
+        let closed_port = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let config = Arc::new(ScannerConfig {
+            port_string: format!("{},{}", open_port, closed_port),
+            port_timeout_ms: 1000,
+            port_scan_mode: PortScanMode::Syn,
+            ..Default::default()
+        });
+        // Runs as a SYN scan when privileged and as a connect scan otherwise;
+        // both must agree on the port states.
+        let fetcher = PortsFetcher::new(config.clone());
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config);
+
+        assert_eq!(
+            fetcher.scan(&mut subject).await.unwrap(),
+            open_port.to_string()
+        );
+        let states = subject.get_parameter::<PortStates>(PORT_STATES).unwrap();
+        assert_eq!(states.get(&open_port), Some(&PortState::Open));
+        assert_eq!(states.get(&closed_port), Some(&PortState::Closed));
+        assert_eq!(
+            subject.get_parameter::<u8>(TCP_TTL).is_some(),
+            fetcher.is_syn_scan()
+        );
+    }
+
+    #[test]
+    fn test_udp_payloads() {
+        assert_eq!(udp_payloads::payload_for(53), udp_payloads::DNS_QUERY);
+        assert_eq!(udp_payloads::payload_for(123).len(), 48);
+        assert_eq!(udp_payloads::payload_for(137).len(), 50);
+        assert_eq!(udp_payloads::payload_for(161)[1] as usize + 2, 41);
+        assert!(udp_payloads::payload_for(1900).starts_with(b"M-SEARCH"));
+        assert!(udp_payloads::payload_for(9999).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ports_fetcher_udp() {
+        let responder = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let open_port = responder.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 512];
+            if let Ok((len, peer)) = responder.recv_from(&mut buffer).await {
+                let _ = responder.send_to(&buffer[..len], peer).await;
+            }
+        });
+        let closed_port = {
+            let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+            socket.local_addr().unwrap().port()
+        };
+
+        let config = Arc::new(ScannerConfig {
+            port_string: format!("U:{},{}", open_port, closed_port),
+            port_timeout_ms: 500,
+            ..Default::default()
+        });
+        let fetcher = PortsFetcher::new(config.clone());
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config);
+
+        let result = fetcher.scan(&mut subject).await.unwrap();
+        assert_eq!(result, format!("U:{}", open_port));
+        assert_eq!(subject.result_type(), ResultType::WithPorts);
+
+        let states = subject
+            .get_parameter::<PortStates>(UDP_PORT_STATES)
+            .unwrap();
+        assert_eq!(states.get(&open_port), Some(&PortState::Open));
+        assert_eq!(states.get(&closed_port), Some(&PortState::Closed));
+        assert!(
+            subject
+                .get_parameter::<PortStates>(PORT_STATES)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_sanitize_banner() {
+        assert_eq!(
+            sanitize_banner(b"SSH-2.0-OpenSSH_9.6\r\n"),
+            "SSH-2.0-OpenSSH_9.6"
+        );
+        assert_eq!(
+            sanitize_banner(b"220 ready\r\n\x00\xff"),
+            "220 ready \\x00\\xff"
+        );
+        assert_eq!(sanitize_banner(b"\r\n\r\n"), "");
+    }
+
+    #[tokio::test]
+    async fn test_banner_fetcher() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let talker = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let talker_port = talker.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = talker.accept().await {
+                let _ = stream.write_all(b"220 test FTP server\r\n").await;
+            }
+        });
+
+        // Only answers once the client has said something.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listener_port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buffer = [0u8; 16];
+                if stream.read(&mut buffer).await.unwrap_or(0) > 0 {
+                    let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+                }
+            }
+        });
+
+        let config = Arc::new(ScannerConfig {
+            port_string: format!("{},{}", talker_port, listener_port),
+            banner_timeout_ms: 300,
+            banner_max_bytes: 12,
+            ..Default::default()
+        });
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config.clone());
+        PortsFetcher::new(config.clone())
+            .scan(&mut subject)
+            .await
+            .unwrap();
+
+        let summary = BannerFetcher::new(config).scan(&mut subject).await.unwrap();
+        let banners = subject.get_parameter::<Banners>(BANNERS).unwrap();
+        assert_eq!(banners.get(&talker_port).unwrap(), "220 test FTP");
+        assert_eq!(banners.get(&listener_port).unwrap(), "HTTP/1.1 400");
+        assert!(summary.contains(&format!("{}: 220 test FTP", talker_port)));
+    }
+
+    #[tokio::test]
+    async fn test_banner_fetcher_waits_for_a_socket() {
+        let port = spawn_scripted_server(b"220 test FTP\r\n".to_vec(), |_| Vec::new()).await;
+        let config = Arc::new(ScannerConfig {
+            port_string: port.to_string(),
+            banner_timeout_ms: 300,
+            ..Default::default()
+        });
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config.clone());
+        PortsFetcher::new(config.clone())
+            .scan(&mut subject)
+            .await
+            .unwrap();
+
+        // The port scan of another host holds the only socket.
+        let sockets = Arc::new(tokio::sync::Semaphore::new(1));
+        let held = sockets.clone().acquire_owned().await.unwrap();
+        let fetcher = BannerFetcher::with_sockets(config, sockets);
+        let waiting = tokio::time::timeout(Duration::from_millis(200), fetcher.scan(&mut subject));
+        assert!(waiting.await.is_err());
+
+        drop(held);
+        let summary = fetcher.scan(&mut subject).await.unwrap();
+        assert!(summary.contains("220 test FTP"));
+    }
+
+    /// Listens on a free raw printing port and counts the bytes sent to it,
+    /// as a printer would print them.
+    async fn spawn_print_port() -> (u16, Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::AsyncReadExt;
+
+        let mut listener = None;
+        for port in banner::RAW_PRINT_PORTS {
+            if let Ok(bound) = tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+                listener = Some(bound);
+                break;
+            }
+        }
+        let listener = listener.expect("a free port in 9100-9107");
+        let port = listener.local_addr().unwrap().port();
+        let printed = Arc::new(AtomicUsize::new(0));
+        let counter = printed.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let counter = counter.clone();
+                tokio::spawn(async move {
+                    let mut buffer = [0u8; 1024];
+                    while let Ok(len) = stream.read(&mut buffer).await {
+                        if len == 0 {
+                            break;
+                        }
+                        counter.fetch_add(len, Ordering::SeqCst);
+                    }
+                });
+            }
+        });
+        (port, printed)
+    }
+
+    #[tokio::test]
+    async fn test_banner_fetcher_skips_print_ports() {
+        use std::sync::atomic::Ordering;
+
+        let (port, printed) = spawn_print_port().await;
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        for opt_in in [false, true] {
+            let config = Arc::new(ScannerConfig {
+                port_string: port.to_string(),
+                banner_timeout_ms: 200,
+                banner_print_ports: opt_in,
+                ..Default::default()
+            });
+            let mut subject = ScanningSubject::new(addr, config.clone());
+            PortsFetcher::new(config.clone())
+                .scan(&mut subject)
+                .await
+                .unwrap();
+
+            let summary = BannerFetcher::new(config).scan(&mut subject).await.unwrap();
+            assert_eq!(summary, "[n/a]");
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert_eq!(printed.load(Ordering::SeqCst) > 0, opt_in);
+        }
+    }
+
+    #[test]
+    fn test_http_response_parse() {
+        let response = HttpResponse::parse(
+            b"HTTP/1.1 302 Found\r\nServer: lighttpd/1.4\r\nLocation: /login\r\n\
+Content-Length: 52\r\n\r\n<html><head><TITLE>\n  Router   Admin\n</TITLE></head>",
+        )
+        .unwrap();
+        assert_eq!(response.status, 302);
+        assert_eq!(response.server.as_deref(), Some("lighttpd/1.4"));
+        assert_eq!(response.location.as_deref(), Some("/login"));
+        assert_eq!(response.title.as_deref(), Some("Router Admin"));
+        assert_eq!(response.content_length, Some(52));
+        assert_eq!(
+            response.to_string(),
+            "302 \"Router Admin\" -> /login (lighttpd/1.4)"
+        );
+
+        let bare = HttpResponse::parse(b"HTTP/1.0 200 OK\r\n\r\nhello").unwrap();
+        assert_eq!(bare.content_length, Some(5));
+        assert_eq!(bare.title, None);
+        assert!(HttpResponse::parse(b"SSH-2.0-OpenSSH_9.6\r\n").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_fetch_local_server() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0u8; 1024];
+                let len = stream.read(&mut request).await.unwrap_or(0);
+                if !request[..len].starts_with(b"GET / HTTP/1.1\r\n") {
+                    continue;
+                }
+                let body = "<html><title>NAS</title></html>";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nServer: test\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_millis(1000);
+        let response = http::fetch(addr, port, false, wait).await.unwrap();
+        assert!(!response.tls);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.title.as_deref(), Some("NAS"));
+        assert_eq!(response.server.as_deref(), Some("test"));
+        assert_eq!(response.content_length, Some(31));
+
+        // A plain HTTP server cannot complete a TLS handshake.
+        assert!(http::fetch(addr, port, true, wait).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_skips_non_web_ports() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let config = Arc::new(ScannerConfig {
+            port_string: port.to_string(),
+            ..Default::default()
+        });
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config.clone());
+        let fetcher = HttpFetcher::new(config.clone());
+
+        // Without a port scan nothing is known to be open.
+        assert_eq!(fetcher.scan(&mut subject).await.unwrap(), "[n/a]");
+
+        PortsFetcher::new(config).scan(&mut subject).await.unwrap();
+        assert_eq!(fetcher.scan(&mut subject).await.unwrap(), "[n/a]");
+        assert!(
+            subject
+                .get_parameter::<http::HttpResponses>(http::HTTP_RESPONSES)
+                .is_none()
+        );
+    }
+
+    /// Serves `GET /` over TLS with a self-signed certificate for localhost
+    /// that expires in `days` days.
+    async fn spawn_tls_server(days: i64) -> u16 {
+        use chrono::Datelike;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio_rustls::TlsAcceptor;
+        use tokio_rustls::rustls::ServerConfig;
+        use tokio_rustls::rustls::crypto::ring;
+        use tokio_rustls::rustls::pki_types::PrivateKeyDer;
+
+        let expiry = chrono::Utc::now() + chrono::Duration::days(days);
+        let mut params =
+            rcgen::CertificateParams::new(vec!["localhost".to_string(), "127.0.0.1".to_string()])
+                .unwrap();
+        params.not_after =
+            rcgen::date_time_ymd(expiry.year(), expiry.month() as u8, expiry.day() as u8);
+        let key = rcgen::KeyPair::generate().unwrap();
+        let cert = params.self_signed(&key).unwrap();
+
+        let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![cert.der().clone()],
+                PrivateKeyDer::Pkcs8(key.serialize_der().into()),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let Ok(mut stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let mut request = [0u8; 1024];
+                    if stream.read(&mut request).await.unwrap_or(0) > 0 {
+                        let _ = stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                            .await;
+                        let _ = stream.shutdown().await;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_tls_cert_inspect() {
+        let port = spawn_tls_server(10).await;
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+
+        let cert = tls_cert::inspect(addr, port, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(cert.protocol, "TLSv1.3");
+        assert!(cert.cipher.starts_with("TLS13_"));
+        assert!(cert.self_signed);
+        assert_eq!(cert.subject, cert.issuer);
+        assert!(!cert.serial.is_empty());
+        assert_eq!(cert.sans, vec!["localhost", "127.0.0.1"]);
+        assert!(cert.not_before < cert.not_after);
+
+        let now = chrono::Utc::now();
+        assert!((9..=10).contains(&cert.days_until_expiry(now)));
+        assert!(cert.expires_within(30, now));
+        assert!(!cert.expires_within(5, now));
+        assert!(cert.to_string().ends_with(", self-signed)"));
+    }
+
+    fn tls10_handshake(request: &[u8]) -> Vec<u8> {
+        // Only the TLS 1.1 ClientHello of the fallback is answered.
+        if request.get(9..11) != Some(&[3, 2][..]) {
+            return vec![21, 3, 1, 0, 2, 2, 70];
+        }
+        let params = rcgen::CertificateParams::new(vec!["legacy.local".to_string()]).unwrap();
+        let key = rcgen::KeyPair::generate().unwrap();
+        let der = params.self_signed(&key).unwrap().der().to_vec();
+
+        let mut hello = vec![3, 1];
+        hello.extend_from_slice(&[0u8; 32]);
+        hello.extend_from_slice(&[0, 0x00, 0x2f, 0]);
+        let mut certificates = (der.len() as u32).to_be_bytes()[1..].to_vec();
+        certificates.extend_from_slice(&der);
+        let mut chain = (certificates.len() as u32).to_be_bytes()[1..].to_vec();
+        chain.extend_from_slice(&certificates);
+
+        let mut handshake = Vec::new();
+        for (kind, body) in [(2u8, hello), (11, chain), (14, Vec::new())] {
+            handshake.push(kind);
+            handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+            handshake.extend_from_slice(&body);
+        }
+        let mut reply = vec![22, 3, 1];
+        reply.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        reply.extend_from_slice(&handshake);
+        reply
+    }
+
+    #[tokio::test]
+    async fn test_tls_cert_inspect_tls10() {
+        let port = spawn_scripted_server(Vec::new(), tls10_handshake).await;
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+
+        let cert = tls_cert::inspect(addr, port, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(cert.protocol, "TLSv1.0");
+        assert_eq!(cert.cipher, "TLS_RSA_WITH_AES_128_CBC_SHA");
+        assert_eq!(cert.sans, vec!["legacy.local"]);
+        assert!(cert.self_signed);
+    }
+
+    #[tokio::test]
+    async fn test_http_fetch_over_tls() {
+        let port = spawn_tls_server(365).await;
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+
+        let response = http::fetch(addr, port, true, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert!(response.tls);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_length, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_tls_cert_fetcher_without_tls_ports() {
+        let config = Arc::new(ScannerConfig::default());
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config.clone());
+
+        let result = TlsCertFetcher::new(config)
+            .scan(&mut subject)
+            .await
+            .unwrap();
+        assert_eq!(result, "[n/a]");
+    }
+
+    fn ssh_string(buffer: &mut Vec<u8>, value: &[u8]) {
+        buffer.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        buffer.extend_from_slice(value);
+    }
+
+    fn ssh_packet(payload: &[u8]) -> Vec<u8> {
+        let padding = 4 + (8 - (9 + payload.len()) % 8) % 8;
+        let mut packet = ((1 + payload.len() + padding) as u32)
+            .to_be_bytes()
+            .to_vec();
+        packet.push(padding as u8);
+        packet.extend_from_slice(payload);
+        packet.resize(packet.len() + padding, 0);
+        packet
+    }
+
+    fn ssh_host_key() -> Vec<u8> {
+        let mut key = Vec::new();
+        ssh_string(&mut key, b"ssh-ed25519");
+        ssh_string(&mut key, &[7u8; 32]);
+        key
+    }
+
+    /// Sends an identification string and KEXINIT, then answers the key
+    /// exchange init with a fixed host key.
+    async fn spawn_ssh_server() -> u16 {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    let mut kexinit = vec![20u8];
+                    kexinit.extend_from_slice(&[0u8; 16]);
+                    for list in [
+                        "curve25519-sha256,diffie-hellman-group1-sha1",
+                        "ssh-ed25519,ssh-rsa",
+                        "aes128-ctr,aes128-cbc",
+                        "aes128-ctr,aes128-cbc",
+                        "hmac-sha2-256",
+                        "hmac-sha2-256",
+                        "none",
+                        "none",
+                        "",
+                        "",
+                    ] {
+                        ssh_string(&mut kexinit, list.as_bytes());
+                    }
+                    kexinit.extend_from_slice(&[0, 0, 0, 0, 0]);
+                    let mut greeting = b"SSH-2.0-OpenSSH_9.6 test\r\n".to_vec();
+                    greeting.extend_from_slice(&ssh_packet(&kexinit));
+                    if stream.get_mut().write_all(&greeting).await.is_err() {
+                        return;
+                    }
+
+                    let mut ident = String::new();
+                    if stream.read_line(&mut ident).await.unwrap_or(0) == 0 {
+                        return;
+                    }
+                    for expected in [20u8, 30] {
+                        let Ok(len) = stream.read_u32().await else {
+                            return;
+                        };
+                        let mut body = vec![0u8; len as usize];
+                        if stream.read_exact(&mut body).await.is_err() || body[1] != expected {
+                            return;
+                        }
+                    }
+
+                    let mut reply = vec![31u8];
+                    ssh_string(&mut reply, &ssh_host_key());
+                    ssh_string(&mut reply, &[9u8; 32]);
+                    ssh_string(&mut reply, &[0u8; 83]);
+                    let _ = stream.get_mut().write_all(&ssh_packet(&reply)).await;
+                });
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_ssh_weak_algorithms() {
+        assert!(ssh::is_weak_algorithm("diffie-hellman-group1-sha1"));
+        assert!(ssh::is_weak_algorithm("ssh-dss"));
+        assert!(ssh::is_weak_algorithm("aes256-cbc"));
+        assert!(ssh::is_weak_algorithm("hmac-md5-96"));
+        assert!(!ssh::is_weak_algorithm("curve25519-sha256"));
+        assert!(!ssh::is_weak_algorithm("rsa-sha2-512"));
+        assert!(!ssh::is_weak_algorithm("aes256-gcm@openssh.com"));
+        assert!(ssh::parse_kexinit(b"\x1e").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ssh_inspect() {
+        let port = spawn_ssh_server().await;
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+
+        let server = ssh::inspect(addr, port, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(server.ident, "SSH-2.0-OpenSSH_9.6 test");
+        assert_eq!(
+            server.kex_algorithms,
+            vec!["curve25519-sha256", "diffie-hellman-group1-sha1"]
+        );
+        assert_eq!(server.host_key_algorithms, vec!["ssh-ed25519", "ssh-rsa"]);
+        assert_eq!(server.ciphers, vec!["aes128-ctr", "aes128-cbc"]);
+        assert_eq!(server.macs, vec!["hmac-sha2-256"]);
+        assert_eq!(server.host_key_type.as_deref(), Some("ssh-ed25519"));
+        assert_eq!(
+            server.fingerprint.as_deref(),
+            Some(ssh::fingerprint(&ssh_host_key()).as_str())
+        );
+        assert_eq!(
+            server.weak_algorithms,
+            vec!["aes128-cbc", "diffie-hellman-group1-sha1", "ssh-rsa"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ssh_fetcher_uses_banners() {
+        let port = spawn_ssh_server().await;
+        let config = Arc::new(ScannerConfig {
+            port_string: port.to_string(),
+            service_timeout_ms: 2000,
+            ..Default::default()
+        });
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config.clone());
+        PortsFetcher::new(config.clone())
+            .scan(&mut subject)
+            .await
+            .unwrap();
+        let fetcher = SshFetcher::new(config);
+
+        // An unusual port is only probed once its banner says SSH.
+        assert_eq!(fetcher.scan(&mut subject).await.unwrap(), "[n/a]");
+
+        subject.set_parameter(
+            BANNERS.to_string(),
+            Banners::from([(port, "SSH-2.0-OpenSSH_9.6 test".to_string())]),
+        );
+        let result = fetcher.scan(&mut subject).await.unwrap();
+        assert!(result.starts_with(&format!("{}: SSH-2.0-OpenSSH_9.6 test SHA256:", port)));
+        let servers = subject.get_parameter::<SshServers>(SSH_SERVERS).unwrap();
+        assert!(servers[&port].fingerprint.is_some());
+    }
+
+    fn nbstat_response() -> Vec<u8> {
+        let mut response = vec![0x13, 0x37, 0x84, 0x00, 0, 0, 0, 1, 0, 0, 0, 0];
+        response.extend_from_slice(&udp_payloads::NETBIOS_NBSTAT[12..46]);
+        response.extend_from_slice(&[0x00, 0x21, 0x00, 0x01, 0, 0, 0, 0, 0x00, 0x53, 4]);
+        for (name, suffix, flags) in [
+            ("DESKTOP-1", 0x00, 0x0400u16),
+            ("WORKGROUP", 0x00, 0x8400),
+            ("DESKTOP-1", 0x03, 0x0400),
+            ("ALICE", 0x03, 0x0400),
+        ] {
+            response.extend_from_slice(format!("{:<15}", name).as_bytes());
+            response.push(suffix);
+            response.extend_from_slice(&flags.to_be_bytes());
+        }
+        response.extend_from_slice(&[0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e]);
+        response
+    }
+
+    #[test]
+    fn test_netbios_parse() {
+        let info = NetBiosInfo::parse(&nbstat_response()).unwrap();
+        assert_eq!(info.computer_name.as_deref(), Some("DESKTOP-1"));
+        assert_eq!(info.workgroup.as_deref(), Some("WORKGROUP"));
+        assert_eq!(info.user.as_deref(), Some("ALICE"));
+        assert_eq!(info.mac.as_deref(), Some("00:1A:2B:3C:4D:5E"));
+        assert_eq!(info.names.len(), 4);
+        assert!(info.names[1].group);
+        assert_eq!(info.to_string(), "WORKGROUP\\DESKTOP-1 (ALICE)");
+
+        let mut samba = nbstat_response();
+        let len = samba.len();
+        samba[len - 6..].fill(0);
+        assert_eq!(NetBiosInfo::parse(&samba).unwrap().mac, None);
+        assert!(NetBiosInfo::parse(&samba[..20]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_netbios_query() {
+        let responder = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = responder.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 512];
+            if let Ok((len, peer)) = responder.recv_from(&mut buffer).await
+                && buffer[..len] == *udp_payloads::NETBIOS_NBSTAT
+            {
+                let _ = responder.send_to(&nbstat_response(), peer).await;
+            }
+        });
+
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let info = netbios::query(addr, port, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(info.computer_name.as_deref(), Some("DESKTOP-1"));
+    }
+
+    #[tokio::test]
+    async fn test_netbios_fetcher_gives_up_quickly() {
+        let config = Arc::new(ScannerConfig {
+            netbios_timeout_ms: 200,
+            service_timeout_ms: 5000,
+            ..Default::default()
+        });
+        // A documentation address, where nothing answers.
+        let addr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let mut subject = ScanningSubject::new(addr, config.clone());
+
+        let started = std::time::Instant::now();
+        let result = NetBiosFetcher::new(config)
+            .scan(&mut subject)
+            .await
+            .unwrap();
+        assert_eq!(result, "[n/a]");
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_hostname_falls_back_to_netbios() {
+        let config = Arc::new(ScannerConfig::default());
+        // TEST-NET-1 has no PTR records.
+        let addr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let mut subject = ScanningSubject::new(addr, config);
+        subject.set_parameter(
+            NETBIOS.to_string(),
+            NetBiosInfo::parse(&nbstat_response()).unwrap(),
+        );
+
+        let result = HostnameFetcher::new().scan(&mut subject).await.unwrap();
+        assert_eq!(result, "DESKTOP-1");
+    }
+
+    /// Record type and data of a stub answer owned by the question name.
+    type StubAnswers = Option<Vec<(u16, Vec<u8>)>>;
+
+    fn dns_name(name: &str) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        for label in name.split('.') {
+            encoded.push(label.len() as u8);
+            encoded.extend_from_slice(label.as_bytes());
+        }
+        encoded.push(0);
+        encoded
+    }
+
+    /// Answers every question with `answer`, or NXDOMAIN when it has none.
+    async fn spawn_dns_stub(answer: fn(&dns::Question) -> StubAnswers) -> std::net::SocketAddr {
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 512];
+            while let Ok((len, peer)) = socket.recv_from(&mut buffer).await {
+                let Some(question) = dns::Message::parse(&buffer[..len])
+                    .and_then(|query| query.questions.into_iter().next())
+                else {
+                    continue;
+                };
+                let answers = answer(&question);
+
+                let mut reply = buffer[..len].to_vec();
+                let rcode = if answers.is_some() { 0 } else { 3 };
+                reply[2..4].copy_from_slice(&(0x8580u16 | rcode).to_be_bytes());
+                let answers = answers.unwrap_or_default();
+                reply[6..8].copy_from_slice(&(answers.len() as u16).to_be_bytes());
+                for (record_type, data) in answers {
+                    reply.extend_from_slice(&[0xc0, 0x0c]);
+                    reply.extend_from_slice(&record_type.to_be_bytes());
+                    reply.extend_from_slice(&[0x80, 0x01, 0, 0, 0x0e, 0x10]);
+                    reply.extend_from_slice(&(data.len() as u16).to_be_bytes());
+                    reply.extend_from_slice(&data);
+                }
+                let _ = socket.send_to(&reply, peer).await;
+            }
+        });
+        addr
+    }
+
+    fn mdns_answer(question: &dns::Question) -> StubAnswers {
+        match question.name.as_str() {
+            "1.0.0.127.in-addr.arpa" => Some(vec![(dns::TYPE_PTR, dns_name("mac-mini.local"))]),
+            "_services._dns-sd._udp.local" => Some(vec![
+                (dns::TYPE_PTR, dns_name("_ssh._tcp.local")),
+                (dns::TYPE_PTR, dns_name("_ipp._tcp.local")),
+            ]),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_dns_reverse_name() {
+        assert_eq!(
+            dns::reverse_name(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20))),
+            "20.1.168.192.in-addr.arpa"
+        );
+        let v6 = dns::reverse_name("2001:db8::1".parse().unwrap());
+        assert!(v6.starts_with("1.0.0.0.0.0.0.0."));
+        assert!(v6.ends_with(".8.b.d.0.1.0.0.2.ip6.arpa"));
+    }
+
+    #[test]
+    fn test_dns_message_roundtrip() {
+        let query = dns::query(0x1234, "example.com", dns::TYPE_A, dns::CLASS_IN, true);
+        let message = dns::Message::parse(&query).unwrap();
+        assert_eq!(message.id, 0x1234);
+        assert!(!message.is_response());
+        assert_eq!(message.questions[0].name, "example.com");
+        assert_eq!(message.questions[0].record_type, dns::TYPE_A);
+        assert!(dns::Message::parse(&query[..8]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mdns_query() {
+        let server = spawn_dns_stub(mdns_answer).await;
+        let info = mdns::query(server.ip(), server.port(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(info.name.as_deref(), Some("mac-mini.local"));
+        assert_eq!(info.services, vec!["_ssh._tcp", "_ipp._tcp"]);
+        assert_eq!(info.to_string(), "mac-mini.local [_ssh._tcp, _ipp._tcp]");
+    }
+
+    #[tokio::test]
+    async fn test_llmnr_query() {
+        let server = spawn_dns_stub(|question| {
+            (question.name == "1.0.0.127.in-addr.arpa")
+                .then(|| vec![(dns::TYPE_PTR, dns_name("WIN-PC"))])
+        })
+        .await;
+        let wait = Duration::from_secs(1);
+        assert_eq!(
+            llmnr::query(server.ip(), server.port(), wait)
+                .await
+                .as_deref(),
+            Some("WIN-PC")
+        );
+
+        let silent = spawn_dns_stub(|_| None).await;
+        assert_eq!(llmnr::query(silent.ip(), silent.port(), wait).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_hostname_falls_back_to_mdns() {
+        let config = Arc::new(ScannerConfig::default());
+        let addr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let mut subject = ScanningSubject::new(addr, config);
+        subject.set_parameter(
+            MDNS.to_string(),
+            MdnsInfo {
+                name: Some("printer.local".to_string()),
+                services: Vec::new(),
+            },
+        );
+
+        let result = HostnameFetcher::new().scan(&mut subject).await.unwrap();
+        assert_eq!(result, "printer.local");
+    }
+
+    fn resolver_answer(question: &dns::Question) -> StubAnswers {
+        match (question.name.as_str(), question.record_type) {
+            ("1.0.0.127.in-addr.arpa", dns::TYPE_PTR) => {
+                Some(vec![(dns::TYPE_PTR, dns_name("host.example"))])
+            }
+            ("2.0.0.127.in-addr.arpa", dns::TYPE_PTR) => {
+                Some(vec![(dns::TYPE_PTR, dns_name("liar.example"))])
+            }
+            ("host.example", dns::TYPE_A) => Some(vec![(dns::TYPE_A, vec![127, 0, 0, 1])]),
+            ("liar.example", dns::TYPE_A) => Some(vec![(dns::TYPE_A, vec![10, 0, 0, 1])]),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_resolver_parse_servers() {
+        let servers = resolver::parse_servers("1.1.1.1, 9.9.9.9:5353,[::1]:53,").unwrap();
+        assert_eq!(
+            servers,
+            vec![
+                "1.1.1.1:53".parse().unwrap(),
+                "9.9.9.9:5353".parse().unwrap(),
+                "[::1]:53".parse().unwrap(),
+            ]
+        );
+        assert!(resolver::parse_servers("").unwrap().is_empty());
+        assert!(resolver::parse_servers("dns.example").is_err());
+    }
+
+    #[test]
+    fn test_resolver_system_files() {
+        let servers = resolver::parse_resolv_conf(
+            "# generated\nsearch corp.example\nnameserver 192.0.2.53\n\
+             nameserver fe80::1%eth0\noptions edns0\n",
+        );
+        assert_eq!(
+            servers,
+            vec![
+                "192.0.2.53:53".parse().unwrap(),
+                "[fe80::1]:53".parse().unwrap(),
+            ]
+        );
+
+        let hosts = resolver::parse_hosts(
+            "127.0.0.1\tlocalhost\n::1 localhost ip6-localhost\n\
+             # 10.0.0.9 commented.example\n10.0.0.5 nas.lan nas # storage\n\
+             10.0.0.5 other.lan\nbogus line\n",
+        );
+        let name = |ip: &str| hosts.get(&ip.parse::<IpAddr>().unwrap()).cloned();
+        assert_eq!(name("127.0.0.1").as_deref(), Some("localhost"));
+        assert_eq!(name("::1").as_deref(), Some("localhost"));
+        assert_eq!(name("10.0.0.5").as_deref(), Some("nas.lan"));
+        assert_eq!(name("10.0.0.9"), None);
+        assert_eq!(hosts.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_resolver_reverse() {
+        let server = spawn_dns_stub(resolver_answer).await;
+        let wait = Duration::from_millis(500);
+        let localhost = |last| IpAddr::V4(Ipv4Addr::new(127, 0, 0, last));
+
+        let resolver = Resolver::new(vec![server], wait, 0, false);
+        assert_eq!(
+            resolver.reverse(localhost(1)).await.as_deref(),
+            Some("host.example")
+        );
+        assert_eq!(
+            resolver.reverse(localhost(2)).await.as_deref(),
+            Some("liar.example")
+        );
+        assert_eq!(resolver.reverse(localhost(3)).await, None);
+
+        let confirming = Resolver::new(vec![server], wait, 0, true);
+        assert_eq!(
+            confirming.reverse(localhost(1)).await.as_deref(),
+            Some("host.example")
+        );
+        assert_eq!(confirming.reverse(localhost(2)).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_resolver_fails_over_and_caches() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static QUERIES: AtomicUsize = AtomicUsize::new(0);
+
+        let server = spawn_dns_stub(|question| {
+            QUERIES.fetch_add(1, Ordering::Relaxed);
+            resolver_answer(question)
+        })
+        .await;
+        // Bound but never answering.
+        let silent = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        let resolver = Resolver::new(
+            vec![silent.local_addr().unwrap(), server],
+            Duration::from_millis(200),
+            1,
+            false,
+        );
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        assert_eq!(
+            resolver.reverse(addr).await.as_deref(),
+            Some("host.example")
+        );
+        assert_eq!(
+            resolver.reverse(addr).await.as_deref(),
+            Some("host.example")
+        );
+        assert_eq!(QUERIES.load(Ordering::Relaxed), 1);
+
+        // The cache outlives the resolver, as the hostname fetcher of each
+        // scan gets a new one.
+        let next_scan = Resolver::new(
+            vec![silent.local_addr().unwrap(), server],
+            Duration::from_millis(200),
+            1,
+            false,
+        );
+        assert_eq!(
+            next_scan.reverse(addr).await.as_deref(),
+            Some("host.example")
+        );
+        assert_eq!(QUERIES.load(Ordering::Relaxed), 1);
+
+        let unreachable = Resolver::new(
+            vec![silent.local_addr().unwrap()],
+            Duration::from_millis(100),
+            2,
+            false,
+        );
+        let started = std::time::Instant::now();
+        assert_eq!(unreachable.reverse(addr).await, None);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+
+        // A timeout is not an answer, so the next scan asks again.
+        let started = std::time::Instant::now();
+        assert_eq!(unreachable.reverse(addr).await, None);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_hostname_fetcher_uses_configured_servers() {
+        let server = spawn_dns_stub(resolver_answer).await;
+        let config = Arc::new(ScannerConfig {
+            dns_servers: vec![server],
+            dns_confirm_forward: true,
+            ..Default::default()
+        });
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config.clone());
+
+        let fetcher = HostnameFetcher::with_config(&config);
+        assert_eq!(fetcher.scan(&mut subject).await.unwrap(), "host.example");
+    }
+
+    fn ber(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![tag];
+        if content.len() < 0x80 {
+            encoded.push(content.len() as u8);
+        } else {
+            encoded.push(0x82);
+            encoded.extend_from_slice(&(content.len() as u16).to_be_bytes());
+        }
+        encoded.extend_from_slice(content);
+        encoded
+    }
+
+    fn ber_read(bytes: &[u8]) -> (u8, &[u8], &[u8]) {
+        let (len, start) = match bytes[1] {
+            0x81 => (bytes[2] as usize, 3),
+            0x82 => (u16::from_be_bytes([bytes[2], bytes[3]]) as usize, 4),
+            len => (len as usize, 2),
+        };
+        (bytes[0], &bytes[start..start + len], &bytes[start + len..])
+    }
+
+    /// Value of `1.3.6.1.2.1.1.<n>.0` on the stub agent.
+    fn snmp_system_value(n: u8) -> Option<Vec<u8>> {
+        match n {
+            1 => Some(ber(0x04, b"Linux router 5.10\nbuilt by someone")),
+            2 => Some(ber(0x06, &[0x2b, 6, 1, 4, 1, 0xbf, 0x08, 3, 2, 10])),
+            3 => Some(ber(0x43, &[0x01, 0xe2, 0x40])),
+            4 => Some(ber(0x04, b"")),
+            5 => Some(ber(0x04, b"router")),
+            _ => None,
+        }
+    }
+
+    /// Agent that speaks v2c with `public` and only v1 with `private`,
+    /// failing v1 requests for several objects like agents that lack one.
+    async fn spawn_snmp_agent() -> u16 {
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 1500];
+            while let Ok((len, peer)) = socket.recv_from(&mut buffer).await {
+                let (_, message, _) = ber_read(&buffer[..len]);
+                let (_, version, rest) = ber_read(message);
+                let (_, community, rest) = ber_read(rest);
+                let (_, pdu, _) = ber_read(rest);
+                let (_, request_id, rest) = ber_read(pdu);
+                let (_, _, rest) = ber_read(rest);
+                let (_, _, rest) = ber_read(rest);
+                let (_, mut list, _) = ber_read(rest);
+
+                let mut oids = Vec::new();
+                while !list.is_empty() {
+                    let (_, varbind, rest) = ber_read(list);
+                    oids.push(ber_read(varbind).1.to_vec());
+                    list = rest;
+                }
+
+                let v1 = match (version, community) {
+                    ([1], b"public") => false,
+                    ([0], b"private") => true,
+                    _ => continue,
+                };
+                let mut error_status = 0;
+                let mut varbinds = Vec::new();
+                for oid in &oids {
+                    let value = match snmp_system_value(oid[6]) {
+                        Some(value) => value,
+                        None if v1 => {
+                            error_status = 2;
+                            break;
+                        }
+                        None => ber(0x80, &[]),
+                    };
+                    let mut varbind = ber(0x06, oid);
+                    varbind.extend_from_slice(&value);
+                    varbinds.extend_from_slice(&ber(0x30, &varbind));
+                }
+                if v1 && oids.len() > 1 {
+                    error_status = 2;
+                }
+                if error_status != 0 {
+                    // v1 echoes the request's variable bindings on error.
+                    varbinds.clear();
+                    for oid in &oids {
+                        let mut varbind = ber(0x06, oid);
+                        varbind.extend_from_slice(&ber(0x05, &[]));
+                        varbinds.extend_from_slice(&ber(0x30, &varbind));
+                    }
+                }
+
+                let mut pdu = ber(0x02, request_id);
+                pdu.extend_from_slice(&ber(0x02, &[error_status]));
+                pdu.extend_from_slice(&ber(0x02, &[0]));
+                pdu.extend_from_slice(&ber(0x30, &varbinds));
+                let mut message = ber(0x02, version);
+                message.extend_from_slice(&ber(0x04, community));
+                message.extend_from_slice(&ber(0xa2, &pdu));
+                let _ = socket.send_to(&ber(0x30, &message), peer).await;
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_snmp_get_request_encoding() {
+        let request =
+            snmp::get_request(SnmpVersion::V2c, "public", 0x1234, &[snmp::SYS_NAME]).unwrap();
+        let (tag, message, rest) = ber_read(&request);
+        assert_eq!(tag, 0x30);
+        assert!(rest.is_empty());
+        let (_, version, rest) = ber_read(message);
+        assert_eq!(version, [1]);
+        let (_, community, rest) = ber_read(rest);
+        assert_eq!(community, b"public");
+        let (tag, pdu, _) = ber_read(rest);
+        assert_eq!(tag, 0xa0);
+        let (_, request_id, _) = ber_read(pdu);
+        assert_eq!(request_id, [0x12, 0x34]);
+        assert!(request.windows(8).any(|w| w == [0x2b, 6, 1, 2, 1, 1, 5, 0]));
+        assert!(snmp::get_request(SnmpVersion::V1, "public", 1, &["not.an.oid"]).is_none());
+    }
+
+    #[test]
+    fn test_snmp_response_parse() {
+        let mut varbind = ber(0x06, &[0x2b, 6, 1, 2, 1, 1, 3, 0]);
+        varbind.extend_from_slice(&ber(0x43, &[0x01, 0xe2, 0x40]));
+        let mut pdu = ber(0x02, &[0x00, 0x80]);
+        pdu.extend_from_slice(&ber(0x02, &[0]));
+        pdu.extend_from_slice(&ber(0x02, &[0]));
+        pdu.extend_from_slice(&ber(0x30, &ber(0x30, &varbind)));
+        let mut message = ber(0x02, &[1]);
+        message.extend_from_slice(&ber(0x04, b"public"));
+        message.extend_from_slice(&ber(0xa2, &pdu));
+
+        let response = SnmpResponse::parse(&ber(0x30, &message)).unwrap();
+        assert_eq!(response.request_id, 128);
+        assert_eq!(response.error_status, 0);
+        assert_eq!(
+            response.value(snmp::SYS_UPTIME),
+            Some(&SnmpValue::TimeTicks(123456))
+        );
+        assert!(SnmpResponse::parse(&message).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_snmp_query_v2c() {
+        let port = spawn_snmp_agent().await;
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_millis(500);
+
+        let info = snmp::query(addr, port, SnmpVersion::V2c, "public", wait)
+            .await
+            .unwrap();
+        assert_eq!(info.sys_name.as_deref(), Some("router"));
+        assert_eq!(
+            info.sys_descr.as_deref(),
+            Some("Linux router 5.10\nbuilt by someone")
+        );
+        assert_eq!(
+            info.sys_object_id.as_deref(),
+            Some("1.3.6.1.4.1.8072.3.2.10")
+        );
+        assert_eq!(info.sys_uptime, Some(123456));
+        assert_eq!(info.sys_contact, None);
+        assert_eq!(info.sys_location, None);
+        assert_eq!(info.to_string(), "router: Linux router 5.10 (v2c public)");
+
+        let wrong = snmp::query(addr, port, SnmpVersion::V2c, "private", wait).await;
+        assert!(wrong.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_snmp_query_v1_falls_back_to_single_gets() {
+        let port = spawn_snmp_agent().await;
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+
+        let info = snmp::query(
+            addr,
+            port,
+            SnmpVersion::V1,
+            "private",
+            Duration::from_millis(500),
+        )
+        .await
+        .unwrap();
+        assert_eq!(info.version, SnmpVersion::V1);
+        assert_eq!(info.sys_name.as_deref(), Some("router"));
+        assert_eq!(info.sys_uptime, Some(123456));
+        assert_eq!(info.sys_location, None);
+    }
+
+    const UPNP_DESCRIPTION: &str = "<?xml version=\"1.0\"?>
+<root xmlns=\"urn:schemas-upnp-org:device-1-0\">
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:InternetGatewayDevice:1</deviceType>
+    <friendlyName>Home Router</friendlyName>
+    <manufacturer>AT&amp;T</manufacturer>
+    <modelName>BGW210</modelName>
+    <modelNumber>700</modelNumber>
+    <deviceList>
+      <device>
+        <deviceType>urn:schemas-upnp-org:device:WANDevice:1</deviceType>
+        <friendlyName>WANDevice</friendlyName>
+      </device>
+    </deviceList>
+  </device>
+</root>";
+
+    #[test]
+    fn test_ssdp_parse_description() {
+        let mut info = SsdpInfo::default();
+        info.parse_description(UPNP_DESCRIPTION);
+        assert_eq!(info.friendly_name.as_deref(), Some("Home Router"));
+        assert_eq!(info.manufacturer.as_deref(), Some("AT&T"));
+        assert_eq!(
+            info.device_type.as_deref(),
+            Some("urn:schemas-upnp-org:device:InternetGatewayDevice:1")
+        );
+        assert_eq!(info.to_string(), "Home Router (AT&T BGW210 700)");
+
+        let bare = SsdpInfo {
+            server: Some("Linux UPnP/1.0 miniupnpd/2.0".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(bare.to_string(), "Linux UPnP/1.0 miniupnpd/2.0");
+    }
+
+    #[tokio::test]
+    async fn test_ssdp_query_follows_location() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let web = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let web_port = web.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = web.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let len = stream.read(&mut request).await.unwrap();
+            assert!(request[..len].starts_with(b"GET /rootDesc.xml HTTP/1.0\r\n"));
+            let response = format!(
+                "HTTP/1.0 200 OK\r\nContent-Type: text/xml\r\n\r\n{}",
+                UPNP_DESCRIPTION
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let ssdp_port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 1024];
+            let (len, peer) = socket.recv_from(&mut buffer).await.unwrap();
+            assert!(buffer[..len].starts_with(b"M-SEARCH * HTTP/1.1\r\n"));
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=120\r\nST: upnp:rootdevice\r\n\
+                 LOCATION: http://127.0.0.1:{}/rootDesc.xml\r\nSERVER: Linux UPnP/1.0 miniupnpd/2.0\r\n\r\n",
+                web_port
+            );
+            socket.send_to(response.as_bytes(), peer).await.unwrap();
+        });
+
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let info = ssdp::query(addr, ssdp_port, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(info.server.as_deref(), Some("Linux UPnP/1.0 miniupnpd/2.0"));
+        assert_eq!(info.friendly_name.as_deref(), Some("Home Router"));
+        assert_eq!(info.model_name.as_deref(), Some("BGW210"));
+        assert_eq!(info.model_number.as_deref(), Some("700"));
+    }
+
+    /// NTLMSSP CHALLENGE for `CORP\\FILES01` running Windows 10.0.19041.
+    fn ntlm_challenge() -> Vec<u8> {
+        let utf16 = |text: &str| -> Vec<u8> {
+            text.encode_utf16()
+                .flat_map(|unit| unit.to_le_bytes())
+                .collect()
+        };
+        let mut target_info = Vec::new();
+        for (id, value) in [
+            (2u16, "CORP"),
+            (1, "FILES01"),
+            (4, "corp.example"),
+            (3, "files01.corp.example"),
+        ] {
+            let value = utf16(value);
+            target_info.extend_from_slice(&id.to_le_bytes());
+            target_info.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            target_info.extend_from_slice(&value);
+        }
+        target_info.extend_from_slice(&[0; 4]);
+
+        let mut challenge = b"NTLMSSP\0".to_vec();
+        challenge.extend_from_slice(&2u32.to_le_bytes());
+        challenge.extend_from_slice(&[0, 0, 0, 0, 56, 0, 0, 0]); // target name
+        challenge.extend_from_slice(&0xe289_8215u32.to_le_bytes());
+        challenge.extend_from_slice(&[0x11; 8]); // server challenge
+        challenge.extend_from_slice(&[0; 8]);
+        challenge.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        challenge.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        challenge.extend_from_slice(&56u32.to_le_bytes());
+        challenge.extend_from_slice(&[10, 0, 0x61, 0x4a, 0, 0, 0, 15]);
+        challenge.extend_from_slice(&target_info);
+        challenge
+    }
+
+    #[test]
+    fn test_smb_parse_challenge() {
+        let mut info = SmbInfo::default();
+        smb::parse_challenge(&ntlm_challenge(), &mut info).unwrap();
+        assert_eq!(info.netbios_computer_name.as_deref(), Some("FILES01"));
+        assert_eq!(info.netbios_domain_name.as_deref(), Some("CORP"));
+        assert_eq!(
+            info.dns_computer_name.as_deref(),
+            Some("files01.corp.example")
+        );
+        assert_eq!(info.dns_domain_name.as_deref(), Some("corp.example"));
+        assert_eq!(info.os_version.as_deref(), Some("10.0.19041"));
+
+        assert!(smb::parse_challenge(b"NTLMSSP\0\x01\0\0\0", &mut info).is_none());
+    }
+
+    /// SMB server speaking 2.1 to 3.1.1 with signing required, and SMBv1
+    /// when `smb1` is set.
+    async fn spawn_smb_server(smb1: bool) -> u16 {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        fn smb2_reply(request: &[u8], status: u32, body: &[u8]) -> Vec<u8> {
+            let mut reply = request[..64].to_vec();
+            reply[8..12].copy_from_slice(&status.to_le_bytes());
+            reply[16] = 1; // response flag
+            reply.extend_from_slice(body);
+            reply
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    loop {
+                        let mut header = [0u8; 4];
+                        if stream.read_exact(&mut header).await.is_err() {
+                            return;
+                        }
+                        let len = u32::from_be_bytes(header) as usize;
+                        let mut request = vec![0u8; len];
+                        stream.read_exact(&mut request).await.unwrap();
+
+                        let reply = if request.starts_with(b"\xffSMB") {
+                            if !smb1 {
+                                return;
+                            }
+                            let mut reply = request[..32].to_vec();
+                            reply.extend_from_slice(&[17, 0, 0, 0x0f]);
+                            reply.extend_from_slice(&[0; 32]);
+                            reply
+                        } else if request[12] == 0 {
+                            let count = u16::from_le_bytes([request[66], request[67]]) as usize;
+                            let offered: Vec<u16> = request[100..100 + count * 2]
+                                .chunks(2)
+                                .map(|d| u16::from_le_bytes([d[0], d[1]]))
+                                .collect();
+                            let Some(&dialect) = offered
+                                .iter()
+                                .filter(|&&d| (0x0210..=0x0311).contains(&d))
+                                .max()
+                            else {
+                                return;
+                            };
+                            let mut body = vec![65, 0, 0x03, 0];
+                            body.extend_from_slice(&dialect.to_le_bytes());
+                            body.extend_from_slice(&[0; 58]);
+                            smb2_reply(&request, 0, &body)
+                        } else {
+                            let challenge = ntlm_challenge();
+                            let mut body = vec![9, 0, 0, 0, 72, 0];
+                            body.extend_from_slice(&(challenge.len() as u16).to_le_bytes());
+                            body.extend_from_slice(&challenge);
+                            smb2_reply(&request, 0xc000_0016, &body)
+                        };
+                        let mut framed = (reply.len() as u32).to_be_bytes().to_vec();
+                        framed.extend_from_slice(&reply);
+                        stream.write_all(&framed).await.unwrap();
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_smb_query() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+        let sockets = Arc::new(tokio::sync::Semaphore::new(500));
+
+        let port = spawn_smb_server(true).await;
+        let info = smb::query(addr, port, true, &sockets, wait).await.unwrap();
+        assert_eq!(info.dialects, vec!["2.1", "3.0", "3.0.2", "3.1.1"]);
+        assert!(info.signing_enabled);
+        assert!(info.signing_required);
+        assert_eq!(info.smb1, Some(true));
+        assert_eq!(info.netbios_computer_name.as_deref(), Some("FILES01"));
+        assert_eq!(
+            info.to_string(),
+            "CORP\\FILES01; Windows 10.0.19041; SMB 2.1, 3.0, 3.0.2, 3.1.1; signing required; SMBv1"
+        );
+
+        let port = spawn_smb_server(false).await;
+        let info = smb::query(addr, port, true, &sockets, wait).await.unwrap();
+        assert_eq!(info.smb1, Some(false));
+        let info = smb::query(addr, port, false, &sockets, wait).await.unwrap();
+        assert_eq!(info.smb1, None);
+    }
+
+    #[tokio::test]
+    async fn test_smb_query_takes_a_socket_per_connection() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+        let port = spawn_smb_server(true).await;
+
+        // The port scan of another host holds the only socket.
+        let sockets = Arc::new(tokio::sync::Semaphore::new(1));
+        let held = sockets.clone().acquire_owned().await.unwrap();
+        let waiting = smb::query(addr, port, true, &sockets, wait);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), waiting)
+                .await
+                .is_err()
+        );
+        drop(held);
+
+        // One socket is enough, the connections just take turns.
+        let info = smb::query(addr, port, true, &sockets, wait).await.unwrap();
+        assert_eq!(info.dialects, vec!["2.1", "3.0", "3.0.2", "3.1.1"]);
+        assert_eq!(info.smb1, Some(true));
+        assert_eq!(sockets.available_permits(), 1);
+    }
+
+    /// Sends `greeting`, then answers every read with `respond` until it
+    /// returns nothing.
+    async fn spawn_scripted_server(greeting: Vec<u8>, respond: fn(&[u8]) -> Vec<u8>) -> u16 {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let greeting = greeting.clone();
+                tokio::spawn(async move {
+                    if !greeting.is_empty() {
+                        stream.write_all(&greeting).await.unwrap();
+                    }
+                    let mut buffer = [0u8; 4096];
+                    while let Ok(len) = stream.read(&mut buffer).await {
+                        let reply = respond(&buffer[..len]);
+                        if len == 0 || reply.is_empty() {
+                            break;
+                        }
+                        stream.write_all(&reply).await.unwrap();
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    #[tokio::test]
+    async fn test_database_mysql_and_redis() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let mut payload = b"\x0a5.5.5-10.6.12-MariaDB\0".to_vec();
+        payload.extend_from_slice(&[
+            1, 0, 0, 0, b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h', 0,
+        ]);
+        let mut greeting = (payload.len() as u32).to_le_bytes().to_vec();
+        greeting[3] = 0;
+        greeting.extend_from_slice(&payload);
+        let port = spawn_scripted_server(greeting, |_| Vec::new()).await;
+        let mysql = database::probe(DatabaseKind::MySql, addr, port, wait)
+            .await
+            .unwrap();
+        assert_eq!(mysql.version.as_deref(), Some("10.6.12-MariaDB"));
+        assert!(!mysql.unauthenticated);
+
+        let port = spawn_scripted_server(Vec::new(), |_| {
+            b"+PONG\r\n$38\r\n# Server\r\nredis_version:7.0.11\r\nredis_mode:standalone\r\n\r\n"
+                .to_vec()
+        })
+        .await;
+        let redis = database::probe(DatabaseKind::Redis, addr, port, wait)
+            .await
+            .unwrap();
+        assert!(redis.unauthenticated);
+        assert_eq!(redis.to_string(), "Redis 7.0.11 (NO AUTHENTICATION)");
+
+        let port = spawn_scripted_server(Vec::new(), |_| {
+            b"-NOAUTH Authentication required.\r\n-NOAUTH Authentication required.\r\n".to_vec()
+        })
+        .await;
+        let redis = database::probe(DatabaseKind::Redis, addr, port, wait)
+            .await
+            .unwrap();
+        assert!(!redis.unauthenticated);
+        assert_eq!(redis.details, vec!["auth required"]);
+
+        let port =
+            spawn_scripted_server(Vec::new(), |_| b"HTTP/1.1 400 Bad Request\r\n\r\n".to_vec())
+                .await;
+        assert!(
+            database::probe(DatabaseKind::Redis, addr, port, wait)
+                .await
+                .is_none()
+        );
+    }
+
+    fn postgres_message(kind: u8, body: &[u8]) -> Vec<u8> {
+        let mut message = vec![kind];
+        message.extend_from_slice(&((body.len() + 4) as u32).to_be_bytes());
+        message.extend_from_slice(body);
+        message
+    }
+
+    #[tokio::test]
+    async fn test_database_postgres() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            if request == [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f] {
+                return b"N".to_vec();
+            }
+            if request.first() == Some(&b'X') {
+                return Vec::new();
+            }
+            let mut reply = postgres_message(b'R', &0u32.to_be_bytes());
+            reply.extend(postgres_message(b'S', b"server_encoding\0UTF8\0"));
+            reply.extend(postgres_message(b'S', b"server_version\x0016.2\0"));
+            reply.extend(postgres_message(b'Z', b"I"));
+            reply
+        })
+        .await;
+        let trusting = database::probe(DatabaseKind::PostgreSql, addr, port, wait)
+            .await
+            .unwrap();
+        assert!(trusting.unauthenticated);
+        assert_eq!(trusting.version.as_deref(), Some("16.2"));
+
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            if request == [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f] {
+                return b"S".to_vec();
+            }
+            let mut body = 10u32.to_be_bytes().to_vec();
+            body.extend_from_slice(b"SCRAM-SHA-256\0\0");
+            postgres_message(b'R', &body)
+        })
+        .await;
+        let secured = database::probe(DatabaseKind::PostgreSql, addr, port, wait)
+            .await
+            .unwrap();
+        assert!(!secured.unauthenticated);
+        assert_eq!(secured.details, vec!["TLS", "SCRAM-SHA-256 auth"]);
+    }
+
+    /// BSON document of `(type, name, value)` elements.
+    fn bson_document(elements: &[(u8, &str, Vec<u8>)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (kind, name, value) in elements {
+            body.push(*kind);
+            body.extend_from_slice(name.as_bytes());
+            body.push(0);
+            body.extend_from_slice(value);
+        }
+        body.push(0);
+        let mut document = ((body.len() + 4) as i32).to_le_bytes().to_vec();
+        document.extend_from_slice(&body);
+        document
+    }
+
+    fn bson_string(text: &str) -> Vec<u8> {
+        let mut value = ((text.len() + 1) as i32).to_le_bytes().to_vec();
+        value.extend_from_slice(text.as_bytes());
+        value.push(0);
+        value
+    }
+
+    #[tokio::test]
+    async fn test_database_mssql_and_mongodb() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            assert_eq!(request[0], 0x12);
+            let mut payload = vec![0, 0, 11, 0, 6, 1, 0, 17, 0, 1, 0xff];
+            payload.extend_from_slice(&[15, 0, 0x07, 0xd0, 0, 0, 0]);
+            let mut reply = vec![0x04, 0x01];
+            reply.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+            reply.extend_from_slice(&[0, 0, 1, 0]);
+            reply.extend_from_slice(&payload);
+            reply
+        })
+        .await;
+        let mssql = database::probe(DatabaseKind::MsSql, addr, port, wait)
+            .await
+            .unwrap();
+        assert_eq!(
+            mssql.to_string(),
+            "MSSQL 15.0.2000 (SQL Server 2019, encryption off)"
+        );
+
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            assert_eq!(request[12..16], 2013i32.to_le_bytes());
+            let document = if contains(request, b"hello") {
+                bson_document(&[
+                    (0x08, "isWritablePrimary", vec![1]),
+                    (0x10, "maxWireVersion", 21i32.to_le_bytes().to_vec()),
+                    (0x01, "ok", 1.0f64.to_le_bytes().to_vec()),
+                ])
+            } else if contains(request, b"buildInfo") {
+                bson_document(&[
+                    (0x02, "version", bson_string("7.0.5")),
+                    (0x04, "versionArray", bson_document(&[])),
+                    (0x01, "ok", 1.0f64.to_le_bytes().to_vec()),
+                ])
+            } else {
+                bson_document(&[
+                    (0x01, "ok", 0.0f64.to_le_bytes().to_vec()),
+                    (0x02, "errmsg", bson_string("requires authentication")),
+                    (0x10, "code", 13i32.to_le_bytes().to_vec()),
+                ])
+            };
+            let mut reply = ((21 + document.len()) as i32).to_le_bytes().to_vec();
+            reply.extend_from_slice(&[0; 4]);
+            reply.extend_from_slice(&request[4..8]);
+            reply.extend_from_slice(&2013i32.to_le_bytes());
+            reply.extend_from_slice(&[0; 5]);
+            reply.extend_from_slice(&document);
+            reply
+        })
+        .await;
+        let mongodb = database::probe(DatabaseKind::MongoDb, addr, port, wait)
+            .await
+            .unwrap();
+        assert_eq!(mongodb.version.as_deref(), Some("7.0.5"));
+        assert!(!mongodb.unauthenticated);
+        assert_eq!(mongodb.details, vec!["auth required"]);
+
+        // Before 3.6 there is no OP_MSG: the server hangs up on it and only
+        // answers OP_QUERY on admin.$cmd with an OP_REPLY.
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            if request[12..16] != 2004i32.to_le_bytes() {
+                return Vec::new();
+            }
+            assert!(contains(request, b"admin.$cmd\0"));
+            assert!(!contains(request, b"$db"));
+            let document = if contains(request, b"isMaster") {
+                bson_document(&[
+                    (0x08, "ismaster", vec![1]),
+                    (0x01, "ok", 1.0f64.to_le_bytes().to_vec()),
+                ])
+            } else if contains(request, b"buildInfo") {
+                bson_document(&[
+                    (0x02, "version", bson_string("3.4.24")),
+                    (0x01, "ok", 1.0f64.to_le_bytes().to_vec()),
+                ])
+            } else {
+                bson_document(&[
+                    (0x04, "databases", bson_document(&[])),
+                    (0x10, "totalSize", (-1i32).to_le_bytes().to_vec()),
+                    (0x01, "ok", 1.0f64.to_le_bytes().to_vec()),
+                ])
+            };
+            let mut reply = ((36 + document.len()) as i32).to_le_bytes().to_vec();
+            reply.extend_from_slice(&[0; 4]);
+            reply.extend_from_slice(&request[4..8]);
+            reply.extend_from_slice(&1i32.to_le_bytes());
+            reply.extend_from_slice(&[0; 16]);
+            reply.extend_from_slice(&1i32.to_le_bytes());
+            reply.extend_from_slice(&document);
+            reply
+        })
+        .await;
+        let legacy = database::probe(DatabaseKind::MongoDb, addr, port, wait)
+            .await
+            .unwrap();
+        assert_eq!(legacy.to_string(), "MongoDB 3.4.24 (NO AUTHENTICATION)");
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_smtp_and_ftp() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let port = spawn_scripted_server(b"220 mail.example ESMTP Postfix\r\n".to_vec(), |request| {
+            if request.starts_with(b"EHLO") {
+                b"250-mail.example\r\n250-PIPELINING\r\n250-STARTTLS\r\n250 AUTH PLAIN LOGIN\r\n"
+                    .to_vec()
+            } else {
+                b"221 Bye\r\n".to_vec()
+            }
+        })
+        .await;
+        let smtp = capabilities::inspect(TextProtocol::Smtp, addr, port, false, wait)
+            .await
+            .unwrap();
+        assert_eq!(smtp.greeting, "mail.example ESMTP Postfix");
+        assert_eq!(
+            smtp.capabilities,
+            vec!["PIPELINING", "STARTTLS", "AUTH PLAIN LOGIN"]
+        );
+        assert!(smtp.starttls);
+        assert!(smtp.plaintext_auth);
+        assert_eq!(smtp.to_string(), "SMTP (STARTTLS, plaintext auth)");
+
+        use std::sync::atomic::{AtomicBool, Ordering};
+        static USER_SENT: AtomicBool = AtomicBool::new(false);
+
+        let port = spawn_scripted_server(b"220-Welcome\r\n220 FTP ready\r\n".to_vec(), |request| {
+            let reply: &[u8] = if request.starts_with(b"FEAT") {
+                b"211-Features:\r\n MDTM\r\n AUTH TLS\r\n UTF8\r\n211 End\r\n"
+            } else if request.starts_with(b"USER") {
+                USER_SENT.store(true, Ordering::Relaxed);
+                b"331 Please specify the password.\r\n"
+            } else if request.starts_with(b"PASS") {
+                b"230 Login successful.\r\n"
+            } else {
+                b"221 Goodbye.\r\n"
+            };
+            reply.to_vec()
+        })
+        .await;
+        let ftp = capabilities::inspect(TextProtocol::Ftp, addr, port, false, wait)
+            .await
+            .unwrap();
+        assert_eq!(ftp.capabilities, vec!["MDTM", "AUTH TLS", "UTF8"]);
+        assert!(ftp.starttls);
+        // Only a login attempt would tell, and none is made.
+        assert!(!ftp.plaintext_auth);
+        assert_eq!(ftp.anonymous_login, None);
+        assert!(!USER_SENT.load(Ordering::Relaxed));
+
+        let ftp = capabilities::inspect(TextProtocol::Ftp, addr, port, true, wait)
+            .await
+            .unwrap();
+        assert!(USER_SENT.load(Ordering::Relaxed));
+        assert!(ftp.plaintext_auth);
+        assert_eq!(ftp.anonymous_login, Some(true));
+        assert_eq!(
+            ftp.to_string(),
+            "FTP (STARTTLS, plaintext auth, anonymous login)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_bounds() {
+        use tokio::io::AsyncWriteExt;
+
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_millis(500);
+
+        // Without AUTH, credentials can only be sent in the clear.
+        let port = spawn_scripted_server(b"220 FTP ready\r\n".to_vec(), |request| {
+            if request.starts_with(b"FEAT") {
+                b"211-Features:\r\n MDTM\r\n211 End\r\n".to_vec()
+            } else {
+                b"221 Goodbye.\r\n".to_vec()
+            }
+        })
+        .await;
+        let ftp = capabilities::inspect(TextProtocol::Ftp, addr, port, false, wait)
+            .await
+            .unwrap();
+        assert!(!ftp.starttls && ftp.plaintext_auth);
+
+        let mut greeting = b"220 ".to_vec();
+        greeting.extend(std::iter::repeat_n(b'x', 5000));
+        greeting.extend_from_slice(b"\r\n");
+        let port = spawn_scripted_server(greeting, |_| Vec::new()).await;
+        let ftp = capabilities::inspect(TextProtocol::Ftp, addr, port, false, wait).await;
+        assert!(ftp.is_none_or(|ftp| ftp.greeting.len() < 1024));
+
+        // A reply that never ends, one line at a time, costs one timeout.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    while stream.write_all(b"220-still here\r\n").await.is_ok() {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                });
+            }
+        });
+        let started = std::time::Instant::now();
+        assert!(
+            capabilities::inspect(TextProtocol::Ftp, addr, port, false, wait)
+                .await
+                .is_none()
+        );
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_pop3_and_imap() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let port = spawn_scripted_server(b"+OK Dovecot ready.\r\n".to_vec(), |request| {
+            if request.starts_with(b"CAPA") {
+                b"+OK\r\nTOP\r\nUIDL\r\nSTLS\r\nSASL\r\n.\r\n".to_vec()
+            } else {
+                b"+OK Logging out.\r\n".to_vec()
+            }
+        })
+        .await;
+        let pop3 = capabilities::inspect(TextProtocol::Pop3, addr, port, false, wait)
+            .await
+            .unwrap();
+        assert!(pop3.starttls);
+        assert!(!pop3.plaintext_auth);
+
+        let port = spawn_scripted_server(
+            b"* OK [CAPABILITY IMAP4rev1] Dovecot ready.\r\n".to_vec(),
+            |request| {
+                if request.starts_with(b"a1 CAPABILITY") {
+                    b"* CAPABILITY IMAP4rev1 LITERAL+ STARTTLS LOGINDISABLED\r\na1 OK done\r\n"
+                        .to_vec()
+                } else {
+                    b"* BYE\r\na2 OK\r\n".to_vec()
+                }
+            },
+        )
+        .await;
+        let imap = capabilities::inspect(TextProtocol::Imap, addr, port, false, wait)
+            .await
+            .unwrap();
+        assert_eq!(imap.greeting, "[CAPABILITY IMAP4rev1] Dovecot ready.");
+        assert!(imap.starttls);
+        assert!(!imap.plaintext_auth);
+        assert_eq!(imap.to_string(), "IMAP (STARTTLS)");
+
+        // An SMTP greeting is not a POP3 one.
+        let port =
+            spawn_scripted_server(b"220 mail.example ESMTP\r\n".to_vec(), |_| Vec::new()).await;
+        assert!(
+            capabilities::inspect(TextProtocol::Pop3, addr, port, false, wait)
+                .await
+                .is_none()
+        );
+    }
+
+    /// Connection Confirm carrying a negotiation response or failure.
+    fn rdp_confirm(kind: u8, value: u32) -> Vec<u8> {
+        let mut reply = vec![0x03, 0x00, 0x00, 19, 14, 0xd0, 0, 0, 0x12, 0x34, 0];
+        reply.extend_from_slice(&[kind, 0, 8, 0]);
+        reply.extend_from_slice(&value.to_le_bytes());
+        reply
+    }
+
+    #[tokio::test]
+    async fn test_remote_desktop_rdp() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        // Requires NLA: refuses anything without HYBRID.
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            let requested = u32::from_le_bytes(request[15..19].try_into().unwrap());
+            if requested & 0x2 != 0 {
+                rdp_confirm(0x02, 0x2)
+            } else {
+                rdp_confirm(0x03, 5)
+            }
+        })
+        .await;
+        let secured = remote_desktop::rdp(addr, port, wait).await.unwrap();
+        assert_eq!(
+            secured,
+            RemoteDesktop::Rdp {
+                selected: RdpSecurity::Nla,
+                accepted: vec![RdpSecurity::Nla],
+                nla_required: true,
+            }
+        );
+        assert!(!secured.is_weak());
+        assert_eq!(secured.to_string(), "RDP NLA");
+
+        // Takes whatever it is offered.
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            let requested = u32::from_le_bytes(request[15..19].try_into().unwrap());
+            rdp_confirm(0x02, requested & 0x1)
+        })
+        .await;
+        let open = remote_desktop::rdp(addr, port, wait).await.unwrap();
+        assert!(open.is_weak());
+        assert_eq!(
+            open.to_string(),
+            "RDP TLS (NLA not required: RDP, TLS, NLA)"
+        );
+
+        let port = spawn_scripted_server(b"SSH-2.0-OpenSSH_9.6\r\n".to_vec(), |_| Vec::new()).await;
+        assert!(remote_desktop::rdp(addr, port, wait).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_remote_desktop_vnc() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let port = spawn_scripted_server(b"RFB 003.008\n".to_vec(), |request| {
+            assert_eq!(request, b"RFB 003.008\n");
+            vec![2, 1, 2]
+        })
+        .await;
+        let vnc = remote_desktop::vnc(addr, port, wait).await.unwrap();
+        assert!(vnc.is_weak());
+        assert_eq!(
+            vnc.to_string(),
+            "VNC 3.8 [None, VNC Authentication] NO AUTHENTICATION"
+        );
+
+        let port = spawn_scripted_server(b"RFB 003.003\n".to_vec(), |_| vec![0, 0, 0, 2]).await;
+        let vnc = remote_desktop::vnc(addr, port, wait).await.unwrap();
+        assert!(!vnc.is_weak());
+        assert_eq!(vnc.to_string(), "VNC 3.3 [VNC Authentication]");
+    }
+
+    fn ipp_attribute(body: &mut Vec<u8>, tag: u8, name: &str, value: &[u8]) {
+        body.push(tag);
+        body.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        body.extend_from_slice(name.as_bytes());
+        body.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        body.extend_from_slice(value);
+    }
+
+    fn ipp_response() -> Vec<u8> {
+        let mut body = vec![0x02, 0x00, 0x00, 0x00, 0, 0, 0, 1, 0x01];
+        ipp_attribute(&mut body, 0x47, "attributes-charset", b"utf-8");
+        body.push(0x04);
+        ipp_attribute(
+            &mut body,
+            0x41,
+            "printer-make-and-model",
+            b"HP LaserJet M404dn",
+        );
+        ipp_attribute(&mut body, 0x23, "printer-state", &3i32.to_be_bytes());
+        ipp_attribute(&mut body, 0x41, "printer-state-message", b"Ready");
+        ipp_attribute(&mut body, 0x41, "", b"Toner low");
+        ipp_attribute(
+            &mut body,
+            0x41,
+            "printer-device-id",
+            b"MFG:HP;MDL:HP LaserJet M404dn;CMD:PJL,PCL;SN:PHB8K12345;",
+        );
+        body.push(0x03);
+
+        let mut response = format!(
+            "HTTP/1.0 200 OK\r\nContent-Type: application/ipp\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(&body);
+        response
+    }
+
+    #[test]
+    fn test_printer_ipp_parsing() {
+        let response = ipp_response();
+        let start = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let (status, attributes) = printer::parse_ipp(&response[start + 4..]).unwrap();
+        assert_eq!(status, 0);
+        assert_eq!(attributes.get("printer-state"), Some(&IppValue::Integer(3)));
+        // Additional values keep the first one.
+        assert_eq!(
+            attributes.get("printer-state-message"),
+            Some(&IppValue::Text("Ready".to_string()))
+        );
+        assert!(printer::parse_ipp(&[0x02, 0x00]).is_none());
+
+        let device_id = "MFG:HP;MDL:LaserJet;SERN: ABC123 ;";
+        assert_eq!(
+            printer::device_id_field(device_id, &["SN", "SERN"]),
+            Some("ABC123".to_string())
+        );
+        assert_eq!(printer::device_id_field(device_id, &["CMD"]), None);
+    }
+
+    #[tokio::test]
+    async fn test_printer_ipp() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        // Only the CUPS style path answers, so the first one falls through.
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            if request.starts_with(b"POST / HTTP/1.0\r\n") {
+                assert!(contains(request, b"Content-Type: application/ipp"));
+                assert!(contains(request, b"printer-make-and-model"));
+                ipp_response()
+            } else {
+                b"HTTP/1.0 404 Not Found\r\n\r\n".to_vec()
+            }
+        })
+        .await;
+        let info = printer::ipp(addr, port, wait).await.unwrap();
+        assert_eq!(info.protocol, PrinterProtocol::Ipp);
+        assert_eq!(info.serial.as_deref(), Some("PHB8K12345"));
+        assert_eq!(
+            info.to_string(),
+            "IPP HP LaserJet M404dn (idle: Ready) SN PHB8K12345"
+        );
+
+        let port = spawn_scripted_server(Vec::new(), |_| {
+            b"HTTP/1.0 200 OK\r\n\r\n<html></html>".to_vec()
+        })
+        .await;
+        assert!(printer::ipp(addr, port, wait).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_printer_pjl_and_lpd() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            assert!(request.starts_with(b"\x1b%-12345X@PJL\r\n"));
+            b"@PJL INFO ID\r\n\"HP LaserJet 4250\"\r\n\x0c@PJL INFO STATUS\r\nCODE=10001\r\nDISPLAY=\"Ready\"\r\nONLINE=TRUE\r\n\x0c".to_vec()
+        })
+        .await;
+        let info = printer::pjl(addr, port, wait).await.unwrap();
+        assert_eq!(info.to_string(), "PJL HP LaserJet 4250 (Ready)");
+
+        // A raw port that does not speak PJL is no printer we know.
+        let port = spawn_scripted_server(b"220 ready\r\n".to_vec(), |_| Vec::new()).await;
+        assert!(printer::pjl(addr, port, wait).await.is_none());
+
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            assert_eq!(request, b"\x03lp\n");
+            b"lp is ready\nno entries\n".to_vec()
+        })
+        .await;
+        let info = printer::lpd(addr, port, wait).await.unwrap();
+        assert_eq!(info.to_string(), "LPD (lp is ready)");
+    }
+
+    fn modbus_identification(request: &[u8]) -> Vec<u8> {
+        // The regular category continues from object 3 on a second request.
+        let objects: &[(u8, &[u8])] = if request[10] == 0 {
+            &[
+                (0, b"Schneider Electric"),
+                (1, b"BMX P34 2020"),
+                (2, b"v2.70"),
+            ]
+        } else {
+            &[(4, b"Modicon M340"), (5, b"BMX P34")]
+        };
+        let more_follows = request[9] == 2 && request[10] == 0;
+        let mut pdu = vec![0x2b, 0x0e, request[9], 0x81];
+        pdu.extend_from_slice(&[if more_follows { 0xff } else { 0 }, 3, objects.len() as u8]);
+        for (id, value) in objects {
+            pdu.extend_from_slice(&[*id, value.len() as u8]);
+            pdu.extend_from_slice(value);
+        }
+        let mut reply = request[..4].to_vec();
+        reply.extend_from_slice(&(pdu.len() as u16 + 1).to_be_bytes());
+        reply.push(request[6]);
+        reply.extend_from_slice(&pdu);
+        reply
+    }
+
+    #[tokio::test]
+    async fn test_industrial_modbus() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let port = spawn_scripted_server(Vec::new(), modbus_identification).await;
+        let basic = industrial::modbus(addr, port, false, wait).await.unwrap();
+        assert!(basic.identification);
+        assert_eq!(basic.product_name, None);
+        assert_eq!(
+            basic.to_string(),
+            "Modbus Schneider Electric BMX P34 2020 v2.70"
+        );
+
+        let extended = industrial::modbus(addr, port, true, wait).await.unwrap();
+        assert_eq!(extended.revision.as_deref(), Some("v2.70"));
+        assert_eq!(extended.model_name.as_deref(), Some("BMX P34"));
+        assert_eq!(
+            extended.to_string(),
+            "Modbus Schneider Electric Modicon M340 BMX P34 BMX P34 2020 v2.70"
+        );
+
+        // Illegal function: Modbus, but no identification to read.
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            let mut reply = request[..4].to_vec();
+            reply.extend_from_slice(&[0, 3, request[6], 0xab, 0x01]);
+            reply
+        })
+        .await;
+        let refused = industrial::modbus(addr, port, false, wait).await.unwrap();
+        assert!(!refused.identification);
+        assert_eq!(refused.to_string(), "Modbus (no device identification)");
+    }
+
+    #[tokio::test]
+    async fn test_industrial_bacnet() {
+        // Routed I-Am carrying the source network of the device.
+        let routed = [
+            0x81, 0x0b, 0x00, 0x18, 0x01, 0x08, 0x00, 0x05, 0x01, 0x0a, 0x10, 0x00, 0xc4, 0x02,
+            0x00, 0x04, 0xd2, 0x22, 0x05, 0xc4, 0x91, 0x03, 0x21, 0x05,
+        ];
+        assert_eq!(
+            industrial::parse_i_am(&routed),
+            Some(BacnetInfo {
+                device_instance: 1234,
+                vendor_id: 5,
+                max_apdu: 1476,
+            })
+        );
+        assert_eq!(industrial::parse_i_am(&routed[..20]), None);
+
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 1500];
+            let (len, peer) = socket.recv_from(&mut buffer).await.unwrap();
+            assert_eq!(
+                &buffer[..len],
+                [0x81, 0x0a, 0x00, 0x08, 0x01, 0x00, 0x10, 0x08]
+            );
+            // Something else first, which the client skips.
+            socket.send_to(b"noise", peer).await.unwrap();
+            let i_am = [
+                0x81, 0x0a, 0x00, 0x14, 0x01, 0x00, 0x10, 0x00, 0xc4, 0x02, 0x01, 0x86, 0xa0, 0x21,
+                0xf0, 0x91, 0x00, 0x22, 0x01, 0x04,
+            ];
+            socket.send_to(&i_am, peer).await.unwrap();
+        });
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let info = industrial::bacnet(addr, port, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(info.to_string(), "BACnet device 100000 (vendor 260)");
+    }
+
+    #[tokio::test]
+    async fn test_industrial_mqtt() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            if request[0] == 0xe0 {
+                return Vec::new();
+            }
+            assert!(contains(request, b"\x00\x04MQTT\x04"));
+            vec![0x20, 0x02, 0x00, 0x00]
+        })
+        .await;
+        let open = industrial::mqtt(addr, port, wait).await.unwrap();
+        let info = IndustrialInfo {
+            mqtt: Some(open),
+            ..Default::default()
+        };
+        assert!(info.has_anonymous_mqtt());
+        assert_eq!(info.to_string(), "MQTT ANONYMOUS ACCESS");
+
+        let port = spawn_scripted_server(Vec::new(), |_| vec![0x20, 0x02, 0x00, 0x05]).await;
+        let closed = industrial::mqtt(addr, port, wait).await.unwrap();
+        assert!(!closed.anonymous);
+        assert_eq!(closed.to_string(), "MQTT requires authentication");
+
+        let port = spawn_scripted_server(b"220 ready\r\n".to_vec(), |_| Vec::new()).await;
+        assert!(industrial::mqtt(addr, port, wait).await.is_none());
+    }
+
+    fn ldap_attribute(name: &str, values: &[&str]) -> Vec<u8> {
+        let values: Vec<u8> = values
+            .iter()
+            .flat_map(|v| ber(0x04, v.as_bytes()))
+            .collect();
+        ber(
+            0x30,
+            &[ber(0x04, name.as_bytes()), ber(0x31, &values)].concat(),
+        )
+    }
+
+    fn ldap_message(operation: u8, content: &[u8]) -> Vec<u8> {
+        ber(0x30, &[ber(0x02, &[1]), ber(operation, content)].concat())
+    }
+
+    fn ldap_root_dse(request: &[u8]) -> Vec<u8> {
+        let (_, message, _) = ber_read(request);
+        let (_, _, rest) = ber_read(message);
+        assert_eq!(rest[0], 0x63);
+        assert!(contains(request, b"supportedSASLMechanisms"));
+
+        let attributes = [
+            ldap_attribute("defaultNamingContext", &["DC=corp,DC=example,DC=com"]),
+            ldap_attribute("dnsHostName", &["dc01.corp.example.com"]),
+            ldap_attribute("supportedLDAPVersion", &["3", "2"]),
+            ldap_attribute("supportedSASLMechanisms", &["GSSAPI", "GSS-SPNEGO"]),
+            ldap_attribute("domainFunctionality", &["7"]),
+        ]
+        .concat();
+        let entry = [ber(0x04, b""), ber(0x30, &attributes)].concat();
+        let done = [ber(0x0a, &[0]), ber(0x04, b""), ber(0x04, b"")].concat();
+        [ldap_message(0x64, &entry), ldap_message(0x65, &done)].concat()
+    }
+
+    #[tokio::test]
+    async fn test_ldap_root_dse() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let port = spawn_scripted_server(Vec::new(), ldap_root_dse).await;
+        let info = ldap::root_dse(addr, port, false, wait).await.unwrap();
+        assert!(info.anonymous_read);
+        assert!(info.is_domain_controller());
+        assert_eq!(info.domain().as_deref(), Some("corp.example.com"));
+        assert_eq!(info.sasl_mechanisms, vec!["GSSAPI", "GSS-SPNEGO"]);
+        assert_eq!(
+            info.to_string(),
+            "LDAP domain controller, dc01.corp.example.com, domain corp.example.com, level 2016 (v3, v2)"
+        );
+
+        // insufficientAccessRights without any entry.
+        let port = spawn_scripted_server(Vec::new(), |_| {
+            let done = [ber(0x0a, &[50]), ber(0x04, b""), ber(0x04, b"")].concat();
+            ldap_message(0x65, &done)
+        })
+        .await;
+        let refused = ldap::root_dse(addr, port, false, wait).await.unwrap();
+        assert_eq!(
+            refused,
+            LdapInfo {
+                tls: false,
+                anonymous_read: false,
+                ..Default::default()
+            }
+        );
+        assert_eq!(refused.to_string(), "LDAP (anonymous rootDSE refused)");
+
+        let port = spawn_scripted_server(b"SSH-2.0-OpenSSH_9.6\r\n".to_vec(), |_| Vec::new()).await;
+        assert!(ldap::root_dse(addr, port, false, wait).await.is_none());
+    }
+
+    fn dns_server_answer(question: &dns::Question) -> StubAnswers {
+        match (question.name.as_str(), question.class) {
+            ("version.bind", dns::CLASS_CH) => Some(vec![(dns::TYPE_TXT, b"\x079.18.24".to_vec())]),
+            ("example.com", dns::CLASS_IN) => Some(vec![(dns::TYPE_A, vec![93, 184, 215, 14])]),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dns_server_probe() {
+        let wait = Duration::from_secs(1);
+
+        let server = spawn_dns_stub(dns_server_answer).await;
+        let info = dns_server::probe(
+            server.ip(),
+            server.port(),
+            false,
+            Some("corp.example"),
+            wait,
+        )
+        .await
+        .unwrap();
+        assert!(info.udp && !info.tcp);
+        assert!(info.is_exposed());
+        // Transfers need TCP, which is not open here.
+        assert_eq!(info.zone_transfer, None);
+        assert_eq!(info.to_string(), "DNS 9.18.24 [UDP]; OPEN RESOLVER");
+
+        // Hides its version and does not recurse for us.
+        let server = spawn_dns_stub(|_| None).await;
+        let info = dns_server::probe(server.ip(), server.port(), false, None, wait)
+            .await
+            .unwrap();
+        assert_eq!(
+            info,
+            DnsServerInfo {
+                udp: true,
+                ..Default::default()
+            }
+        );
+        assert!(!info.is_exposed());
+        assert_eq!(info.to_string(), "DNS [UDP]");
+
+        let silent = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = silent.local_addr().unwrap().port();
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_millis(200);
+        assert!(
+            dns_server::probe(addr, port, false, None, wait)
+                .await
+                .is_none()
+        );
+    }
+
+    /// TCP framed reply to `request`, one message per record group.
+    fn axfr_reply(request: &[u8], rcode: u16, messages: &[&[(u16, &[u8])]]) -> Vec<u8> {
+        let query = &request[2..];
+        let mut reply = Vec::new();
+        for records in messages {
+            let mut message = query.to_vec();
+            message[2..4].copy_from_slice(&(0x8400 | rcode).to_be_bytes());
+            message[6..8].copy_from_slice(&(records.len() as u16).to_be_bytes());
+            for (record_type, data) in records.iter() {
+                message.extend_from_slice(&[0xc0, 0x0c]);
+                message.extend_from_slice(&record_type.to_be_bytes());
+                message.extend_from_slice(&[0, 1, 0, 0, 0x0e, 0x10]);
+                message.extend_from_slice(&(data.len() as u16).to_be_bytes());
+                message.extend_from_slice(data);
+            }
+            reply.extend_from_slice(&(message.len() as u16).to_be_bytes());
+            reply.extend_from_slice(&message);
+        }
+        reply
+    }
+
+    #[tokio::test]
+    async fn test_dns_server_zone_transfer() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_secs(1);
+
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            let query = dns::Message::parse(&request[2..]).unwrap();
+            assert_eq!(query.questions[0].name, "corp.example");
+            assert_eq!(query.questions[0].record_type, dns::TYPE_AXFR);
+            let soa: &[u8] = &[0; 22];
+            axfr_reply(
+                request,
+                0,
+                &[
+                    &[(dns::TYPE_SOA, soa), (dns::TYPE_A, &[10, 0, 0, 1])],
+                    &[(dns::TYPE_A, &[10, 0, 0, 2]), (dns::TYPE_SOA, soa)],
+                ],
+            )
+        })
+        .await;
+        let transfer = dns_server::zone_transfer(addr, port, "corp.example", wait)
+            .await
+            .unwrap();
+        assert_eq!(
+            transfer,
+            ZoneTransfer {
+                zone: "corp.example".to_string(),
+                allowed: true,
+                records: 4,
+            }
+        );
+
+        // Stalls after the first message, as a large zone would past the
+        // timeout; what arrived still shows the transfer is allowed.
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            let soa: &[u8] = &[0; 22];
+            axfr_reply(
+                request,
+                0,
+                &[&[(dns::TYPE_SOA, soa), (dns::TYPE_A, &[10, 0, 0, 1])]],
+            )
+        })
+        .await;
+        let partial = dns_server::zone_transfer(addr, port, "corp.example", wait)
+            .await
+            .unwrap();
+        assert!(partial.allowed);
+        assert_eq!(partial.records, 2);
+
+        // Keeps sending, each message well within the timeout, but the
+        // transfer as a whole still ends with it.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = [0u8; 512];
+                    let Ok(len) = stream.read(&mut request).await else {
+                        return;
+                    };
+                    let message =
+                        axfr_reply(&request[..len], 0, &[&[(dns::TYPE_A, &[10, 0, 0, 1])]]);
+                    while stream.write_all(&message).await.is_ok() {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                });
+            }
+        });
+        let started = std::time::Instant::now();
+        let endless =
+            dns_server::zone_transfer(addr, port, "corp.example", Duration::from_millis(500))
+                .await
+                .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(endless.allowed);
+        assert!(endless.records >= 3);
+
+        let port =
+            spawn_scripted_server(Vec::new(), |request| axfr_reply(request, 5, &[&[]])).await;
+        let refused = dns_server::zone_transfer(addr, port, "corp.example", wait)
+            .await
+            .unwrap();
+        assert!(!refused.allowed);
+        let info = DnsServerInfo {
+            tcp: true,
+            zone_transfer: Some(refused),
+            ..Default::default()
+        };
+        assert_eq!(info.to_string(), "DNS [TCP]; AXFR corp.example refused");
+    }
+
+    #[test]
+    fn test_probe_database_builtin() {
+        let database = ProbeDatabase::builtin();
+        assert_eq!(database.skipped, 0);
+        let names: Vec<&str> = database
+            .probes_for(ProbeProtocol::Tcp, 8080)
+            .iter()
+            .map(|probe| probe.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["NULL", "GetRequest"]);
+        let udp = database.probes_for(ProbeProtocol::Udp, 53);
+        assert_eq!(udp[0].name, "DNSVersionBindReq");
+
+        let null = &database.probes[0];
+        let ssh = null
+            .identify(b"SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13\r\n")
+            .unwrap();
+        assert_eq!(ssh.to_string(), "ssh OpenSSH 9.6p1 (protocol 2.0)");
+        assert_eq!(ssh.cpe, vec!["cpe:/a:openbsd:openssh:9.6p1"]);
+
+        let mariadb = null
+            .identify(b"\x59\x00\x00\x00\x0a5.5.5-10.11.6-MariaDB-0+deb12u1\x00\x08\x00\x00\x00")
+            .unwrap();
+        assert_eq!(mariadb.to_string(), "mysql MariaDB 10.11.6");
+
+        let soft = null.identify(b"SSH-1.99-Cisco-1.25\r\n").unwrap();
+        assert!(soft.soft);
+        assert_eq!(soft.to_string(), "ssh");
+        assert!(null.identify(b"\x00\x01garbage").is_none());
+
+        let http = &database.probes[1];
+        let nginx = http
+            .identify(b"HTTP/1.1 200 OK\r\nDate: Sun, 18 Oct 2026 10:00:00 GMT\r\nServer: nginx/1.24.0\r\n\r\n")
+            .unwrap();
+        assert_eq!(nginx.to_string(), "http nginx 1.24.0");
+        let apache = http
+            .identify(b"HTTP/1.0 403 Forbidden\r\nServer: Apache/2.4.62 (Debian)\r\n\r\n")
+            .unwrap();
+        assert_eq!(apache.info.as_deref(), Some("Debian"));
+
+        assert_eq!(
+            service_fingerprint::guess_service(ProbeProtocol::Tcp, 3389),
+            Some("ms-wbt-server")
+        );
+        assert_eq!(
+            service_fingerprint::guess_service(ProbeProtocol::Udp, 3389),
+            None
+        );
+    }
+
+    #[test]
+    fn test_probe_database_user_file() {
+        let extra = ProbeDatabase::parse(
+            "# Local additions\n\
+             Exclude 53,T:9100-9102,9200,U:30000\n\
+             Probe TCP NULL q||\n\
+             match ssh m|^SSH-2\\.0-Gateway (\\w+)\\r\\n|i p/Acme gateway sshd/ v/$P(1)/ d/router/ cpe:/h:acme:gw:$1/a\n\
+             match ssh m|^(?<=x)SSH| p/lookbehind/\n\
+             \n\
+             Probe TCP Hello q|HELLO\\r\\n\\x00|\n\
+             ports 7000-7010,7100\n\
+             rarity 5\n\
+             softmatch custom m|^OK|\n",
+        )
+        .unwrap();
+        assert_eq!(extra.skipped, 1);
+        assert_eq!(extra.probes[1].payload, b"HELLO\r\n\x00");
+        assert!(extra.probes[1].lists_port(7005) && extra.probes[1].lists_port(7100));
+        assert!(!extra.probes[1].lists_port(7050));
+        assert!(extra.excludes(ProbeProtocol::Tcp, 53) && extra.excludes(ProbeProtocol::Udp, 53));
+        assert!(
+            extra.excludes(ProbeProtocol::Tcp, 9101) && extra.excludes(ProbeProtocol::Tcp, 9200)
+        );
+        assert!(!extra.excludes(ProbeProtocol::Udp, 9101));
+        assert!(extra.excludes(ProbeProtocol::Udp, 30000));
+        assert!(!extra.excludes(ProbeProtocol::Tcp, 30000));
+
+        let mut database = ProbeDatabase::builtin();
+        let builtin_probes = database.probes.len();
+        database.extend(extra);
+        assert_eq!(database.probes.len(), builtin_probes + 1);
+        assert_eq!(database.skipped, 1);
+        assert!(database.excludes(ProbeProtocol::Tcp, 9107));
+        assert!(database.excludes(ProbeProtocol::Udp, 30000));
+
+        // Rules added to a known probe are tried before the built-in ones.
+        let found = database.probes[0]
+            .identify(b"ssh-2.0-gateway V3\r\n")
+            .unwrap();
+        assert_eq!(
+            found,
+            ServiceFingerprint {
+                service: "ssh".to_string(),
+                product: Some("Acme gateway sshd".to_string()),
+                version: Some("V3".to_string()),
+                device_type: Some("router".to_string()),
+                cpe: vec!["cpe:/h:acme:gw:V3".to_string()],
+                ..Default::default()
+            }
+        );
+
+        assert!(ProbeDatabase::parse("match ssh m|^SSH|").is_err());
+        assert!(ProbeDatabase::parse("Probe TCP NULL q||\nports 22-ssh").is_err());
+        assert!(ProbeDatabase::parse("Probe SCTP INIT q||").is_err());
+        assert!(ProbeDatabase::parse("Exclude T:printer").is_err());
+        assert!(ProbeDatabase::parse("Probe TCP NULL q||\nmatch ssh m|unterminated").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_service_fingerprint_probes() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let listen_wait = Duration::from_millis(300);
+        let wait = Duration::from_secs(1);
+        let database = ProbeDatabase::builtin();
+
+        let port =
+            spawn_scripted_server(b"SSH-2.0-dropbear_2022.83\r\n".to_vec(), |_| Vec::new()).await;
+        let ssh = service_fingerprint::fingerprint(
+            &database,
+            ProbeProtocol::Tcp,
+            addr,
+            port,
+            listen_wait,
+            wait,
+        )
+        .await
+        .unwrap();
+        assert_eq!(ssh.to_string(), "ssh Dropbear sshd 2022.83 (protocol 2.0)");
+
+        // Silent until asked, so the NULL probe comes back empty.
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            assert_eq!(request, b"GET / HTTP/1.0\r\n\r\n");
+            b"HTTP/1.1 404 Not Found\r\nServer: Microsoft-IIS/10.0\r\n\r\n".to_vec()
+        })
+        .await;
+        let iis = service_fingerprint::fingerprint(
+            &database,
+            ProbeProtocol::Tcp,
+            addr,
+            port,
+            listen_wait,
+            wait,
+        )
+        .await
+        .unwrap();
+        assert_eq!(iis.os.as_deref(), Some("Windows"));
+        assert_eq!(iis.to_string(), "http Microsoft IIS httpd 10.0");
+
+        let port = spawn_scripted_server(Vec::new(), |_| b"\x00\x00".to_vec()).await;
+        assert!(
+            service_fingerprint::fingerprint(
+                &database,
+                ProbeProtocol::Tcp,
+                addr,
+                port,
+                listen_wait,
+                wait,
+            )
+            .await
+            .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_service_fingerprint_fetcher_skips_excluded_ports() {
+        use std::sync::atomic::Ordering;
+
+        let (port, printed) = spawn_print_port().await;
+        let config = Arc::new(ScannerConfig {
+            port_string: port.to_string(),
+            banner_timeout_ms: 200,
+            service_timeout_ms: 200,
+            ..Default::default()
+        });
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config.clone());
+        PortsFetcher::new(config.clone())
+            .scan(&mut subject)
+            .await
+            .unwrap();
+
+        let summary = ServiceFingerprintFetcher::new(config)
+            .scan(&mut subject)
+            .await
+            .unwrap();
+        assert_eq!(summary, "[n/a]");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(printed.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_favicon_hash() {
+        assert_eq!(web_tech::murmur3_32(b"foo", 0) as i32, -156908512);
+        assert_eq!(web_tech::murmur3_32(b"hello", 0) as i32, 613153351);
+        assert_eq!(web_tech::murmur3_32(b"", 0), 0);
+
+        // Shodan hashes the base64 text with a newline after every 76 columns.
+        let icon = vec![0xa5u8; 100];
+        let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &icon);
+        let wrapped = format!("{}\n{}\n", &encoded[..76], &encoded[76..]);
+        assert_eq!(
+            web_tech::favicon_hash(&icon),
+            web_tech::murmur3_32(wrapped.as_bytes(), 0) as i32
+        );
+    }
+
+    #[test]
+    fn test_web_page_parse() {
+        let page = WebPage::parse(
+            b"HTTP/1.1 200 OK\r\n\
+              Transfer-Encoding: chunked\r\n\
+              Set-Cookie: grafana_session=abc123; Path=/; HttpOnly\r\n\
+              Set-Cookie: lang=en\r\n\r\n\
+              1a\r\n<meta name=\"Generator\" con\r\n\
+              25\r\ntent='WordPress 6.4.2'><meta charset>\r\n\
+              0\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(page.status, 200);
+        assert_eq!(page.header("transfer-encoding"), Some("chunked"));
+        assert_eq!(
+            page.cookies().collect::<Vec<_>>(),
+            vec![("grafana_session", "abc123"), ("lang", "en")]
+        );
+        assert_eq!(
+            page.meta(),
+            vec![("generator".to_string(), "WordPress 6.4.2".to_string())]
+        );
+
+        let rules = WebTechRules::builtin();
+        assert_eq!(rules.skipped, 0);
+        let found = rules.detect(&[page], None);
+        let names: Vec<String> = found.iter().map(|t| t.to_string()).collect();
+        assert_eq!(names, vec!["Grafana", "WordPress 6.4.2"]);
+
+        assert!(WebPage::parse(b"SSH-2.0-OpenSSH_9.6\r\n\r\n").is_none());
+    }
+
+    #[test]
+    fn test_web_tech_rules_user_file() {
+        let extra = WebTechRules::parse(
+            r#"{
+                "Acme Router": {
+                    "category": "Router",
+                    "headers": { "server": "AcmeOS/(\\S+)" },
+                    "favicon": [-1234]
+                },
+                "nginx": { "body": ["(?<=x)nginx"] }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(extra.len(), 2);
+        assert_eq!(extra.skipped, 1);
+
+        let mut rules = WebTechRules::builtin();
+        let builtin = rules.len();
+        rules.extend(extra);
+        assert_eq!(rules.len(), builtin + 1);
+
+        let page = WebPage::parse(b"HTTP/1.1 200 OK\r\nServer: nginx/1.24.0\r\n\r\n").unwrap();
+        // The user's nginx rule replaced the built-in one.
+        assert!(rules.detect(std::slice::from_ref(&page), None).is_empty());
+        assert_eq!(
+            rules.detect(&[page], Some(-1234)),
+            vec![WebTechnology {
+                name: "Acme Router".to_string(),
+                category: Some("Router".to_string()),
+                version: None,
+            }]
+        );
+
+        assert!(WebTechRules::parse("[]").is_err());
+        assert!(WebTechRules::parse(r#"{"X": {"title": "x"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_web_tech_redirect_path() {
+        let v4 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let v6: IpAddr = "::1".parse().unwrap();
+        let redirect = web_tech::redirect_path;
+
+        assert_eq!(
+            redirect("/login", v4, 8080, false).as_deref(),
+            Some("/login")
+        );
+        assert_eq!(redirect("//evil.example/", v4, 80, false), None);
+        assert_eq!(
+            redirect("http://10.0.0.1/a", v4, 80, false).as_deref(),
+            Some("/a")
+        );
+        assert_eq!(
+            redirect("HTTP://10.0.0.1:8080", v4, 8080, false).as_deref(),
+            Some("/")
+        );
+        assert_eq!(redirect("http://10.0.0.1/a", v4, 8080, false), None);
+        assert_eq!(redirect("http://10.0.0.2/a", v4, 80, false), None);
+        // An upgrade to https is another server, even on the same port.
+        assert_eq!(redirect("https://10.0.0.1/", v4, 80, false), None);
+        assert_eq!(redirect("https://10.0.0.1:80/", v4, 80, false), None);
+        assert_eq!(
+            redirect("https://10.0.0.1/", v4, 443, true).as_deref(),
+            Some("/")
+        );
+
+        assert_eq!(
+            redirect("http://[::1]/x", v6, 80, false).as_deref(),
+            Some("/x")
+        );
+        assert_eq!(
+            redirect("https://[::1]:8443/x", v6, 8443, true).as_deref(),
+            Some("/x")
+        );
+        assert_eq!(redirect("http://[::1]:81/x", v6, 80, false), None);
+        assert_eq!(redirect("http://[::1/x", v6, 80, false), None);
+    }
+
+    #[tokio::test]
+    async fn test_web_tech_identify() {
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let wait = Duration::from_millis(500);
+        let rules = WebTechRules::builtin();
+
+        let port = spawn_scripted_server(Vec::new(), |request| {
+            if request.starts_with(b"GET / ") {
+                b"HTTP/1.1 302 Found\r\nLocation: /login?from=%2F\r\n\r\n".to_vec()
+            } else if request.starts_with(b"GET /login") {
+                b"HTTP/1.1 200 OK\r\nX-Jenkins: 2.440.1\r\nServer: Jetty(10.0.18)\r\n\r\n\
+                  <html><title>Sign in [Jenkins]</title></html>"
+                    .to_vec()
+            } else {
+                b"HTTP/1.1 404 Not Found\r\n\r\n".to_vec()
+            }
+        })
+        .await;
+        let found = web_tech::identify(&rules, addr, port, false, true, wait)
+            .await
+            .unwrap();
+        let names: Vec<String> = found.iter().map(|t| t.to_string()).collect();
+        assert_eq!(names, vec!["Jenkins 2.440.1", "Jetty 10.0.18"]);
+        assert_eq!(found[0].category.as_deref(), Some("CI server"));
+
+        let port = spawn_scripted_server(Vec::new(), |_| b"\x00\x00".to_vec()).await;
+        assert!(
+            web_tech::identify(&rules, addr, port, false, false, wait)
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_banner_fetcher_without_open_ports() {
+        let config = Arc::new(ScannerConfig::default());
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config.clone());
+
+        let result = BannerFetcher::new(config).scan(&mut subject).await.unwrap();
+        assert_eq!(result, "[n/a]");
+        assert!(subject.get_parameter::<Banners>(BANNERS).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ping_fetcher() {
+        let config = Arc::new(ScannerConfig {
+            ping_count: 1,
+            ping_timeout_ms: 100,
+            adapt_port_timeout: false,
+            ..Default::default()
+        });
+        let fetcher = PingFetcher::new(config.clone());
+
+        assert_eq!(fetcher.id(), "ping");
+        assert_eq!(fetcher.name(), "Ping");
+
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let mut subject = ScanningSubject::new(addr, config);
+
+        if fetcher.scan(&mut subject).await.is_ok() {
+            let stats = subject.get_parameter::<PingStats>(PING_STATS).unwrap();
+            assert_eq!(stats.sent, 1);
+        }
     }
 
     #[tokio::test]